@@ -1,42 +1,156 @@
-use common::{self, quantities::Position, request::Request, response::Response}; // Добавили Response
-use std::env;
-use tokio::io::{self, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader}; // Убрали AsyncBufReadExt, так как читаем не строки
-use tokio::net::TcpStream;
+use common::{
+    self,
+    calibration::ServoCalibration,
+    mechanics_config::JointLimits,
+    motion::{
+        MotionProfile, Timing,
+        jog::ContinuousJog,
+        spline::{MAX_SPLINE_POINTS, SplinePoints},
+    },
+    quantities::{Axis, Quantity},
+    request::{
+        Command, MAX_BATCH_LEN, MAX_TELEMETRY_RATE_HZ, MAX_WATCHDOG_TIMEOUT_MS,
+        MIN_TELEMETRY_RATE_HZ, MIN_WATCHDOG_TIMEOUT_MS, MotionControl, Request, Waypoint,
+        WaypointBatch,
+    },
+    response::{MotionState, Response},
+    session::Tagged,
+    units::{Radians, RadiansPerSecond, Seconds},
+};
+use sdk::Robot;
+use std::{env, time::Duration};
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::select;
 use tokio::sync::mpsc;
 
-/// Читает длину в формате LEB128 (Varint) из асинхронного потока.
-async fn read_varint<R: tokio::io::AsyncRead + Unpin>(reader: &mut R) -> io::Result<usize> {
-    let mut res = 0;
-    let mut shift = 0;
-    loop {
-        let byte = reader.read_u8().await?;
-        res |= ((byte & 0x7F) as usize) << shift;
-        if byte & 0x80 == 0 {
-            break;
-        }
-        shift += 7;
+/// Время непрерывного движения по умолчанию, мс.
+const DEFAULT_JOG_TIMEOUT_MS: u32 = 2000;
+
+/// Разбирает четыре значения по осям (rotation, shoulder, forearm, claw).
+fn parse_axes<Unit: From<f32>>(args: &[&str]) -> Option<Quantity<Unit>> {
+    let coords: Vec<f32> = args.iter().filter_map(|s| s.parse().ok()).collect();
+    if args.len() != 4 || coords.len() != 4 {
+        return None;
     }
-    Ok(res)
+    Some(Quantity {
+        rotation: coords[0].into(),
+        shoulder: coords[1].into(),
+        forearm: coords[2].into(),
+        claw: coords[3].into(),
+    })
 }
 
-/// Записывает длину в формате LEB128 (Varint)
-async fn write_varint<W: tokio::io::AsyncWrite + Unpin>(
-    writer: &mut W,
-    mut val: usize,
-) -> io::Result<()> {
-    loop {
-        let mut byte = (val & 0x7F) as u8;
-        val >>= 7;
-        if val != 0 {
-            byte |= 0x80;
+/// Разбирает название профиля скорости.
+fn parse_profile(name: &str) -> Option<MotionProfile> {
+    match name {
+        "constant" => Some(MotionProfile::ConstantVelocity),
+        "trapezoidal" => Some(MotionProfile::Trapezoidal),
+        "scurve" => Some(MotionProfile::SCurve),
+        _ => None,
+    }
+}
+
+/// Разбирает название оси.
+fn parse_axis(name: &str) -> Option<Axis> {
+    match name {
+        "rotation" => Some(Axis::Rotation),
+        "shoulder" => Some(Axis::Shoulder),
+        "forearm" => Some(Axis::Forearm),
+        "claw" => Some(Axis::Claw),
+        _ => None,
+    }
+}
+
+/// Разбирает темп перемещения: `t=<секунды>` или `v=<доля скорости>`.
+fn parse_timing(arg: &str) -> Option<Timing> {
+    if let Some(duration) = arg.strip_prefix("t=") {
+        return Some(Timing::Duration(Seconds::new(duration.parse().ok()?)));
+    }
+    Some(Timing::SpeedScale(arg.strip_prefix("v=")?.parse().ok()?))
+}
+
+/// Разбирает калибровку сервопривода:
+/// <min_us> <max_us> <offset> <inverted 0|1> <min_angle> <max_angle>.
+fn parse_calibration(args: &[&str]) -> Option<ServoCalibration> {
+    let [min_us, max_us, offset, inverted, min_angle, max_angle] = args else {
+        return None;
+    };
+    Some(ServoCalibration {
+        min_pulse_us: min_us.parse().ok()?,
+        max_pulse_us: max_us.parse().ok()?,
+        offset: Radians::new(offset.parse().ok()?),
+        inverted: match *inverted {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+        },
+        min_angle: Radians::new(min_angle.parse().ok()?),
+        max_angle: Radians::new(max_angle.parse().ok()?),
+    })
+}
+
+/// Выводит ответ роборуки в читаемом виде.
+fn print_response(Tagged { id, message }: &Tagged<Response>) {
+    let id = id.map(|id| format!(" #{id}")).unwrap_or_default();
+    match message {
+        Response::PositionAck { free_slots } => {
+            println!("\n[СЕРВЕР{id}] позиция достигнута, свободно в очереди: {free_slots}")
+        }
+        Response::CommandAck => println!("\n[СЕРВЕР{id}] команда выполнена"),
+        Response::MotionCancelled => println!("\n[СЕРВЕР{id}] перемещение отменено"),
+        Response::Calibration(cal) => {
+            println!("\n[СЕРВЕР{id}] калибровка:");
+            for axis in Axis::ALL {
+                let c = cal.get(axis);
+                println!(
+                    "  {axis:?}: {}..{} мкс, смещение {}, инверсия {}, углы {}..{}",
+                    c.min_pulse_us,
+                    c.max_pulse_us,
+                    f32::from(c.offset),
+                    c.inverted,
+                    f32::from(c.min_angle),
+                    f32::from(c.max_angle)
+                );
+            }
         }
-        writer.write_all(&[byte]).await?;
-        if val == 0 {
-            break;
+        Response::Rejected(rejection) => println!("\n[СЕРВЕР{id}] ОТКАЗ: {rejection}"),
+        Response::Telemetry(t) => {
+            // Одна строка на кадр: время, позиция и цель по осям.
+            let (p, g) = (t.position, t.target);
+            println!(
+                "[ТЕЛЕМЕТРИЯ] {:.6} {:.4} {:.4} {:.4} {:.4} | {:.4} {:.4} {:.4} {:.4}",
+                t.timestamp_us as f64 / 1e6,
+                f32::from(p.rotation),
+                f32::from(p.shoulder),
+                f32::from(p.forearm),
+                f32::from(p.claw),
+                f32::from(g.rotation),
+                f32::from(g.shoulder),
+                f32::from(g.forearm),
+                f32::from(g.claw)
+            );
+        }
+        Response::Status(status) => {
+            let state = match status.state {
+                MotionState::Idle => "покой",
+                MotionState::Moving => "движение",
+                MotionState::Paused => "пауза",
+            };
+            println!(
+                "\n[СЕРВЕР{id}] {state}, очередь {}/{}, время работы {:.1} с",
+                status.queued,
+                status.queue_capacity,
+                status.uptime_ms as f64 / 1000.0
+            );
+            for axis in Axis::ALL {
+                println!(
+                    "  {axis:?}: позиция {:.3}, цель {:.3}",
+                    f32::from(*status.position.get(axis)),
+                    f32::from(*status.target.get(axis))
+                );
+            }
         }
     }
-    Ok(())
 }
 
 #[tokio::main]
@@ -48,12 +162,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     let addr = &args[1];
 
-    let stream = TcpStream::connect(addr).await?;
+    let robot = Robot::connect(addr).await?;
     println!("Успешно подключено к {}", addr);
-    println!("Команды: go <rot> <sho> <for> <cla> | exit");
+    println!("Команды: go <rot> <sho> <for> <cla> [profile] [t=<с>|v=<доля>]");
+    println!("         speed <rot> <sho> <for> <cla>");
+    println!("         accel <rot> <sho> <for> <cla> | jerk <rot> <sho> <for> <cla>");
+    println!("         profile <constant|trapezoidal|scurve> | blend <tolerance>");
+    println!("         spline <rot> <sho> <for> <cla> [<rot> <sho> <for> <cla> ...]");
+    println!("         batch <rot> <sho> <for> <cla> [<rot> <sho> <for> <cla> ...]");
+    println!("         cal get | cal <set|test> <axis> <min_us> <max_us> <offset> <inv>");
+    println!("                   <min_angle> <max_angle>");
+    println!("         limits <min: rot sho for cla> <max: rot sho for cla>");
+    println!("         init <rot> <sho> <for> <cla> | wifi <SSID> <PASSWORD> | exit");
+    println!("         status | sub <{MIN_TELEMETRY_RATE_HZ}..{MAX_TELEMETRY_RATE_HZ} Гц> | unsub");
+    println!("         stop | pause | resume | clear");
+    println!("         jog <rot> <sho> <for> <cla> | jogv <axis> <speed> [timeout_ms]");
+    println!("         watchdog <timeout_ms | 0>");
 
-    let (read_half, mut write_half) = tokio::io::split(stream);
-    let mut reader = BufReader::new(read_half);
+    let welcome = robot.welcome();
+    println!(
+        "Прошивка {}, протокол {}, осей: {}, сессия {}, свободно в очереди: {}",
+        welcome.firmware_version.as_str(),
+        welcome.protocol_version,
+        welcome.axes,
+        welcome.session,
+        welcome.free_slots
+    );
+    if welcome.watchdog_tripped {
+        println!(
+            "ВНИМАНИЕ: в прошлой сессии сработал сторожевой таймер, движение было остановлено"
+        );
+    }
     let (tx, mut rx) = mpsc::channel::<String>(100);
 
     // Поток чтения stdin
@@ -71,49 +210,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    // Буфер для десериализации (размер берем из MaxSize если доступно, либо с запасом)
-    let mut response_buf = [0u8; 512];
+    // Период отправки признака жизни, если включен сторожевой таймер.
+    let mut keepalive: Option<tokio::time::Interval> = None;
+
+    let mut responses = robot.responses();
 
     loop {
         select! {
             // Чтение ОТВЕТОВ от сервера (Response)
-            len_res = read_varint(&mut reader) => {
-                match len_res {
-                    Ok(len) => {
-                        if len > response_buf.len() {
-                            eprintln!("Ошибка: слишком большой пакет от сервера ({} байт)", len);
-                            break;
-                        }
-
-                        // Читаем ровно len байт
-                        if let Err(e) = reader.read_exact(&mut response_buf[..len]).await {
-                            eprintln!("Ошибка при чтении тела ответа: {}", e);
-                            break;
-                        }
+            resp = responses.recv() => match resp {
+                Ok(resp) => print_response(&resp),
+                Err(sdk::Error::Lagged(skipped)) => println!("Пропущено ответов: {skipped}"),
+                Err(_) => {
+                    println!("Соединение закрыто сервером.");
+                    break;
+                }
+            },
 
-                        // Десериализация postcard
-                        match common::from_bytes::<Response>(&response_buf[..len]) {
-                            Ok(resp) => {
-                                println!("\n[СЕРВЕР] {:?}", resp);
-                            }
-                            Err(e) => eprintln!("Ошибка десериализации ответа: {:?}", e),
-                        }
-                    }
-                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
-                        println!("Соединение закрыто сервером.");
-                        break;
-                    }
-                    Err(e) => {
-                        eprintln!("Ошибка чтения длины: {}", e);
-                        break;
-                    }
+            // Признак жизни для сторожевого таймера прошивки
+            _ = async { keepalive.as_mut().unwrap().tick().await }, if keepalive.is_some() => {
+                if let Err(e) = robot.send(Request::Keepalive) {
+                    println!("Ошибка отправки признака жизни: {e}");
                 }
             }
 
             // Отправка КОМАНД на сервер
             Some(input) = rx.recv() => {
                 if input.eq_ignore_ascii_case("exit") {
-                    let _ = write_half.shutdown().await;
                     break;
                 }
 
@@ -133,8 +256,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .map_err(|_| "Пароль слишком длинный")?;
                     // 2. Сборка полного конфига клиента
                     let client_cfg = common::wifi_config::ClientConfig {
-                        ssid: ssid_raw.into(),
-                        password: pass_raw.into(),
+                        ssid: ssid_raw,
+                        password: pass_raw,
                         bssid: None,
                         auth_method: common::wifi_config::AuthMethod::Wpa2Personal, // По умолчанию
                         channel: None,
@@ -142,52 +265,275 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     };
 
                     // 3. Упаковка в WifiConfig (AP оставляем дефолтной или None)
-                    let mut wifi_config = common::wifi_config::WifiConfig::default();
-                    wifi_config.client = Some(client_cfg);
+                    let wifi_config = common::wifi_config::WifiConfig {
+                        client: Some(client_cfg),
+                        ..Default::default()
+                    };
 
                     let req = Request::Immediate(common::request::Command::ConfigureWifi(wifi_config));
 
-                    // 4. Сериализация (Postcard)
-                    // Используем буфер 512, так как SSID(32) + PASS(64) + метаданные точно влезут
-                    let bytes = match common::to_vec::<Request, 512>(&req) {
-                        Ok(b) => b,
-                        Err(e) => { println!("Ошибка сериализации: {:?}", e); continue; }
+                    match robot.send(req) {
+                        Ok(id) => println!(">>> [#{id}] Команда UpdateWifi отправлена на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                // --- Настройка механики ---
+                if ["speed ", "accel ", "jerk ", "init "].iter().any(|p| input.starts_with(p)) {
+                    let v: Vec<&str> = input.split_whitespace().collect();
+                    let cmd = match v[0] {
+                        "speed" => parse_axes(&v[1..]).map(Command::SetMaxSpeed),
+                        "accel" => parse_axes(&v[1..]).map(Command::SetMaxAcceleration),
+                        "jerk" => parse_axes(&v[1..]).map(Command::SetMaxJerk),
+                        _ => parse_axes(&v[1..]).map(Command::SetInitPosition),
+                    };
+                    let Some(cmd) = cmd else {
+                        println!("Нужно 4 значения. Пример: {} 1.0 1.5 1.5 3.0", v[0]);
+                        continue;
+                    };
+
+                    match robot.send(Request::Immediate(cmd)) {
+                        Ok(id) => println!(">>> [#{id}] Команда {} отправлена на роборуку", v[0]),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("profile ") {
+                    let Some(profile) = input.split_whitespace().nth(1).and_then(parse_profile) else {
+                        println!("Использование: profile <constant|trapezoidal|scurve>");
+                        continue;
+                    };
+
+                    let req = Request::Immediate(Command::SetMotionProfile(profile));
+                    match robot.send(req) {
+                        Ok(id) => println!(">>> [#{id}] Команда profile отправлена на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("blend ") {
+                    let Some(tolerance) = input.split_whitespace().nth(1).and_then(|v| v.parse::<f32>().ok()) else {
+                        println!("Использование: blend <допуск в радианах>");
+                        continue;
                     };
 
-                    // 5. Отправка пакета: [Length Varint][Body Bytes]
-                    if write_varint(&mut write_half, bytes.len()).await.is_ok() {
-                        let _ = write_half.write_all(&bytes).await;
-                        let _ = write_half.flush().await;
-                        println!(">>> Команда UpdateWifi отправлена на роборуку");
+                    let req = Request::Immediate(Command::SetBlendTolerance(Radians::new(tolerance)));
+                    match robot.send(req) {
+                        Ok(id) => println!(">>> [#{id}] Команда blend отправлена на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("limits ") {
+                    let v: Vec<&str> = input.split_whitespace().collect();
+                    let limits = (v.len() == 9)
+                        .then(|| parse_axes::<Radians>(&v[1..5]).zip(parse_axes::<Radians>(&v[5..9])))
+                        .flatten()
+                        .map(|(min, max)| JointLimits { min, max });
+                    let Some(limits) = limits else {
+                        println!("Нужно 4 нижних и 4 верхних границы.");
+                        println!("Пример: limits 0.0 0.2 0.0 1.0 3.1 2.9 3.1 2.6");
+                        continue;
+                    };
+
+                    let req = Request::Immediate(Command::SetJointLimits(limits));
+                    match robot.send(req) {
+                        Ok(id) => println!(">>> [#{id}] Команда limits отправлена на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if let Some(timeout) = input.strip_prefix("watchdog ") {
+                    let Ok(timeout_ms) = timeout.trim().parse::<u32>() else {
+                        println!("Использование: watchdog <{MIN_WATCHDOG_TIMEOUT_MS}..{MAX_WATCHDOG_TIMEOUT_MS} мс | 0>");
+                        continue;
+                    };
+                    match robot.send(Request::SetWatchdog { timeout_ms }) {
+                        Ok(id) => {
+                            println!(">>> [#{id}] Сторожевой таймер отправлен на роборуку");
+                            // Признак жизни отправляется трижды за время ожидания.
+                            let enabled = (MIN_WATCHDOG_TIMEOUT_MS..=MAX_WATCHDOG_TIMEOUT_MS).contains(&timeout_ms);
+                            keepalive = enabled.then(|| {
+                                tokio::time::interval(Duration::from_millis(timeout_ms as u64 / 3))
+                            });
+                        }
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input == "status" {
+                    match robot.send(Request::GetStatus) {
+                        Ok(id) => println!(">>> [#{id}] Запрос состояния отправлен на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("sub ") || input == "unsub" {
+                    let req = match input.strip_prefix("sub ").map(|r| r.trim().parse()) {
+                        Some(Ok(rate_hz)) => Request::Subscribe { rate_hz },
+                        Some(Err(_)) => {
+                            println!("Использование: sub <частота {MIN_TELEMETRY_RATE_HZ}..{MAX_TELEMETRY_RATE_HZ} Гц>");
+                            continue;
+                        }
+                        None => Request::Unsubscribe,
+                    };
+                    match robot.send(req) {
+                        Ok(id) => println!(">>> [#{id}] Запрос подписки отправлен на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                let control = match input.as_str() {
+                    "stop" => Some(MotionControl::Stop),
+                    "pause" => Some(MotionControl::Pause),
+                    "resume" => Some(MotionControl::Resume),
+                    "clear" => Some(MotionControl::ClearQueue),
+                    _ => None,
+                };
+                if let Some(control) = control {
+                    match robot.send(Request::Control(control)) {
+                        Ok(id) => println!(">>> [#{id}] Команда {input} отправлена на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("cal ") {
+                    let v: Vec<&str> = input.split_whitespace().collect();
+                    let cmd = match v.get(1).copied() {
+                        Some("get") if v.len() == 2 => Some(Command::GetCalibration),
+                        Some(action @ ("set" | "test")) if v.len() == 9 => {
+                            parse_axis(v[2]).zip(parse_calibration(&v[3..])).map(|(axis, cal)| {
+                                if action == "set" {
+                                    Command::SetCalibration(axis, cal)
+                                } else {
+                                    Command::TestCalibration(axis, cal)
+                                }
+                            })
+                        }
+                        _ => None,
+                    };
+                    let Some(cmd) = cmd else {
+                        println!("Использование: cal get");
+                        println!("               cal <set|test> <rotation|shoulder|forearm|claw> <min_us> <max_us> <offset> <inverted 0|1> <min_angle> <max_angle>");
+                        println!("Пример: cal test claw 600 2400 0.1 1 0.0 3.0");
+                        continue;
+                    };
+
+                    match robot.send(Request::Immediate(cmd)) {
+                        Ok(id) => println!(">>> [#{id}] Команда cal {} отправлена на роборуку", v[1]),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("spline ") {
+                    let v: Vec<&str> = input.split_whitespace().skip(1).collect();
+                    let mut points = SplinePoints::new();
+                    let parsed = v.len().is_multiple_of(4)
+                        && v.chunks(4).all(|axes| {
+                            parse_axes::<Radians>(axes).is_some_and(|p| points.push(p).is_ok())
+                        });
+                    if !parsed || points.is_empty() {
+                        println!("Нужно от 1 до {MAX_SPLINE_POINTS} точек по 4 координаты.");
+                        println!("Пример: spline 1.5 1.0 0.5 0.0 1.0 1.2 0.7 0.0");
+                        continue;
+                    }
+
+                    match robot.send(Request::EnqueueSpline(points)) {
+                        Ok(id) => println!(">>> [#{id}] Сплайн отправлен на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
+
+                if input.starts_with("batch ") {
+                    let v: Vec<&str> = input.split_whitespace().skip(1).collect();
+                    let mut batch = WaypointBatch::new();
+                    let parsed = v.len().is_multiple_of(4)
+                        && v.chunks(4).all(|axes| {
+                            parse_axes::<Radians>(axes)
+                                .is_some_and(|p| batch.push(Waypoint::from(p)).is_ok())
+                        });
+                    if !parsed || batch.is_empty() {
+                        println!("Нужно от 1 до {MAX_BATCH_LEN} точек по 4 координаты.");
+                        println!("Пример: batch 1.5 1.0 0.5 0.0 1.0 1.2 0.7 0.0");
+                        continue;
+                    }
+
+                    let len = batch.len();
+                    match robot.send(Request::EnqueueBatch(batch)) {
+                        Ok(id) => println!(">>> [#{id}] Пакет из {len} точек отправлен на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
                     }
                 }
 
                 if input.starts_with("go ") {
                     let v: Vec<&str> = input.split_whitespace().collect();
-                    if v.len() != 5 {
-                        println!("Нужно 4 координаты. Пример: go 1.5 1.0 0.5 0.0");
+                    if !(5..=7).contains(&v.len()) {
+                        println!("Нужно 4 координаты и, при необходимости, профиль и темп.");
+                        println!("Пример: go 1.5 1.0 0.5 0.0 [constant|trapezoidal|scurve] [t=<с>|v=<доля>]");
                         continue;
+                    }
+
+                    let Some(position) = parse_axes::<Radians>(&v[1..5]) else { continue };
+                    let (mut profile, mut timing) = (None, None);
+                    let mut valid = true;
+                    for arg in &v[5..] {
+                        if let Some(t) = parse_timing(arg) {
+                            timing = Some(t);
+                        } else if let Some(p) = parse_profile(arg) {
+                            profile = Some(p);
+                        } else {
+                            println!("Неизвестный профиль или темп: {arg}");
+                            valid = false;
+                        }
+                    }
+                    if !valid {
+                        continue;
+                    }
+
+                    let req = Request::Enqueue(Waypoint { position, profile, timing });
+                    match robot.send(req) {
+                        Ok(id) => println!(">>> [#{id}] Точка отправлена на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
                 }
 
-                    let coords: Vec<f32> = v[1..].iter().filter_map(|s| s.parse().ok()).collect();
-                if coords.len() != 4 { continue; }
+                if input.starts_with("jog ") {
+                    let v: Vec<&str> = input.split_whitespace().collect();
+                    let Some(offset) = parse_axes::<Radians>(&v[1..]) else {
+                        println!("Нужно 4 смещения относительно последней цели.");
+                        println!("Пример: jog 0.1 0.0 -0.05 0.0");
+                        continue;
+                    };
 
-                    let req = Request::Enqueue(Position {
-                        rotation: coords[0].into(),
-                        shoulder: coords[1].into(),
-                        forearm: coords[2].into(),
-                        claw: coords[3].into(),
-                    });
+                    match robot.send(Request::Jog(offset)) {
+                        Ok(id) => println!(">>> [#{id}] Смещение отправлено на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
+                    }
+                }
 
-                    let bytes = match common::to_vec::<Request, 100>(&req) {
-                        Ok(b) => b,
-                        Err(e) => { println!("Ошибка сериализации: {:?}", e); continue; }
+                if input.starts_with("jogv ") {
+                    let v: Vec<&str> = input.split_whitespace().collect();
+                    let timeout_ms = match v.get(3) {
+                        Some(t) => t.parse().ok(),
+                        None => Some(DEFAULT_JOG_TIMEOUT_MS),
+                    };
+                    let jog = (v.len() == 3 || v.len() == 4)
+                        .then(|| parse_axis(v[1]).zip(v[2].parse().ok()).zip(timeout_ms))
+                        .flatten()
+                        .map(|((axis, speed), timeout_ms)| ContinuousJog {
+                            axis,
+                            speed: RadiansPerSecond::new(speed),
+                            timeout_ms,
+                        });
+                    let Some(jog) = jog else {
+                        println!("Нужны ось, скорость в рад/с и, при необходимости, время в мс.");
+                        println!("Пример: jogv shoulder -0.3 [{DEFAULT_JOG_TIMEOUT_MS}]; остановка: stop");
+                        continue;
                     };
 
-                    // Отправка длины + тела
-                    if write_varint(&mut write_half, bytes.len()).await.is_ok() {
-                        let _ = write_half.write_all(&bytes).await;
-                        let _ = write_half.flush().await;
+                    match robot.send(Request::JogContinuous(jog)) {
+                        Ok(id) => println!(">>> [#{id}] Непрерывное движение отправлено на роборуку"),
+                        Err(e) => println!("Ошибка отправки: {e}"),
                     }
                 }
             }
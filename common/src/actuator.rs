@@ -0,0 +1,255 @@
+//! # Actuators
+//!
+//! Приводы сочленений манипулятора.
+//!
+//! Цикл позиционирования задает углы через [`Actuator`] и не зависит от типа
+//! привода: сервопривод с ШИМ, шаговый двигатель или симуляция реализуют один
+//! и тот же трейт. Приводы всех осей собираются в [`Joints`].
+
+use crate::{
+    calibration::{Calibration, ServoCalibration},
+    quantities::{Axis, Position, Quantity},
+    units::Radians,
+};
+use heapless::Deque;
+
+/// Привод одного сочленения.
+pub trait Actuator {
+    /// Задает целевой угол сочленения. Отключенный привод запоминает угол и
+    /// переходит в него при включении.
+    fn set_angle(&mut self, angle: Radians);
+
+    /// Включает привод: он удерживает последний заданный угол.
+    fn enable(&mut self);
+
+    /// Отключает привод: он перестает удерживать положение.
+    fn disable(&mut self);
+
+    /// Задает калибровку привода. Применяется со следующей установки угла.
+    /// Приводы, которым калибровка не нужна, ее игнорируют.
+    fn calibrate(&mut self, calibration: ServoCalibration) {
+        let _ = calibration;
+    }
+
+    /// Измеренный угол сочленения, если привод умеет его сообщать.
+    fn angle(&mut self) -> Option<Radians> {
+        None
+    }
+}
+
+/// Приводы всех осей манипулятора.
+pub type Joints<A> = Quantity<A>;
+
+impl<A: Actuator> Joints<A> {
+    /// Задает углы всех сочленений.
+    pub fn set_pos(&mut self, position: Position) {
+        for axis in Axis::ALL {
+            self.get_mut(axis).set_angle(*position.get(axis));
+        }
+    }
+
+    /// Включает все приводы.
+    pub fn enable(&mut self) {
+        for axis in Axis::ALL {
+            self.get_mut(axis).enable();
+        }
+    }
+
+    /// Отключает все приводы.
+    pub fn disable(&mut self) {
+        for axis in Axis::ALL {
+            self.get_mut(axis).disable();
+        }
+    }
+
+    /// Задает калибровку всех приводов.
+    pub fn calibrate(&mut self, calibration: &Calibration) {
+        for axis in Axis::ALL {
+            self.get_mut(axis).calibrate(*calibration.get(axis));
+        }
+    }
+
+    /// Измеренная позиция, если ее сообщают приводы всех осей.
+    pub fn read_pos(&mut self) -> Option<Position> {
+        Some(Position {
+            rotation: self.rotation.angle()?,
+            shoulder: self.shoulder.angle()?,
+            forearm: self.forearm.angle()?,
+            claw: self.claw.angle()?,
+        })
+    }
+}
+
+/// Команда, полученная приводом.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ActuatorEvent {
+    Angle(Radians),
+    Enabled,
+    Disabled,
+    Calibrated(ServoCalibration),
+}
+
+/// Привод без оборудования для проверок на хосте: запоминает последние `N`
+/// полученных команд и сообщает последний заданный угол.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingActuator<const N: usize> {
+    events: Deque<ActuatorEvent, N>,
+    angle: Option<Radians>,
+    enabled: bool,
+}
+
+impl<const N: usize> RecordingActuator<N> {
+    /// Создает отключенный привод без истории.
+    pub const fn new() -> Self {
+        Self {
+            events: Deque::new(),
+            angle: None,
+            enabled: false,
+        }
+    }
+
+    /// Последние команды в порядке получения.
+    pub fn events(&self) -> impl Iterator<Item = &ActuatorEvent> {
+        self.events.iter()
+    }
+
+    /// Углы, заданные включенному приводу, в порядке получения.
+    pub fn angles(&self) -> impl Iterator<Item = Radians> + '_ {
+        let mut enabled = false;
+        self.events.iter().filter_map(move |event| match *event {
+            ActuatorEvent::Enabled => {
+                enabled = true;
+                None
+            }
+            ActuatorEvent::Disabled => {
+                enabled = false;
+                None
+            }
+            ActuatorEvent::Angle(angle) if enabled => Some(angle),
+            _ => None,
+        })
+    }
+
+    /// Привод включен.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn record(&mut self, event: ActuatorEvent) {
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        let _ = self.events.push_back(event);
+    }
+}
+
+impl<const N: usize> Actuator for RecordingActuator<N> {
+    fn set_angle(&mut self, angle: Radians) {
+        self.angle = Some(angle);
+        self.record(ActuatorEvent::Angle(angle));
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        self.record(ActuatorEvent::Enabled);
+    }
+
+    fn disable(&mut self) {
+        self.enabled = false;
+        self.record(ActuatorEvent::Disabled);
+    }
+
+    fn calibrate(&mut self, calibration: ServoCalibration) {
+        self.record(ActuatorEvent::Calibrated(calibration));
+    }
+
+    fn angle(&mut self) -> Option<Radians> {
+        self.angle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mechanics_config::StartupMechanicsConfig,
+        motion::executor::{Executor, Motion},
+        units::Seconds,
+    };
+
+    type Mock = RecordingActuator<256>;
+
+    fn joints() -> Joints<Mock> {
+        Joints {
+            rotation: Mock::new(),
+            shoulder: Mock::new(),
+            forearm: Mock::new(),
+            claw: Mock::new(),
+        }
+    }
+
+    fn pos(r: f32, s: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(0.0),
+            claw: Radians::new(0.0),
+        }
+    }
+
+    #[test]
+    fn test_joints_route_axes() {
+        let mut joints = joints();
+        assert_eq!(joints.read_pos(), None);
+        joints.set_pos(pos(1.0, 2.0));
+        joints.enable();
+        assert!(joints.claw.is_enabled());
+        assert_eq!(joints.read_pos(), Some(pos(1.0, 2.0)));
+        // Угол, заданный до включения, не считается исполненным.
+        assert_eq!(joints.rotation.angles().count(), 0);
+
+        joints.set_pos(pos(0.5, 0.5));
+        joints.disable();
+        joints.set_pos(pos(0.0, 0.0));
+        let angles: heapless::Vec<_, 4> = joints.shoulder.angles().collect();
+        assert_eq!(angles, [Radians::new(0.5)]);
+    }
+
+    #[test]
+    fn test_recording_keeps_last_events() {
+        let mut actuator = RecordingActuator::<2>::new();
+        for angle in [1.0, 2.0, 3.0] {
+            actuator.set_angle(Radians::new(angle));
+        }
+        let events: heapless::Vec<_, 2> = actuator.events().copied().collect();
+        assert_eq!(
+            events,
+            [
+                ActuatorEvent::Angle(Radians::new(2.0)),
+                ActuatorEvent::Angle(Radians::new(3.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trajectory_drives_actuators() {
+        let mut executor = Executor::new(StartupMechanicsConfig {
+            init_position: pos(0.0, 0.0),
+            ..Default::default()
+        });
+        let mut joints = joints();
+        joints.set_pos(executor.position());
+        joints.enable();
+
+        executor.accept(1u32, Motion::Waypoint(pos(1.0, 0.5).into()));
+        while !executor.is_idle() {
+            joints.set_pos(executor.step(Seconds::new(0.02)));
+        }
+
+        assert_eq!(joints.read_pos(), Some(pos(1.0, 0.5)));
+        // Углы меняются монотонно, без скачков назад.
+        let angles: heapless::Vec<f32, 256> = joints.rotation.angles().map(f32::from).collect();
+        assert!(angles.len() > 2);
+        assert!(angles.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
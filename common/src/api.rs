@@ -0,0 +1,1042 @@
+//! # Network API
+//!
+//! Обработка протокола на стороне прошивки без привязки к сетевому стеку и
+//! среде исполнения.
+//!
+//! Сессия с клиентом начинается рукопожатием, затем входящие запросы
+//! проверяются и передаются в очереди устройства, а ответы, итоги перемещений
+//! и кадры телеметрии отправляются клиенту. Сессия завершается при разрыве
+//! соединения, срабатывании сторожевого таймера или подключении нового
+//! клиента; состояние между сессиями хранит [`Sessions`].
+//!
+//! Прошивка и симулятор предоставляют только ввод-вывод: соединение
+//! ([`Read`], [`Write`]), очереди устройства ([`Device`]), часы ([`Clock`]) и
+//! очереди ответов ([`Replies`], [`Outbox`]).
+
+use crate::{
+    envelope::{self, MAX_REQUEST_ENVELOPE_SIZE, MAX_RESPONSE_ENVELOPE_SIZE, OpenError},
+    framing::{self, FrameDecoder, FrameError, RequestDecoder, frame_size, max_frame_size},
+    handshake::{CommandSet, Hello, MAGIC, PROTOCOL_VERSION, Version, Welcome},
+    mechanics_config::JointLimits,
+    motion::{
+        executor::{Motion, Outcome},
+        positioner::Snapshot,
+    },
+    quantities::Axis,
+    request::{Command, MotionControl, Request},
+    response::{MotionState, Rejection, Response, Status, Telemetry},
+    session::{RequestId, SessionId, Tagged},
+};
+use core::{fmt, future::Future};
+use postcard::experimental::max_size::MaxSize;
+use select::{Either, select};
+use watchdog::Watchdog;
+
+mod select;
+pub mod watchdog;
+
+/// Максимально допустимые размеры пакетов на основе схем данных.
+pub const MAX_READ_PACKET_SIZE: usize = frame_size(MAX_REQUEST_ENVELOPE_SIZE);
+pub const MAX_WRITE_PACKET_SIZE: usize = frame_size(MAX_RESPONSE_ENVELOPE_SIZE);
+
+/// Размер буфера приветствия. Поля, добавленные в `Hello` в будущих версиях,
+/// не нужны для проверки совместимости и отбрасываются.
+const HELLO_BUF_SIZE: usize = Hello::POSTCARD_MAX_SIZE;
+const MAX_WELCOME_PACKET_SIZE: usize = max_frame_size::<Welcome>();
+
+/// Размер порции чтения из соединения.
+const READ_CHUNK_SIZE: usize = 64;
+
+/// Метка запроса: сессия, в которой он получен, и идентификатор, выбранный
+/// клиентом. Сопровождает запрос до подтверждения, чтобы подтверждение не
+/// попало в чужую сессию.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tag {
+    pub session: SessionId,
+    pub id: Option<RequestId>,
+}
+
+/// Очередь заполнена.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct QueueFull;
+
+/// Чтение из соединения с клиентом.
+pub trait Read {
+    type Error: fmt::Debug;
+
+    /// Читает доступные байты в `buf`. Ноль прочитанных байт означает, что
+    /// клиент закрыл соединение.
+    fn read(&mut self, buf: &mut [u8]) -> impl Future<Output = Result<usize, Self::Error>>;
+}
+
+/// Запись в соединение с клиентом.
+pub trait Write {
+    type Error: fmt::Debug;
+
+    /// Записывает кадр целиком и отправляет его.
+    fn write_frame(&mut self, frame: &[u8]) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Часы сетевого API.
+pub trait Clock {
+    /// Время от запуска, мс.
+    fn now_ms(&self) -> u64;
+
+    /// Ожидает `ms` миллисекунд.
+    fn sleep_ms(&self, ms: u64) -> impl Future<Output = ()>;
+}
+
+/// Очереди и состояние устройства, которыми пользуется сетевое API.
+pub trait Device {
+    /// Версия прошивки, которая сообщается клиенту.
+    const VERSION: &'static str;
+
+    /// Действующие программные ограничения углов.
+    fn joint_limits(&self) -> JointLimits;
+
+    /// Ставит перемещение в очередь позиционирования.
+    fn try_enqueue(&self, tag: Tag, motion: Motion) -> Result<(), QueueFull>;
+
+    /// Забирает перемещение из очереди позиционирования, чтобы отменить его.
+    fn try_dequeue(&self) -> Option<Tag>;
+
+    /// Число перемещений в очереди позиционирования.
+    fn queued(&self) -> usize;
+
+    /// Емкость очереди позиционирования.
+    fn queue_capacity(&self) -> usize;
+
+    /// Передает команду конфигуратору.
+    fn try_command(&self, tag: Tag, command: Command) -> Result<(), QueueFull>;
+
+    /// Передает команду управления движением позиционеру.
+    fn try_control(&self, action: MotionControl) -> Result<(), QueueFull>;
+
+    /// Последний снимок состояния движения и его момент, мкс от запуска.
+    fn motion(&self) -> (Snapshot, u64);
+
+    /// Очищает очередь позиционирования.
+    ///
+    /// Очередь команд не очищается: принятая команда настройки исполняется и
+    /// после завершения сессии, а ее подтверждение с идентификатором
+    /// завершенной сессии не попадает к новому клиенту.
+    fn clear(&self);
+
+    /// Выводит сообщение в журнал.
+    fn log(&self, args: fmt::Arguments<'_>);
+}
+
+/// Ответы, которые обработчик входящих запросов передает обработчику
+/// исходящих в пределах сессии.
+pub trait Replies {
+    /// Ставит ответ, сформированный самим API, в очередь отправки.
+    fn reply(&self, reply: Tagged<Response>) -> impl Future<Output = ()>;
+
+    /// Меняет подписку на телеметрию: частота кадров в Гц или `None` при
+    /// отписке.
+    fn subscribe(&self, rate_hz: Option<u8>);
+}
+
+/// Сообщение, ожидающее отправки клиенту.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outgoing {
+    /// Ответ конфигуратора на команду.
+    Command(Tag, Response),
+    /// Итог перемещения от позиционера.
+    Motion(Tag, Outcome),
+    /// Ответ, сформированный самим API.
+    Reply(Tagged<Response>),
+    /// Пора отправить кадр телеметрии.
+    Telemetry,
+}
+
+/// Источник сообщений для клиента.
+pub trait Outbox {
+    /// Ожидает следующее сообщение. `None` означает, что источники закрыты.
+    fn next(&mut self) -> impl Future<Output = Option<Outgoing>>;
+
+    /// Отбрасывает неотправленные сообщения и отменяет подписку на
+    /// телеметрию.
+    fn clear(&mut self);
+}
+
+macro_rules! log {
+    ($device:expr, $($arg:tt)*) => {
+        $device.log(format_args!($($arg)*))
+    };
+}
+
+/// Причина завершения сессии.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum End {
+    /// Клиент отключился или не прошел рукопожатие.
+    Disconnected,
+    /// Сработал сторожевой таймер.
+    WatchdogTripped,
+    /// Клиента вытеснило новое подключение.
+    Displaced,
+}
+
+/// Состояние сетевого API между сессиями.
+///
+/// Одновременно обслуживается один клиент. Каждой сессии назначается новый
+/// идентификатор, поэтому подтверждения запросов завершенной сессии не
+/// попадают к следующему клиенту.
+#[derive(Debug, Default)]
+pub struct Sessions {
+    /// Идентификатор текущей сессии.
+    id: SessionId,
+    /// Сторожевой таймер сработал, и об этом еще не сообщено клиенту.
+    watchdog_tripped: bool,
+}
+
+impl Sessions {
+    pub const fn new() -> Self {
+        Self {
+            id: 0,
+            watchdog_tripped: false,
+        }
+    }
+
+    /// Идентификатор текущей сессии.
+    pub fn id(&self) -> SessionId {
+        self.id
+    }
+
+    /// Сторожевой таймер сработал, и об этом еще не сообщено клиенту.
+    pub fn watchdog_tripped(&self) -> bool {
+        self.watchdog_tripped
+    }
+
+    /// Ведет сессию с клиентом до ее завершения: выполняет рукопожатие, затем
+    /// одновременно обрабатывает входящие запросы и отправляет ответы.
+    ///
+    /// Сторожевой таймер сессии отключен, пока клиент не задаст время
+    /// ожидания. Возвращает [`End::WatchdogTripped`], если кадров не было
+    /// дольше этого времени, иначе [`End::Disconnected`].
+    pub async fn run<D, C, R, W, P, O>(
+        &mut self,
+        device: &D,
+        clock: &C,
+        mut reader: R,
+        mut writer: W,
+        replies: &P,
+        outbox: &mut O,
+    ) -> End
+    where
+        D: Device,
+        C: Clock,
+        R: Read,
+        W: Write,
+        P: Replies,
+        O: Outbox,
+    {
+        let session = Session {
+            device,
+            clock,
+            id: self.id,
+            watchdog: Watchdog::new(),
+        };
+        if !session
+            .handshake(&mut reader, &mut writer, self.watchdog_tripped)
+            .await
+        {
+            return End::Disconnected;
+        }
+        self.watchdog_tripped = false;
+
+        let handles = select(
+            session.send(writer, outbox),
+            session.receive(reader, replies),
+        );
+        match select(handles, session.watchdog.expired(clock)).await {
+            Either::First(_) => End::Disconnected,
+            Either::Second(()) => End::WatchdogTripped,
+        }
+    }
+
+    /// Завершает сессию по причине `end` и начинает новую.
+    ///
+    /// Если клиент отключился или сработал сторожевой таймер, движение
+    /// останавливается: манипулятор не должен двигаться без управления.
+    /// Очередь позиционирования очищается до остановки, чтобы позиционер не
+    /// успел забрать из нее следующее перемещение. О срабатывании таймера
+    /// сообщается следующему клиенту при рукопожатии.
+    pub fn end<D: Device, O: Outbox>(&mut self, end: End, device: &D, outbox: &mut O) {
+        match end {
+            End::Disconnected => log!(device, "TRANSPORT: client disconnected, stopping motion"),
+            End::WatchdogTripped => log!(device, "TRANSPORT: watchdog tripped, stopping motion"),
+            End::Displaced => log!(device, "TRANSPORT: client is displaced by a new one..."),
+        }
+        self.clear(device, outbox);
+        if end != End::Displaced && device.try_control(MotionControl::Stop).is_err() {
+            log!(
+                device,
+                "TRANSPORT ERROR: failed to stop motion: control queue is full"
+            );
+        }
+        if end == End::WatchdogTripped {
+            self.watchdog_tripped = true;
+        }
+    }
+
+    /// Очищает очереди и начинает новую сессию.
+    ///
+    /// Запросы, которые уже исполняются, будут подтверждены с идентификатором
+    /// завершенной сессии, и такие подтверждения не попадут к новому клиенту.
+    pub fn clear<D: Device, O: Outbox>(&mut self, device: &D, outbox: &mut O) {
+        device.clear();
+        outbox.clear();
+        self.id = self.id.wrapping_add(1);
+    }
+}
+
+/// Сессия с одним клиентом.
+struct Session<'a, D, C> {
+    device: &'a D,
+    clock: &'a C,
+    id: SessionId,
+    watchdog: Watchdog,
+}
+
+impl<D: Device, C: Clock> Session<'_, D, C> {
+    /// Выполняет рукопожатие с клиентом: принимает `Hello` и отвечает `Welcome`
+    /// с параметрами прошивки и назначенной сессией.
+    ///
+    /// Ответ отправляется и несовместимому клиенту, чтобы тот мог сообщить о
+    /// причине отказа. Возвращает `true`, если с клиентом можно продолжать обмен.
+    async fn handshake<R: Read, W: Write>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+        watchdog_tripped: bool,
+    ) -> bool {
+        let device = self.device;
+        // Приветствие читается побайтно, чтобы не захватить следующие кадры.
+        let mut decoder = FrameDecoder::<HELLO_BUF_SIZE>::truncating();
+        let mut byte = [0u8; 1];
+        while !decoder.is_ready() {
+            match reader.read(&mut byte).await {
+                Ok(1) => {
+                    decoder.push(&byte);
+                }
+                Ok(_) => {
+                    log!(
+                        device,
+                        "API INPUT ERROR: failed to read hello: connection closed"
+                    );
+                    return false;
+                }
+                Err(err) => {
+                    log!(device, "API INPUT ERROR: failed to read hello: {err:?}");
+                    return false;
+                }
+            }
+        }
+        let hello = match decoder.pull() {
+            Some(Ok(body)) => crate::from_bytes::<Hello>(body).ok(),
+            _ => None,
+        };
+
+        let welcome = Welcome {
+            magic: MAGIC,
+            protocol_version: PROTOCOL_VERSION,
+            firmware_version: Version::try_from(D::VERSION).unwrap_or_default(),
+            session: self.id,
+            axes: Axis::ALL.len() as u8,
+            commands: CommandSet::ALL,
+            free_slots: self.free_slots(),
+            watchdog_tripped,
+        };
+        let mut buf = [0u8; MAX_WELCOME_PACKET_SIZE];
+        let welcome = match framing::encode(&welcome, &mut buf) {
+            Ok(frame) => frame,
+            Err(err) => {
+                log!(
+                    device,
+                    "API OUTPUT ERROR: failed to serialize welcome: {err:?}"
+                );
+                return false;
+            }
+        };
+        if let Err(err) = writer.write_frame(welcome).await {
+            log!(device, "API OUTPUT ERROR: failed to write packet: {err:?}");
+            return false;
+        }
+
+        match hello {
+            Some(hello) if hello.is_compatible() => {
+                log!(
+                    device,
+                    "API INPUT: client {} connected, session {}",
+                    hello.client_version.as_str(),
+                    self.id
+                );
+                true
+            }
+            Some(hello) => {
+                log!(
+                    device,
+                    "API INPUT ERROR: protocol mismatch: client {}, firmware {PROTOCOL_VERSION}",
+                    hello.protocol_version
+                );
+                false
+            }
+            None => {
+                log!(device, "API INPUT ERROR: malformed hello");
+                false
+            }
+        }
+    }
+
+    /// Отправляет клиенту ответы на запросы.
+    ///
+    /// Подтверждения запросов из других сессий отбрасываются. Подтверждение
+    /// перемещения сообщает свободное место в очереди на момент отправки. При
+    /// подписке на телеметрию между ответами с заданной частотой отправляются
+    /// кадры с позицией из снимка позиционера.
+    async fn send<W: Write, O: Outbox>(&self, mut writer: W, outbox: &mut O) {
+        let device = self.device;
+        let mut buf = [0u8; MAX_WRITE_PACKET_SIZE];
+        while let Some(outgoing) = outbox.next().await {
+            let (tag, response) = match outgoing {
+                Outgoing::Command(tag, response) => (tag, response),
+                Outgoing::Motion(tag, outcome) => {
+                    let response = match outcome {
+                        Outcome::Passed => Response::PositionAck {
+                            free_slots: self.free_slots(),
+                        },
+                        Outcome::Cancelled => Response::MotionCancelled,
+                        Outcome::Rejected(rejection) => Response::Rejected(rejection),
+                    };
+                    (tag, response)
+                }
+                Outgoing::Reply(reply) => (self.tag(reply.id), reply.message),
+                Outgoing::Telemetry => {
+                    let (snapshot, timestamp_us) = device.motion();
+                    let telemetry = Telemetry {
+                        timestamp_us,
+                        position: snapshot.position,
+                        target: snapshot.target,
+                    };
+                    (self.tag(None), Response::Telemetry(telemetry))
+                }
+            };
+
+            if tag.session != self.id {
+                log!(
+                    device,
+                    "API OUTPUT: dropping {response:?} from stale session {}",
+                    tag.session
+                );
+                continue;
+            }
+
+            let response = Tagged::new(tag.id, response);
+            let response = match framing::encode(&envelope::seal(&response), &mut buf) {
+                Ok(frame) => frame,
+                Err(err) => {
+                    log!(
+                        device,
+                        "API OUTPUT ERROR: failed to serialize response: {err:?}"
+                    );
+                    break;
+                }
+            };
+
+            if let Err(err) = writer.write_frame(response).await {
+                log!(device, "API OUTPUT ERROR: failed to write packet: {err:?}");
+                break;
+            }
+        }
+    }
+
+    /// Обрабатывает входящие запросы от клиента.
+    ///
+    /// Ответы на запрос несут его идентификатор.
+    ///
+    /// Целевые позиции вне программных ограничений осей отклоняются и в очередь
+    /// не попадают; пакет точек отклоняется целиком. Цель относительного
+    /// перемещения известна только позиционеру, и он проверяет ее сам.
+    /// Некорректные кадры и переполнение очередей также приводят к отказу с
+    /// кодом причины; запросы неизвестных типов — к отказу
+    /// `Rejection::Unsupported`. Соединение разрывается только при ошибках чтения.
+    async fn receive<R: Read, P: Replies>(&self, mut reader: R, replies: &P) {
+        let device = self.device;
+        let mut decoder = RequestDecoder::new();
+        let mut chunk = [0u8; READ_CHUNK_SIZE];
+        // Непринятая декодером часть прочитанной порции.
+        let (mut start, mut end) = (0, 0);
+        loop {
+            if start == end {
+                end = match reader.read(&mut chunk).await {
+                    Ok(0) => {
+                        log!(device, "API INPUT: connection closed by client");
+                        break;
+                    }
+                    Ok(n) => n,
+                    Err(err) => {
+                        log!(device, "API INPUT ERROR: failed to read data: {err:?}");
+                        break;
+                    }
+                };
+                start = 0;
+            }
+            start += decoder.push(&chunk[start..end]);
+            let body = match decoder.pull() {
+                None => continue,
+                Some(Ok(body)) => body,
+                Some(Err(FrameError::TooLarge(len))) => {
+                    // Тело кадра пропущено декодером, граница следующего сохранена.
+                    log!(
+                        device,
+                        "API INPUT ERROR: input packet too large: {len} bytes"
+                    );
+                    self.watchdog.feed(self.clock.now_ms());
+                    let reply = Response::Rejected(Rejection::MalformedFrame);
+                    replies.reply(reply.into()).await;
+                    continue;
+                }
+                Some(Err(FrameError::Overflow)) => {
+                    log!(
+                        device,
+                        "API INPUT ERROR: failed to read packet length: overflow"
+                    );
+                    break;
+                }
+            };
+            self.watchdog.feed(self.clock.now_ms());
+
+            let request = match envelope::open::<Request>(body) {
+                Ok(request) => request,
+                Err(err) => {
+                    log!(device, "API INPUT ERROR: failed to open envelope: {err:?}");
+                    let rejection = match err {
+                        // Запрос нового типа пропускается, соединение сохраняется.
+                        OpenError::Unsupported { .. } => Rejection::Unsupported,
+                        _ => Rejection::MalformedFrame,
+                    };
+                    let reply = Tagged::new(err.id(), Response::Rejected(rejection));
+                    replies.reply(reply).await;
+                    continue;
+                }
+            };
+            self.dispatch(request, replies).await;
+        }
+    }
+
+    /// Исполняет запрос или передает его в очередь исполнителя.
+    ///
+    /// Команды управления движением исполняются в обход очередей. Очередь
+    /// позиционирования очищается здесь же, и каждое удаленное перемещение
+    /// подтверждается отменой.
+    async fn dispatch<P: Replies>(&self, request: Tagged<Request>, replies: &P) {
+        let device = self.device;
+        let Tagged { id, message } = request;
+        let reject = |rejection| Tagged::new(id, Response::Rejected(rejection));
+        let ack = Tagged::new(id, Response::CommandAck);
+
+        if let Err(rejection) = message.validate(&device.joint_limits()) {
+            log!(device, "API INPUT ERROR: request rejected: {rejection:?}");
+            replies.reply(reject(rejection)).await;
+            return;
+        }
+
+        let tag = self.tag(id);
+        let enqueue = |motion| {
+            device
+                .try_enqueue(tag, motion)
+                .err()
+                .map(|QueueFull| Rejection::PositionQueueFull)
+        };
+        let rejection = match message {
+            Request::Enqueue(data) => enqueue(Motion::Waypoint(data)),
+            Request::EnqueueSpline(points) => enqueue(Motion::Spline(points)),
+            Request::Jog(offset) => enqueue(Motion::Jog(offset)),
+            Request::JogContinuous(jog) => enqueue(Motion::JogContinuous(jog)),
+            Request::EnqueueBatch(batch) if batch.len() > self.free_slots() as usize => {
+                Some(Rejection::PositionQueueFull)
+            }
+            Request::EnqueueBatch(batch) => {
+                // Очередь пополняет только этот обработчик, поэтому проверенное
+                // место не займут: пакет встает в очередь целиком.
+                for &waypoint in batch.as_slice() {
+                    let _ = device.try_enqueue(tag, Motion::Waypoint(waypoint));
+                }
+                None
+            }
+            Request::Immediate(command) => device
+                .try_command(tag, command)
+                .err()
+                .map(|QueueFull| Rejection::CommandQueueFull),
+            Request::GetStatus => {
+                let status = self.status();
+                replies
+                    .reply(Tagged::new(id, Response::Status(status)))
+                    .await;
+                None
+            }
+            Request::Subscribe { rate_hz } => {
+                replies.subscribe(Some(rate_hz));
+                replies.reply(ack).await;
+                None
+            }
+            Request::Unsubscribe => {
+                replies.subscribe(None);
+                replies.reply(ack).await;
+                None
+            }
+            Request::Keepalive => None,
+            Request::SetWatchdog { timeout_ms } => {
+                self.watchdog.set_timeout(timeout_ms, self.clock.now_ms());
+                replies.reply(ack).await;
+                None
+            }
+            Request::Control(action) => {
+                if let MotionControl::Stop | MotionControl::ClearQueue = action {
+                    // Очередь может содержать перемещения только текущей сессии:
+                    // при смене клиента она очищается.
+                    while let Some(tag) = device.try_dequeue() {
+                        let reply = Tagged::new(tag.id, Response::MotionCancelled);
+                        replies.reply(reply).await;
+                    }
+                }
+                // Очистку очереди позиционер не исполняет.
+                let forwarded = match action {
+                    MotionControl::ClearQueue => Ok(()),
+                    _ => device.try_control(action),
+                };
+                match forwarded {
+                    Ok(()) => {
+                        replies.reply(ack).await;
+                        None
+                    }
+                    Err(QueueFull) => Some(Rejection::CommandQueueFull),
+                }
+            }
+        };
+
+        if let Some(rejection) = rejection {
+            log!(device, "API INPUT ERROR: request rejected: {rejection:?}");
+            replies.reply(reject(rejection)).await;
+        }
+    }
+
+    /// Собирает состояние манипулятора из снимка позиционера и очереди.
+    fn status(&self) -> Status {
+        let (snapshot, _) = self.device.motion();
+        let queued = self.device.queued();
+        // Точка может быть уже в очереди, но еще не забрана позиционером.
+        let state = if snapshot.paused {
+            MotionState::Paused
+        } else if snapshot.moving || queued > 0 {
+            MotionState::Moving
+        } else {
+            MotionState::Idle
+        };
+        Status {
+            position: snapshot.position,
+            target: snapshot.target,
+            queued: queued as u8,
+            queue_capacity: self.device.queue_capacity() as u8,
+            state,
+            uptime_ms: self.clock.now_ms(),
+        }
+    }
+
+    /// Свободное место в очереди позиционирования.
+    fn free_slots(&self) -> u8 {
+        (self.device.queue_capacity() - self.device.queued()) as u8
+    }
+
+    /// Метка запроса `id` текущей сессии.
+    fn tag(&self, id: Option<RequestId>) -> Tag {
+        Tag {
+            session: self.id,
+            id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{handshake::Hello, quantities::Position, request::Waypoint};
+    use core::{
+        cell::{Cell, RefCell},
+        future::pending,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+    use heapless::{Deque, Vec};
+
+    /// Исполняет задачу, которая не ждет внешних событий.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        for _ in 0..10_000 {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+        panic!("задача не завершилась");
+    }
+
+    #[derive(Default)]
+    struct FakeDevice {
+        queue: RefCell<Deque<(Tag, Motion), 4>>,
+        controls: RefCell<Vec<MotionControl, 4>>,
+    }
+
+    impl Device for FakeDevice {
+        const VERSION: &'static str = "test";
+
+        fn joint_limits(&self) -> JointLimits {
+            JointLimits::default()
+        }
+
+        fn try_enqueue(&self, tag: Tag, motion: Motion) -> Result<(), QueueFull> {
+            self.queue
+                .borrow_mut()
+                .push_back((tag, motion))
+                .map_err(|_| QueueFull)
+        }
+
+        fn try_dequeue(&self) -> Option<Tag> {
+            self.queue.borrow_mut().pop_front().map(|(tag, _)| tag)
+        }
+
+        fn queued(&self) -> usize {
+            self.queue.borrow().len()
+        }
+
+        fn queue_capacity(&self) -> usize {
+            self.queue.borrow().capacity()
+        }
+
+        fn try_command(&self, _tag: Tag, _command: Command) -> Result<(), QueueFull> {
+            Err(QueueFull)
+        }
+
+        fn try_control(&self, action: MotionControl) -> Result<(), QueueFull> {
+            self.controls
+                .borrow_mut()
+                .push(action)
+                .map_err(|_| QueueFull)
+        }
+
+        fn motion(&self) -> (Snapshot, u64) {
+            let snapshot = Snapshot {
+                position: pos(0.0),
+                target: pos(0.0),
+                moving: false,
+                paused: false,
+            };
+            (snapshot, 0)
+        }
+
+        fn clear(&self) {
+            self.queue.borrow_mut().clear();
+        }
+
+        fn log(&self, _args: fmt::Arguments<'_>) {}
+    }
+
+    /// Часы, которые не ждут: ожидание только сдвигает время.
+    #[derive(Default)]
+    struct FakeClock(Cell<u64>);
+
+    impl Clock for FakeClock {
+        fn now_ms(&self) -> u64 {
+            self.0.get()
+        }
+
+        async fn sleep_ms(&self, ms: u64) {
+            self.0.set(self.0.get() + ms);
+        }
+    }
+
+    /// Клиент, который передает заданные байты и затем закрывает соединение
+    /// или замолкает.
+    struct FakeReader<'a> {
+        data: &'a [u8],
+        silent: bool,
+    }
+
+    impl Read for FakeReader<'_> {
+        type Error = ();
+
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, ()> {
+            if self.data.is_empty() && self.silent {
+                pending::<()>().await;
+            }
+            let len = buf.len().min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeWriter(Vec<u8, 1024>);
+
+    impl Write for &mut FakeWriter {
+        type Error = ();
+
+        async fn write_frame(&mut self, frame: &[u8]) -> Result<(), ()> {
+            self.0.extend_from_slice(frame).map_err(|_| ())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeReplies {
+        replies: RefCell<Vec<Tagged<Response>, 16>>,
+        rate_hz: Cell<Option<u8>>,
+    }
+
+    impl Replies for FakeReplies {
+        async fn reply(&self, reply: Tagged<Response>) {
+            self.replies.borrow_mut().push(reply).unwrap();
+        }
+
+        fn subscribe(&self, rate_hz: Option<u8>) {
+            self.rate_hz.set(rate_hz);
+        }
+    }
+
+    /// Очередь исходящих без сообщений.
+    #[derive(Default)]
+    struct FakeOutbox {
+        cleared: bool,
+    }
+
+    impl Outbox for FakeOutbox {
+        async fn next(&mut self) -> Option<Outgoing> {
+            pending().await
+        }
+
+        fn clear(&mut self) {
+            self.cleared = true;
+        }
+    }
+
+    fn pos(r: f32) -> Position {
+        Position {
+            rotation: r.into(),
+            shoulder: 0.0.into(),
+            forearm: 0.0.into(),
+            claw: 0.0.into(),
+        }
+    }
+
+    /// Приветствие и кадры запросов клиента.
+    fn client(hello: Hello, requests: &[Tagged<Request>]) -> Vec<u8, 1024> {
+        let mut bytes = Vec::new();
+        let mut buf = [0u8; MAX_READ_PACKET_SIZE];
+        let frame = framing::encode(&hello, &mut buf).unwrap();
+        bytes.extend_from_slice(frame).unwrap();
+        for request in requests {
+            let frame = framing::encode(&envelope::seal(request), &mut buf).unwrap();
+            bytes.extend_from_slice(frame).unwrap();
+        }
+        bytes
+    }
+
+    fn welcome(written: &[u8]) -> Welcome {
+        let mut decoder = FrameDecoder::<MAX_WELCOME_PACKET_SIZE>::new();
+        decoder.push(written);
+        crate::from_bytes(decoder.pull().unwrap().unwrap()).unwrap()
+    }
+
+    struct Fixture {
+        device: FakeDevice,
+        clock: FakeClock,
+        writer: FakeWriter,
+        replies: FakeReplies,
+        outbox: FakeOutbox,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                device: FakeDevice::default(),
+                clock: FakeClock::default(),
+                writer: FakeWriter::default(),
+                replies: FakeReplies::default(),
+                outbox: FakeOutbox::default(),
+            }
+        }
+
+        fn run(&mut self, sessions: &mut Sessions, input: &[u8], silent: bool) -> End {
+            self.writer.0.clear();
+            let reader = FakeReader {
+                data: input,
+                silent,
+            };
+            block_on(sessions.run(
+                &self.device,
+                &self.clock,
+                reader,
+                &mut self.writer,
+                &self.replies,
+                &mut self.outbox,
+            ))
+        }
+
+        fn replies(&self) -> Vec<Tagged<Response>, 16> {
+            self.replies.replies.borrow().clone()
+        }
+    }
+
+    fn hello() -> Hello {
+        Hello::new("test".try_into().unwrap())
+    }
+
+    #[test]
+    fn test_session_enqueues_and_reports_status() {
+        let mut fixture = Fixture::new();
+        let mut sessions = Sessions::new();
+        let input = client(
+            hello(),
+            &[
+                Tagged::new(Some(1), Request::Enqueue(Waypoint::from(pos(0.5)))),
+                Tagged::new(Some(2), Request::GetStatus),
+            ],
+        );
+
+        let end = fixture.run(&mut sessions, &input, false);
+
+        assert_eq!(end, End::Disconnected);
+        let welcome = welcome(&fixture.writer.0);
+        assert_eq!(welcome.session, 0);
+        assert_eq!(welcome.free_slots, 4);
+        assert!(!welcome.watchdog_tripped);
+        let queue = fixture.device.queue.borrow();
+        assert_eq!(queue.len(), 1);
+        assert_eq!(
+            queue.front().unwrap().0,
+            Tag {
+                session: 0,
+                id: Some(1)
+            }
+        );
+        let replies = fixture.replies();
+        let [Tagged { id, message }] = replies.as_slice() else {
+            panic!("ожидался один ответ: {replies:?}");
+        };
+        assert_eq!(*id, Some(2));
+        let Response::Status(status) = message else {
+            panic!("ожидалось состояние: {message:?}");
+        };
+        assert_eq!(status.queued, 1);
+        assert_eq!(status.state, MotionState::Moving);
+    }
+
+    #[test]
+    fn test_out_of_limits_target_is_rejected() {
+        let mut fixture = Fixture::new();
+        let input = client(
+            hello(),
+            &[Tagged::new(
+                Some(3),
+                Request::Enqueue(Waypoint::from(pos(100.0))),
+            )],
+        );
+
+        fixture.run(&mut Sessions::new(), &input, false);
+
+        assert!(fixture.device.queue.borrow().is_empty());
+        let replies = fixture.replies();
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].id, Some(3));
+        assert!(matches!(replies[0].message, Response::Rejected(_)));
+    }
+
+    #[test]
+    fn test_stop_cancels_queued_motions() {
+        let mut fixture = Fixture::new();
+        for id in [10, 11] {
+            let tag = Tag {
+                session: 0,
+                id: Some(id),
+            };
+            let motion = Motion::Waypoint(pos(0.1).into());
+            fixture.device.try_enqueue(tag, motion).unwrap();
+        }
+        let input = client(
+            hello(),
+            &[Tagged::new(Some(12), Request::Control(MotionControl::Stop))],
+        );
+
+        fixture.run(&mut Sessions::new(), &input, false);
+
+        assert!(fixture.device.queue.borrow().is_empty());
+        assert_eq!(*fixture.device.controls.borrow(), [MotionControl::Stop]);
+        assert_eq!(
+            fixture.replies(),
+            [
+                Tagged::new(Some(10), Response::MotionCancelled),
+                Tagged::new(Some(11), Response::MotionCancelled),
+                Tagged::new(Some(12), Response::CommandAck),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_incompatible_client_gets_welcome_only() {
+        let mut fixture = Fixture::new();
+        let hello = Hello {
+            protocol_version: PROTOCOL_VERSION + 1,
+            ..hello()
+        };
+        let input = client(hello, &[Tagged::new(Some(1), Request::GetStatus)]);
+
+        let end = fixture.run(&mut Sessions::new(), &input, false);
+
+        assert_eq!(end, End::Disconnected);
+        assert_eq!(
+            welcome(&fixture.writer.0).protocol_version,
+            PROTOCOL_VERSION
+        );
+        assert!(fixture.replies().is_empty());
+    }
+
+    #[test]
+    fn test_watchdog_trip_stops_motion_and_is_reported_once() {
+        let mut fixture = Fixture::new();
+        let mut sessions = Sessions::new();
+        let input = client(
+            hello(),
+            &[Tagged::new(None, Request::SetWatchdog { timeout_ms: 200 })],
+        );
+
+        let end = fixture.run(&mut sessions, &input, true);
+        assert_eq!(end, End::WatchdogTripped);
+        assert!(fixture.clock.now_ms() >= 200);
+
+        sessions.end(end, &fixture.device, &mut fixture.outbox);
+        assert_eq!(sessions.id(), 1);
+        assert!(sessions.watchdog_tripped());
+        assert!(fixture.outbox.cleared);
+        assert_eq!(*fixture.device.controls.borrow(), [MotionControl::Stop]);
+
+        let input = client(hello(), &[]);
+        let end = fixture.run(&mut sessions, &input, false);
+        assert_eq!(end, End::Disconnected);
+        let welcome = welcome(&fixture.writer.0);
+        assert_eq!(welcome.session, 1);
+        assert!(welcome.watchdog_tripped);
+        assert!(!sessions.watchdog_tripped());
+    }
+
+    #[test]
+    fn test_displaced_session_does_not_stop_motion() {
+        let fixture = &mut Fixture::new();
+        let mut sessions = Sessions::new();
+        sessions.end(End::Displaced, &fixture.device, &mut fixture.outbox);
+
+        assert_eq!(sessions.id(), 1);
+        assert!(!sessions.watchdog_tripped());
+        assert!(fixture.device.controls.borrow().is_empty());
+    }
+}
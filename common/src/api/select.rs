@@ -0,0 +1,29 @@
+//! Ожидание первой из двух задач без привязки к среде исполнения.
+
+use core::{
+    future::{Future, poll_fn},
+    pin::pin,
+    task::Poll,
+};
+
+/// Результат задачи, завершившейся первой.
+pub enum Either<A, B> {
+    First(A),
+    Second(B),
+}
+
+/// Ожидает завершения первой из задач `a` и `b`; вторая отменяется.
+pub async fn select<A: Future, B: Future>(a: A, b: B) -> Either<A::Output, B::Output> {
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = a.as_mut().poll(cx) {
+            return Poll::Ready(Either::First(output));
+        }
+        if let Poll::Ready(output) = b.as_mut().poll(cx) {
+            return Poll::Ready(Either::Second(output));
+        }
+        Poll::Pending
+    })
+    .await
+}
@@ -0,0 +1,88 @@
+//! Сторожевой таймер сессии.
+
+use super::Clock;
+use core::cell::Cell;
+
+/// Интервал проверки отключенного таймера: клиент может включить его в любой
+/// момент.
+const DISABLED_POLL_MS: u64 = 100;
+
+/// Сторожевой таймер сессии. Обработчик входящих запросов отмечает каждый
+/// полученный кадр, а сессия завершается, когда кадров нет дольше заданного
+/// времени.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    timeout_ms: Cell<Option<u32>>,
+    fed_at_ms: Cell<u64>,
+}
+
+impl Watchdog {
+    /// Создает отключенный таймер.
+    pub const fn new() -> Self {
+        Self {
+            timeout_ms: Cell::new(None),
+            fed_at_ms: Cell::new(0),
+        }
+    }
+
+    /// Отмечает получение кадра в момент `now_ms`.
+    pub fn feed(&self, now_ms: u64) {
+        self.fed_at_ms.set(now_ms);
+    }
+
+    /// Задает время ожидания, отсчитываемое от `now_ms`; ноль отключает
+    /// таймер.
+    pub fn set_timeout(&self, timeout_ms: u32, now_ms: u64) {
+        self.timeout_ms.set((timeout_ms > 0).then_some(timeout_ms));
+        self.feed(now_ms);
+    }
+
+    /// Время до срабатывания в момент `now_ms`, мс; `None`, если таймер
+    /// отключен.
+    pub fn remaining_ms(&self, now_ms: u64) -> Option<u64> {
+        let deadline = self.fed_at_ms.get() + self.timeout_ms.get()? as u64;
+        Some(deadline.saturating_sub(now_ms))
+    }
+
+    /// Завершается, когда кадров нет дольше времени ожидания.
+    pub async fn expired<C: Clock>(&self, clock: &C) {
+        loop {
+            let delay = match self.remaining_ms(clock.now_ms()) {
+                Some(0) => return,
+                Some(remaining) => remaining,
+                None => DISABLED_POLL_MS,
+            };
+            clock.sleep_ms(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disabled_by_default() {
+        let watchdog = Watchdog::new();
+        assert_eq!(watchdog.remaining_ms(1_000_000), None);
+    }
+
+    #[test]
+    fn test_feed_postpones_deadline() {
+        let watchdog = Watchdog::new();
+        watchdog.set_timeout(500, 1_000);
+        assert_eq!(watchdog.remaining_ms(1_200), Some(300));
+
+        watchdog.feed(1_400);
+        assert_eq!(watchdog.remaining_ms(1_600), Some(300));
+        assert_eq!(watchdog.remaining_ms(2_000), Some(0));
+    }
+
+    #[test]
+    fn test_zero_timeout_disables() {
+        let watchdog = Watchdog::new();
+        watchdog.set_timeout(500, 0);
+        watchdog.set_timeout(0, 100);
+        assert_eq!(watchdog.remaining_ms(10_000), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! # Servo Calibration
+//!
+//! Калибровка сервоприводов: пересчет угла сочленения в ширину управляющего
+//! импульса с учетом индивидуальных особенностей привода и его установки.
+
+use crate::{quantities::Quantity, units::Radians};
+use core::f32::consts::PI;
+use postcard::experimental::max_size::MaxSize;
+use serde::{Deserialize, Serialize};
+
+/// Наибольшая допустимая ширина импульса: период ШИМ 50 Гц, мкс.
+const MAX_PULSE_US: u16 = 20_000;
+
+/// Калибровка всех сервоприводов манипулятора.
+pub type Calibration = Quantity<ServoCalibration>;
+
+/// Калибровка одного сервопривода.
+///
+/// Импульсы `min_pulse_us` и `max_pulse_us` соответствуют крайним положениям
+/// качалки `0` и `π`. Угол сочленения сначала ограничивается рабочим диапазоном
+/// `[min_angle, max_angle]`, затем смещается на `offset` и, при обратной
+/// установке привода, отражается.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize, MaxSize)]
+pub struct ServoCalibration {
+    /// Ширина импульса для положения качалки `0`, мкс.
+    pub min_pulse_us: u16,
+
+    /// Ширина импульса для положения качалки `π`, мкс.
+    pub max_pulse_us: u16,
+
+    /// Смещение нуля: угол качалки при нулевом угле сочленения.
+    pub offset: Radians,
+
+    /// Привод установлен в обратном направлении.
+    pub inverted: bool,
+
+    /// Нижняя граница рабочего диапазона угла сочленения.
+    pub min_angle: Radians,
+
+    /// Верхняя граница рабочего диапазона угла сочленения.
+    pub max_angle: Radians,
+}
+
+impl ServoCalibration {
+    /// Параметры согласованы: импульсы возрастают и укладываются в период ШИМ
+    /// 50 Гц, углы конечны и диапазон не пуст.
+    pub fn is_valid(&self) -> bool {
+        let angles = [self.offset, self.min_angle, self.max_angle];
+        self.min_pulse_us < self.max_pulse_us
+            && self.max_pulse_us <= MAX_PULSE_US
+            && angles.iter().all(|a| f32::from(*a).is_finite())
+            && self.min_angle <= self.max_angle
+    }
+
+    /// Ширина импульса в микросекундах для угла сочленения `angle`.
+    pub fn pulse_width_us(&self, angle: Radians) -> f32 {
+        // `clamp` не используется: некорректный диапазон от клиента не должен
+        // приводить к панике.
+        let angle = libm::fmaxf(angle.into(), self.min_angle.into());
+        let angle = libm::fminf(angle, self.max_angle.into());
+
+        let horn = angle + f32::from(self.offset);
+        let horn = if self.inverted { PI - horn } else { horn };
+        // Качалка физически не выходит за пределы [0, π].
+        let horn = horn.clamp(0.0, PI);
+
+        let (min, max) = (self.min_pulse_us as f32, self.max_pulse_us as f32);
+        min + (max - min) * horn / PI
+    }
+}
+
+impl Default for ServoCalibration {
+    /// Стандартный сервопривод: 0.5–2.5 мс на диапазон `[0, π]`.
+    fn default() -> Self {
+        Self {
+            min_pulse_us: 500,
+            max_pulse_us: 2500,
+            offset: Radians::new(0.0),
+            inverted: false,
+            min_angle: Radians::new(0.0),
+            max_angle: Radians::new(PI),
+        }
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            rotation: ServoCalibration::default(),
+            shoulder: ServoCalibration::default(),
+            forearm: ServoCalibration::default(),
+            claw: ServoCalibration::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pulse(cal: &ServoCalibration, angle: f32) -> f32 {
+        cal.pulse_width_us(Radians::new(angle))
+    }
+
+    #[test]
+    fn test_default_is_standard_servo() {
+        let cal = ServoCalibration::default();
+        assert_eq!(pulse(&cal, 0.0), 500.0);
+        assert_eq!(pulse(&cal, PI / 2.0), 1500.0);
+        assert_eq!(pulse(&cal, PI), 2500.0);
+        // За пределами диапазона угол ограничивается.
+        assert_eq!(pulse(&cal, -1.0), 500.0);
+        assert_eq!(pulse(&cal, 4.0), 2500.0);
+    }
+
+    #[test]
+    fn test_pulse_range() {
+        let cal = ServoCalibration {
+            min_pulse_us: 600,
+            max_pulse_us: 2400,
+            ..Default::default()
+        };
+        assert_eq!(pulse(&cal, 0.0), 600.0);
+        assert_eq!(pulse(&cal, PI / 2.0), 1500.0);
+        assert_eq!(pulse(&cal, PI), 2400.0);
+    }
+
+    #[test]
+    fn test_offset_and_inversion() {
+        let cal = ServoCalibration {
+            offset: Radians::new(PI / 4.0),
+            ..Default::default()
+        };
+        assert_eq!(pulse(&cal, 0.0), 1000.0);
+        assert_eq!(pulse(&cal, 3.0 * PI / 4.0), 2500.0);
+
+        let cal = ServoCalibration {
+            inverted: true,
+            ..Default::default()
+        };
+        assert_eq!(pulse(&cal, 0.0), 2500.0);
+        assert_eq!(pulse(&cal, PI), 500.0);
+    }
+
+    #[test]
+    fn test_validity() {
+        assert!(ServoCalibration::default().is_valid());
+
+        let swapped = ServoCalibration {
+            min_pulse_us: 2500,
+            max_pulse_us: 500,
+            ..Default::default()
+        };
+        assert!(!swapped.is_valid());
+
+        let empty_range = ServoCalibration {
+            min_angle: Radians::new(2.0),
+            max_angle: Radians::new(1.0),
+            ..Default::default()
+        };
+        assert!(!empty_range.is_valid());
+
+        let nan = ServoCalibration {
+            offset: Radians::new(f32::NAN),
+            ..Default::default()
+        };
+        assert!(!nan.is_valid());
+    }
+
+    #[test]
+    fn test_angle_range() {
+        let cal = ServoCalibration {
+            min_angle: Radians::new(0.5),
+            max_angle: Radians::new(1.0),
+            ..Default::default()
+        };
+        assert_eq!(pulse(&cal, 0.0), pulse(&cal, 0.5));
+        assert_eq!(pulse(&cal, 2.0), pulse(&cal, 1.0));
+        assert!(pulse(&cal, 0.75) > pulse(&cal, 0.5));
+    }
+}
@@ -0,0 +1,387 @@
+//! # Configurator
+//!
+//! Исполнение команд настройки без привязки к хранилищу и среде исполнения.
+//!
+//! Конфигуратор читает сохраненную конфигурацию при запуске, проверяет и
+//! сохраняет изменения, переданные командами, и передает действующие значения
+//! получателям: сетевому менеджеру, позиционеру и сетевому API. Прошивка
+//! хранит конфигурацию во Flash-памяти, симулятор — в файле и в памяти.
+
+use crate::{
+    calibration::Calibration,
+    mechanics_config::StartupMechanicsConfig,
+    request::Command,
+    response::{Parameter, Rejection, Response},
+    wifi_config::WifiConfig,
+};
+use core::{fmt, future::Future};
+
+/// Хранилище конфигурации. Отсутствующая запись читается как `None`.
+pub trait Storage {
+    type Error: fmt::Debug;
+
+    fn fetch_wifi(&mut self) -> impl Future<Output = Result<Option<WifiConfig>, Self::Error>>;
+
+    fn store_wifi(&mut self, config: &WifiConfig) -> impl Future<Output = Result<(), Self::Error>>;
+
+    fn fetch_mechanics(
+        &mut self,
+    ) -> impl Future<Output = Result<Option<StartupMechanicsConfig>, Self::Error>>;
+
+    fn store_mechanics(
+        &mut self,
+        config: &StartupMechanicsConfig,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+
+    fn fetch_calibration(
+        &mut self,
+    ) -> impl Future<Output = Result<Option<Calibration>, Self::Error>>;
+
+    fn store_calibration(
+        &mut self,
+        calibration: &Calibration,
+    ) -> impl Future<Output = Result<(), Self::Error>>;
+}
+
+/// Получатели действующей конфигурации.
+pub trait Listeners {
+    /// Передает конфигурацию Wi-Fi сетевому менеджеру.
+    fn wifi(&self, config: WifiConfig);
+
+    /// Передает конфигурацию механики позиционеру, а ограничения углов —
+    /// сетевому API.
+    fn mechanics(&self, config: StartupMechanicsConfig);
+
+    /// Передает калибровку приводов позиционеру.
+    fn calibration(&self, calibration: Calibration);
+
+    /// Выводит сообщение в журнал.
+    fn log(&self, args: fmt::Arguments<'_>);
+}
+
+macro_rules! log {
+    ($listeners:expr, $($arg:tt)*) => {
+        $listeners.log(format_args!($($arg)*))
+    };
+}
+
+/// Конфигуратор: исполняет команды настройки.
+pub struct Configurator<S, L> {
+    storage: S,
+    listeners: L,
+    mechanics: StartupMechanicsConfig,
+    stored_calibration: Calibration,
+    /// Действующая калибровка может отличаться от сохраненной на время
+    /// проверки.
+    calibration: Calibration,
+}
+
+impl<S: Storage, L: Listeners> Configurator<S, L> {
+    /// Читает сохраненную конфигурацию и передает ее получателям. Вместо
+    /// отсутствующих, нечитаемых и недопустимых записей используются значения
+    /// по умолчанию.
+    pub async fn start(mut storage: S, listeners: L) -> Self {
+        let wifi = fetch(&listeners, "config", storage.fetch_wifi().await);
+        listeners.wifi(wifi);
+
+        let mut mechanics = fetch(
+            &listeners,
+            "mechanics config",
+            storage.fetch_mechanics().await,
+        );
+        // Сохраненная конфигурация могла быть записана версией без нынешних
+        // проверок.
+        if let Err(parameter) = mechanics.validate() {
+            log!(
+                listeners,
+                "CONFIGURATOR ERROR: invalid stored mechanics config: {parameter:?}"
+            );
+            log!(listeners, "CONFIGURATOR: using default mechanics config");
+            mechanics = StartupMechanicsConfig::default();
+        }
+        listeners.mechanics(mechanics.clone());
+
+        let calibration = fetch(&listeners, "calibration", storage.fetch_calibration().await);
+        listeners.calibration(calibration);
+
+        Self {
+            storage,
+            listeners,
+            mechanics,
+            stored_calibration: calibration,
+            calibration,
+        }
+    }
+
+    /// Исполняет команду и возвращает ответ на нее.
+    pub async fn handle(&mut self, command: Command) -> Response {
+        let mechanics = self.mechanics.clone();
+        match command {
+            Command::ConfigureWifi(new_cfg) => {
+                log!(self.listeners, "CONFIGURATOR: saving new WiFi config...");
+                match self.storage.store_wifi(&new_cfg).await {
+                    Ok(()) => {
+                        self.listeners.wifi(new_cfg);
+                        Response::CommandAck
+                    }
+                    Err(err) => self.storage_failure(err),
+                }
+            }
+            Command::SetMaxSpeed(max_speed) => {
+                log!(self.listeners, "CONFIGURATOR: saving new max speed...");
+                let new = StartupMechanicsConfig {
+                    max_speed,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetMaxAcceleration(max_acceleration) => {
+                log!(
+                    self.listeners,
+                    "CONFIGURATOR: saving new max acceleration..."
+                );
+                let new = StartupMechanicsConfig {
+                    max_acceleration,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetMaxJerk(max_jerk) => {
+                log!(self.listeners, "CONFIGURATOR: saving new max jerk...");
+                let new = StartupMechanicsConfig {
+                    max_jerk,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetMotionProfile(profile) => {
+                log!(self.listeners, "CONFIGURATOR: saving new motion profile...");
+                let new = StartupMechanicsConfig {
+                    profile,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetBlendTolerance(blend_tolerance) => {
+                log!(
+                    self.listeners,
+                    "CONFIGURATOR: saving new blend tolerance..."
+                );
+                let new = StartupMechanicsConfig {
+                    blend_tolerance,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetInitPosition(init_position) => {
+                log!(self.listeners, "CONFIGURATOR: saving new init position...");
+                let new = StartupMechanicsConfig {
+                    init_position,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetJointLimits(joint_limits) => {
+                log!(self.listeners, "CONFIGURATOR: saving new joint limits...");
+                let new = StartupMechanicsConfig {
+                    joint_limits,
+                    ..mechanics
+                };
+                self.update_mechanics(new).await
+            }
+            Command::SetCalibration(axis, servo) if servo.is_valid() => {
+                log!(
+                    self.listeners,
+                    "CONFIGURATOR: saving new {axis:?} calibration..."
+                );
+                let mut new = self.stored_calibration;
+                *new.get_mut(axis) = servo;
+                match self.storage.store_calibration(&new).await {
+                    Ok(()) => {
+                        self.stored_calibration = new;
+                        *self.calibration.get_mut(axis) = servo;
+                        self.listeners.calibration(self.calibration);
+                        Response::CommandAck
+                    }
+                    Err(err) => self.storage_failure(err),
+                }
+            }
+            Command::TestCalibration(axis, servo) if servo.is_valid() => {
+                log!(
+                    self.listeners,
+                    "CONFIGURATOR: testing {axis:?} calibration..."
+                );
+                *self.calibration.get_mut(axis) = servo;
+                self.listeners.calibration(self.calibration);
+                Response::CommandAck
+            }
+            Command::SetCalibration(..) | Command::TestCalibration(..) => {
+                log!(self.listeners, "CONFIGURATOR ERROR: invalid calibration");
+                Response::Rejected(Rejection::InvalidParameter(Parameter::Calibration))
+            }
+            Command::GetCalibration => Response::Calibration(self.calibration),
+        }
+    }
+
+    /// Проверяет новую конфигурацию механики, сохраняет ее и передает
+    /// позиционеру и сетевому API.
+    ///
+    /// Недопустимая или несохраненная конфигурация не применяется.
+    async fn update_mechanics(&mut self, new: StartupMechanicsConfig) -> Response {
+        if let Err(parameter) = new.validate() {
+            log!(
+                self.listeners,
+                "CONFIGURATOR ERROR: invalid parameter: {parameter:?}"
+            );
+            return Response::Rejected(Rejection::InvalidParameter(parameter));
+        }
+
+        if let Err(err) = self.storage.store_mechanics(&new).await {
+            return self.storage_failure(err);
+        }
+
+        self.listeners.mechanics(new.clone());
+        self.mechanics = new;
+        Response::CommandAck
+    }
+
+    fn storage_failure(&self, err: S::Error) -> Response {
+        log!(
+            self.listeners,
+            "CONFIGURATOR ERROR: failed to save config: {err:?}"
+        );
+        Response::Rejected(Rejection::StorageFailure)
+    }
+}
+
+/// Возвращает прочитанную запись `name` или значение по умолчанию.
+fn fetch<T: Default, L: Listeners, E: fmt::Debug>(
+    listeners: &L,
+    name: &str,
+    fetched: Result<Option<T>, E>,
+) -> T {
+    match fetched {
+        Ok(Some(value)) => {
+            log!(listeners, "CONFIGURATOR: {name} fetched");
+            return value;
+        }
+        Ok(None) => log!(listeners, "CONFIGURATOR: {name} not found"),
+        Err(err) => log!(
+            listeners,
+            "CONFIGURATOR ERROR: failed to fetch {name}: {err:?}"
+        ),
+    }
+    log!(listeners, "CONFIGURATOR: using default {name}");
+    T::default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::Radians;
+    use core::{
+        cell::RefCell,
+        convert::Infallible,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    /// Исполняет задачу, которая не ждет внешних событий.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("задача ждет внешнего события"),
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeStorage {
+        mechanics: Option<StartupMechanicsConfig>,
+    }
+
+    impl Storage for FakeStorage {
+        type Error = Infallible;
+
+        async fn fetch_wifi(&mut self) -> Result<Option<WifiConfig>, Infallible> {
+            Ok(None)
+        }
+
+        async fn store_wifi(&mut self, _config: &WifiConfig) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        async fn fetch_mechanics(&mut self) -> Result<Option<StartupMechanicsConfig>, Infallible> {
+            Ok(self.mechanics.clone())
+        }
+
+        async fn store_mechanics(
+            &mut self,
+            config: &StartupMechanicsConfig,
+        ) -> Result<(), Infallible> {
+            self.mechanics = Some(config.clone());
+            Ok(())
+        }
+
+        async fn fetch_calibration(&mut self) -> Result<Option<Calibration>, Infallible> {
+            Ok(None)
+        }
+
+        async fn store_calibration(
+            &mut self,
+            _calibration: &Calibration,
+        ) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeListeners {
+        mechanics: RefCell<Option<StartupMechanicsConfig>>,
+    }
+
+    impl Listeners for &FakeListeners {
+        fn wifi(&self, _config: WifiConfig) {}
+
+        fn mechanics(&self, config: StartupMechanicsConfig) {
+            *self.mechanics.borrow_mut() = Some(config);
+        }
+
+        fn calibration(&self, _calibration: Calibration) {}
+
+        fn log(&self, _args: fmt::Arguments<'_>) {}
+    }
+
+    #[test]
+    fn test_invalid_stored_mechanics_is_replaced_by_default() {
+        let mut stored = StartupMechanicsConfig::default();
+        stored.init_position.claw = stored.joint_limits.max.claw + Radians::new(0.5);
+        let storage = FakeStorage {
+            mechanics: Some(stored),
+        };
+        let listeners = FakeListeners::default();
+
+        block_on(Configurator::start(storage, &listeners));
+
+        assert_eq!(
+            *listeners.mechanics.borrow(),
+            Some(StartupMechanicsConfig::default())
+        );
+    }
+
+    #[test]
+    fn test_init_position_outside_new_limits_is_rejected() {
+        let listeners = FakeListeners::default();
+        let mut configurator = block_on(Configurator::start(FakeStorage::default(), &listeners));
+        let mut limits = StartupMechanicsConfig::default().joint_limits;
+        limits.min.rotation = limits.max.rotation;
+
+        let response = block_on(configurator.handle(Command::SetJointLimits(limits)));
+
+        assert_eq!(
+            response,
+            Response::Rejected(Rejection::InvalidParameter(Parameter::InitPosition))
+        );
+    }
+}
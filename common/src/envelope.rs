@@ -0,0 +1,363 @@
+//! # Envelope
+//!
+//! Конверт сообщения: идентификатор запроса, тип сообщения и данные с
+//! префиксом длины. Конверт передается телом кадра (см. [`crate::framing`]).
+//!
+//! Postcard кодирует варианты перечислений по номерам, поэтому сообщение
+//! нового варианта не разбирается старой стороной. Конверт отделяет тип от
+//! данных: сообщение неизвестного типа пропускается целиком, а отправитель
+//! получает отказ `Rejection::Unsupported` с идентификатором своего запроса.
+//! Так прошивку можно обновлять постепенно, не меняя версию протокола.
+//!
+//! Типы сообщений не переиспользуются: новый вариант получает новый тип.
+//! Данные сообщения разбираются без учета лишних байт в конце, поэтому новые
+//! поля допускается добавлять в конец данных.
+
+use crate::{session::RequestId, session::Tagged, varint_size};
+use postcard::experimental::max_size::MaxSize;
+use serde::{
+    Deserialize, Serialize, Serializer,
+    ser::{Error as _, SerializeTuple},
+};
+
+/// Тип сообщения.
+pub type MessageType = u16;
+
+/// Наибольший размер конверта с сообщением типа `T`.
+pub const fn max_envelope_size<T: MaxSize>() -> usize {
+    Option::<RequestId>::POSTCARD_MAX_SIZE
+        + varint_size(MessageType::MAX as usize)
+        + varint_size(T::POSTCARD_MAX_SIZE)
+        + T::POSTCARD_MAX_SIZE
+}
+
+/// Наибольший размер конверта запроса.
+pub const MAX_REQUEST_ENVELOPE_SIZE: usize = max_envelope_size::<crate::request::Request>();
+
+/// Наибольший размер конверта ответа.
+pub const MAX_RESPONSE_ENVELOPE_SIZE: usize = max_envelope_size::<crate::response::Response>();
+
+/// Сообщение, передаваемое в конверте.
+///
+/// Реализуется макросом `impl_message!` по таблице типов вариантов.
+pub trait Message: Sized {
+    /// Тип сообщения.
+    fn kind(&self) -> MessageType;
+
+    /// Размер данных сообщения.
+    fn payload_size(&self) -> postcard::Result<usize>;
+
+    /// Сериализует данные сообщения очередным элементом кортежа.
+    fn serialize_payload<S: SerializeTuple>(&self, tuple: &mut S) -> Result<(), S::Error>;
+
+    /// Разбирает данные сообщения типа `kind`. Возвращает `None`, если тип
+    /// неизвестен.
+    fn decode(kind: MessageType, payload: &[u8]) -> postcard::Result<Option<Self>>;
+}
+
+/// Принятый конверт с неразобранными данными.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<'a> {
+    /// Идентификатор запроса.
+    pub id: Option<RequestId>,
+    /// Тип сообщения.
+    pub kind: MessageType,
+    /// Данные сообщения.
+    pub payload: &'a [u8],
+}
+
+/// Сообщение, упакованное в конверт для отправки.
+#[derive(Copy, Clone, Debug)]
+pub struct Sealed<'a, T>(&'a Tagged<T>);
+
+/// Упаковывает сообщение в конверт.
+#[inline]
+pub fn seal<T: Message>(message: &Tagged<T>) -> Sealed<'_, T> {
+    Sealed(message)
+}
+
+impl<T: Message> Serialize for Sealed<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Данные пишутся на месте, с тем же префиксом длины, что у среза
+        // байт в `Envelope`.
+        let len = self.0.message.payload_size().map_err(S::Error::custom)?;
+        let mut tuple = serializer.serialize_tuple(4)?;
+        tuple.serialize_element(&self.0.id)?;
+        tuple.serialize_element(&self.0.message.kind())?;
+        tuple.serialize_element(&len)?;
+        self.0.message.serialize_payload(&mut tuple)?;
+        tuple.end()
+    }
+}
+
+/// Ошибка разбора конверта.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OpenError {
+    /// Конверт не удалось разобрать.
+    Malformed(postcard::Error),
+    /// Тип сообщения или вариант вложенной команды неизвестен.
+    Unsupported {
+        id: Option<RequestId>,
+        kind: MessageType,
+    },
+    /// Данные сообщения известного типа не удалось разобрать.
+    InvalidPayload {
+        id: Option<RequestId>,
+        error: postcard::Error,
+    },
+}
+
+impl OpenError {
+    /// Идентификатор запроса, если конверт удалось разобрать.
+    pub fn id(&self) -> Option<RequestId> {
+        match self {
+            Self::Malformed(_) => None,
+            Self::Unsupported { id, .. } | Self::InvalidPayload { id, .. } => *id,
+        }
+    }
+}
+
+/// Разбирает конверт из тела кадра.
+pub fn open<T: Message>(body: &[u8]) -> Result<Tagged<T>, OpenError> {
+    let Envelope { id, kind, payload } =
+        postcard::from_bytes(body).map_err(OpenError::Malformed)?;
+    match T::decode(kind, payload) {
+        Ok(Some(message)) => Ok(Tagged::new(id, message)),
+        Ok(None) => Err(OpenError::Unsupported { id, kind }),
+        Err(error) => Err(OpenError::InvalidPayload { id, error }),
+    }
+}
+
+/// Реализует [`Message`] для перечисления по таблице типов вариантов.
+///
+/// Варианты записываются как `тип => Вариант`, `тип => Вариант(поле)` или
+/// `тип => Вариант { поле }`; вариант содержит не больше одного поля.
+///
+/// Поле, которое само может оказаться неизвестным получателю, записывается
+/// как `Вариант(поле: разбор)`: функция `разбор` возвращает `None` для
+/// неизвестных данных, и сообщение считается неподдерживаемым.
+macro_rules! impl_message {
+    (@pat $variant:ident ($field:ident)) => { Self::$variant($field) };
+    (@pat $variant:ident { $field:ident }) => { Self::$variant { $field } };
+    (@pat $variant:ident) => { Self::$variant };
+    (@val $field:ident) => { $field };
+    (@val) => { &() };
+    (@new $payload:ident $variant:ident ($field:ident : $decode:path)) => {
+        match $decode($payload)? {
+            Some($field) => Self::$variant($field),
+            None => return Ok(None),
+        }
+    };
+    (@new $payload:ident $variant:ident ($field:ident)) => {
+        Self::$variant(postcard::from_bytes($payload)?)
+    };
+    (@new $payload:ident $variant:ident { $field:ident }) => {
+        Self::$variant { $field: postcard::from_bytes($payload)? }
+    };
+    (@new $payload:ident $variant:ident) => { Self::$variant };
+    ($ty:ty {
+        $(
+            $kind:literal => $variant:ident
+            $(($field:ident $(: $decode:path)?))? $({ $named:ident })?
+        ),* $(,)?
+    }) => {
+        impl $crate::envelope::Message for $ty {
+            fn kind(&self) -> $crate::envelope::MessageType {
+                match self {
+                    $(Self::$variant { .. } => $kind,)*
+                }
+            }
+
+            fn payload_size(&self) -> postcard::Result<usize> {
+                match self {
+                    $(
+                        $crate::envelope::impl_message!(@pat $variant $(($field))? $({ $named })?) =>
+                            postcard::experimental::serialized_size(
+                                $crate::envelope::impl_message!(@val $($field)? $($named)?)
+                            ),
+                    )*
+                }
+            }
+
+            fn serialize_payload<S: ::serde::ser::SerializeTuple>(
+                &self,
+                tuple: &mut S,
+            ) -> Result<(), S::Error> {
+                match self {
+                    $(
+                        $crate::envelope::impl_message!(@pat $variant $(($field))? $({ $named })?) =>
+                            tuple.serialize_element(
+                                $crate::envelope::impl_message!(@val $($field)? $($named)?)
+                            ),
+                    )*
+                }
+            }
+
+            fn decode(
+                kind: $crate::envelope::MessageType,
+                payload: &[u8],
+            ) -> postcard::Result<Option<Self>> {
+                Ok(Some(match kind {
+                    $(
+                        $kind => $crate::envelope::impl_message!(
+                            @new payload $variant $(($field $(: $decode)?))? $({ $named })?
+                        ),
+                    )*
+                    _ => return Ok(None),
+                }))
+            }
+        }
+    };
+}
+
+pub(crate) use impl_message;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        framing,
+        motion::{MotionProfile, Timing},
+        request::{Command, MAX_BATCH_LEN, Request, Waypoint, WaypointBatch},
+        response::{Rejection, Response},
+        units::Seconds,
+    };
+
+    fn sealed<T: Message>(message: &Tagged<T>) -> impl core::ops::Deref<Target = [u8]> + use<T> {
+        crate::to_vec::<_, 512>(&seal(message)).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let requests = [
+            Tagged::new(Some(3), Request::GetStatus),
+            Tagged::new(Some(4), Request::Subscribe { rate_hz: 10 }),
+            Tagged::new(None, Request::Immediate(Command::GetCalibration)),
+        ];
+        for request in requests {
+            let bytes = sealed(&request);
+            assert_eq!(open::<Request>(&bytes), Ok(request));
+        }
+
+        let response = Tagged::new(Some(5), Response::PositionAck { free_slots: 2 });
+        assert_eq!(open::<Response>(&sealed(&response)), Ok(response));
+    }
+
+    #[test]
+    fn test_layout() {
+        let request = Tagged::new(Some(1), Request::SetWatchdog { timeout_ms: 300 });
+        let bytes = sealed(&request);
+        let envelope = postcard::from_bytes::<Envelope>(&bytes).unwrap();
+        assert_eq!(envelope.id, Some(1));
+        assert_eq!(envelope.kind, request.message.kind());
+        assert_eq!(envelope.payload, &[0xAC, 0x02]);
+    }
+
+    #[test]
+    fn test_unknown_type_is_unsupported() {
+        let envelope = Envelope {
+            id: Some(9),
+            kind: 1000,
+            payload: &[1, 2, 3],
+        };
+        let bytes = postcard::to_vec::<_, 16>(&envelope).unwrap();
+        assert_eq!(
+            open::<Request>(&bytes),
+            Err(OpenError::Unsupported {
+                id: Some(9),
+                kind: 1000
+            })
+        );
+
+        // Неизвестный вариант команды внутри известного типа.
+        let kind = Request::Immediate(Command::GetCalibration).kind();
+        for index in [Command::COUNT, 100, 1000] {
+            let mut payload = [0u8; 8];
+            let payload = postcard::to_slice(&(index as u32), &mut payload).unwrap();
+            let envelope = Envelope {
+                id: Some(10),
+                kind,
+                payload,
+            };
+            let bytes = postcard::to_vec::<_, 16>(&envelope).unwrap();
+            assert_eq!(
+                open::<Request>(&bytes),
+                Err(OpenError::Unsupported { id: Some(10), kind })
+            );
+        }
+    }
+
+    #[test]
+    fn test_known_command_with_bad_data_is_invalid() {
+        // Известный вариант команды с испорченными данными.
+        let envelope = Envelope {
+            id: Some(11),
+            kind: Request::Immediate(Command::GetCalibration).kind(),
+            payload: &[0],
+        };
+        let bytes = postcard::to_vec::<_, 16>(&envelope).unwrap();
+        assert!(matches!(
+            open::<Request>(&bytes),
+            Err(OpenError::InvalidPayload { id: Some(11), .. })
+        ));
+    }
+
+    #[test]
+    fn test_appended_fields_are_ignored() {
+        let response = Response::Rejected(Rejection::PositionQueueFull);
+        let mut payload = heapless::Vec::<u8, 16>::new();
+        payload
+            .extend_from_slice(&postcard::to_vec::<_, 16>(&Rejection::PositionQueueFull).unwrap())
+            .unwrap();
+        payload.extend_from_slice(&[7, 7]).unwrap();
+        let envelope = Envelope {
+            id: None,
+            kind: response.kind(),
+            payload: &payload,
+        };
+        let bytes = postcard::to_vec::<_, 16>(&envelope).unwrap();
+        assert_eq!(open::<Response>(&bytes), Ok(Tagged::from(response)));
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(matches!(
+            open::<Request>(&[0x01]),
+            Err(OpenError::Malformed(_))
+        ));
+        let envelope = Envelope {
+            id: Some(2),
+            kind: Request::Subscribe { rate_hz: 1 }.kind(),
+            payload: &[],
+        };
+        let bytes = postcard::to_vec::<_, 16>(&envelope).unwrap();
+        assert!(matches!(
+            open::<Request>(&bytes),
+            Err(OpenError::InvalidPayload { id: Some(2), .. })
+        ));
+    }
+
+    #[test]
+    fn test_full_batch_fits_max_size() {
+        // Наибольшая точка: заданы и профиль, и время перемещения.
+        let waypoint = Waypoint {
+            position: crate::quantities::Position {
+                rotation: 1.0.into(),
+                shoulder: 2.0.into(),
+                forearm: 3.0.into(),
+                claw: 4.0.into(),
+            },
+            profile: Some(MotionProfile::SCurve),
+            timing: Some(Timing::Duration(Seconds::new(0.5))),
+        };
+        let batch = WaypointBatch::try_from([waypoint; MAX_BATCH_LEN].as_slice()).unwrap();
+        let request = Tagged::new(Some(u32::MAX), Request::EnqueueBatch(batch));
+
+        let mut buf = [0u8; framing::frame_size(MAX_REQUEST_ENVELOPE_SIZE)];
+        let frame = framing::encode(&seal(&request), &mut buf).unwrap();
+        let mut decoder = framing::RequestDecoder::new();
+        assert_eq!(decoder.push(frame), frame.len());
+        let body = decoder.pull().unwrap().unwrap();
+        assert_eq!(open::<Request>(body), Ok(request));
+    }
+}
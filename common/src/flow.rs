@@ -0,0 +1,175 @@
+//! # Flow Control
+//!
+//! Кредитное управление потоком перемещений на стороне клиента.
+//!
+//! Прошивка сообщает свободное место в очереди позиционирования в
+//! `Welcome::free_slots` и в каждом `Response::PositionAck`. Клиент получает
+//! столько кредитов, сколько мест свободно в начале сессии, тратит по кредиту
+//! на каждое перемещение в запросе и получает кредит обратно с каждым
+//! итогом перемещения: подтверждением, отменой или отказом. Пока клиент не
+//! тратит больше, чем имеет, очередь не переполняется.
+//!
+//! Переполнение очереди не разрывает соединение: запрос отклоняется целиком
+//! с `Rejection::PositionQueueFull`, и его кредиты возвращаются.
+
+use crate::{
+    request::Request,
+    response::{Rejection, Response},
+    session::{RequestId, Tagged},
+};
+use heapless::LinearMap;
+
+/// Наибольшее количество запросов, ожидающих итогов перемещений.
+pub const MAX_PENDING_REQUESTS: usize = 32;
+
+/// Кредиты клиента на места в очереди позиционирования.
+#[derive(Clone, Debug)]
+pub struct Credits {
+    /// Свободное место в очереди в начале сессии.
+    capacity: u8,
+    /// Кредиты, потраченные запросами и еще не возвращенные.
+    pending: LinearMap<RequestId, u8, MAX_PENDING_REQUESTS>,
+}
+
+impl Credits {
+    /// Создает кредиты по свободному месту из приветствия прошивки.
+    pub fn new(free_slots: u8) -> Self {
+        Self {
+            capacity: free_slots,
+            pending: LinearMap::new(),
+        }
+    }
+
+    /// Количество перемещений, которые можно отправить сейчас.
+    pub fn available(&self) -> u8 {
+        let spent: u8 = self.pending.values().sum();
+        self.capacity - spent
+    }
+
+    /// Тратит кредиты на запрос `request` с идентификатором `id`.
+    ///
+    /// Возвращает `false` и ничего не тратит, если кредитов не хватает или
+    /// запрос с тем же идентификатором еще ждет итогов перемещений.
+    /// Запросы без перемещений кредитов не требуют.
+    pub fn acquire(&mut self, id: RequestId, request: &Request) -> bool {
+        let slots = request.queue_slots();
+        if slots == 0 {
+            return true;
+        }
+        // Повторный идентификатор заменил бы запись, и кредиты первого
+        // запроса не вернулись бы никогда.
+        if slots > self.available() as usize || self.pending.contains_key(&id) {
+            return false;
+        }
+        self.pending.insert(id, slots as u8).is_ok()
+    }
+
+    /// Возвращает кредиты по ответу прошивки.
+    ///
+    /// Итог перемещения возвращает один кредит. Отказ возвращает все
+    /// оставшиеся кредиты запроса: прошивка отклоняет пакет целиком, а
+    /// отдельные перемещения пакета отклоняются только по недостижимому
+    /// времени.
+    pub fn release(&mut self, response: &Tagged<Response>) {
+        let Some(id) = response.id else { return };
+        let Some(slots) = self.pending.get_mut(&id) else {
+            return;
+        };
+        match response.message {
+            Response::PositionAck { .. }
+            | Response::MotionCancelled
+            | Response::Rejected(Rejection::InfeasibleDuration(_)) => *slots -= 1,
+            Response::Rejected(_) => *slots = 0,
+            _ => {}
+        }
+        if *slots == 0 {
+            self.pending.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        quantities::Position,
+        request::{Waypoint, WaypointBatch},
+        units::{Radians, Seconds},
+    };
+
+    fn waypoint() -> Waypoint {
+        let angle = Radians::new(1.0);
+        Waypoint::from(Position {
+            rotation: angle,
+            shoulder: angle,
+            forearm: angle,
+            claw: angle,
+        })
+    }
+
+    fn batch(len: usize) -> Request {
+        let mut batch = WaypointBatch::new();
+        for _ in 0..len {
+            batch.push(waypoint()).unwrap();
+        }
+        Request::EnqueueBatch(batch)
+    }
+
+    fn ack(id: RequestId) -> Tagged<Response> {
+        Tagged::new(Some(id), Response::PositionAck { free_slots: 0 })
+    }
+
+    #[test]
+    fn test_credits_are_spent_and_returned() {
+        let mut credits = Credits::new(4);
+        assert!(credits.acquire(1, &Request::Enqueue(waypoint())));
+        assert!(credits.acquire(2, &batch(3)));
+        assert_eq!(credits.available(), 0);
+        assert!(!credits.acquire(3, &Request::Enqueue(waypoint())));
+        // Запросы без перемещений не ограничиваются.
+        assert!(credits.acquire(4, &Request::GetStatus));
+
+        credits.release(&ack(2));
+        credits.release(&Tagged::new(Some(2), Response::MotionCancelled));
+        assert_eq!(credits.available(), 2);
+        // Чужие и повторные ответы не возвращают кредиты.
+        credits.release(&ack(7));
+        credits.release(&Tagged::new(Some(1), Response::CommandAck));
+        assert_eq!(credits.available(), 2);
+
+        credits.release(&ack(1));
+        credits.release(&ack(2));
+        credits.release(&ack(2));
+        assert_eq!(credits.available(), 4);
+    }
+
+    #[test]
+    fn test_reused_id_is_refused_while_pending() {
+        let mut credits = Credits::new(4);
+        assert!(credits.acquire(1, &Request::Enqueue(waypoint())));
+        assert!(!credits.acquire(1, &batch(2)));
+        assert_eq!(credits.available(), 3);
+
+        credits.release(&ack(1));
+        assert_eq!(credits.available(), 4);
+        assert!(credits.acquire(1, &batch(2)));
+        assert_eq!(credits.available(), 2);
+    }
+
+    #[test]
+    fn test_rejection_returns_remaining_credits() {
+        let mut credits = Credits::new(8);
+        assert!(credits.acquire(1, &batch(5)));
+        credits.release(&Tagged::new(
+            Some(1),
+            Response::Rejected(Rejection::InfeasibleDuration(Seconds::new(1.0))),
+        ));
+        assert_eq!(credits.available(), 4);
+
+        credits.release(&Tagged::new(
+            Some(1),
+            Response::Rejected(Rejection::PositionQueueFull),
+        ));
+        assert_eq!(credits.available(), 8);
+    }
+}
@@ -0,0 +1,337 @@
+//! # Framing
+//!
+//! Кадры протокола: длина в формате LEB128 (varint), за которой следует
+//! тело сообщения postcard.
+//!
+//! Кодек не выполняет ввод-вывод. Принятые байты передаются декодеру
+//! методом [`FrameDecoder::push`], а готовые кадры забираются методом
+//! [`FrameDecoder::pull`]. Декодер принимает байты только до конца текущего
+//! кадра, поэтому остаток данных можно передать следующему декодеру или
+//! вернуть в буфер чтения.
+
+use crate::{
+    envelope::{MAX_REQUEST_ENVELOPE_SIZE, MAX_RESPONSE_ENVELOPE_SIZE},
+    varint_size,
+};
+use postcard::experimental::max_size::MaxSize;
+use serde::Serialize;
+
+/// Размер кадра с телом длиной `len` байт.
+pub const fn frame_size(len: usize) -> usize {
+    varint_size(len) + len
+}
+
+/// Наибольший размер кадра с сообщением типа `T`.
+pub const fn max_frame_size<T: MaxSize>() -> usize {
+    frame_size(T::POSTCARD_MAX_SIZE)
+}
+
+/// Декодер конвертов с запросами клиента.
+pub type RequestDecoder = FrameDecoder<MAX_REQUEST_ENVELOPE_SIZE>;
+
+/// Декодер конвертов с ответами прошивки.
+pub type ResponseDecoder = FrameDecoder<MAX_RESPONSE_ENVELOPE_SIZE>;
+
+/// Ошибка приема кадра.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FrameError {
+    /// Тело кадра длиной `len` байт не помещается в буфер декодера. Тело
+    /// пропущено, граница следующего кадра сохранена.
+    TooLarge(usize),
+    /// Длина кадра не помещается в `usize`. Граница следующего кадра
+    /// потеряна, и соединение следует разорвать.
+    Overflow,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    /// Чтение длины: накопленное значение и сдвиг следующих 7 бит.
+    Header { len: usize, shift: u32 },
+    /// Чтение тела: длина и количество принятых байт.
+    Body { len: usize, received: usize },
+    /// Кадр длиной `len` принят целиком.
+    Ready { len: usize },
+    /// Длина кадра переполнила `usize`.
+    Overflow,
+}
+
+impl State {
+    const START: Self = Self::Header { len: 0, shift: 0 };
+}
+
+/// Декодер кадров с буфером тела на `N` байт.
+///
+/// Размер буфера обычно берется из `MaxSize` принимаемого сообщения. Кадры
+/// длиннее буфера отклоняются с [`FrameError::TooLarge`], а в режиме
+/// [`FrameDecoder::truncating`] обрезаются до размера буфера.
+#[derive(Clone, Debug)]
+pub struct FrameDecoder<const N: usize> {
+    buf: [u8; N],
+    state: State,
+    /// Обрезать длинные кадры вместо отказа.
+    truncate: bool,
+}
+
+impl<const N: usize> FrameDecoder<N> {
+    /// Создает декодер, отклоняющий кадры длиннее `N` байт.
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            state: State::START,
+            truncate: false,
+        }
+    }
+
+    /// Создает декодер, возвращающий от длинных кадров первые `N` байт.
+    ///
+    /// Подходит для сообщений, к концу которых в новых версиях протокола
+    /// добавляются поля: postcard игнорирует лишние байты, но не их нехватку.
+    pub const fn truncating() -> Self {
+        Self {
+            truncate: true,
+            ..Self::new()
+        }
+    }
+
+    /// Принимает байты из `data` и возвращает количество принятых.
+    ///
+    /// Прием останавливается на конце кадра: пока кадр не забран методом
+    /// [`Self::pull`], новые байты не принимаются.
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        let mut used = 0;
+        while used < data.len() {
+            match self.state {
+                State::Header { len, shift } => {
+                    let byte = data[used];
+                    used += 1;
+                    let bits = (byte & 0x7F) as usize;
+                    if shift >= usize::BITS || (bits << shift) >> shift != bits {
+                        self.state = State::Overflow;
+                    } else if byte & 0x80 != 0 {
+                        self.state = State::Header {
+                            len: len | (bits << shift),
+                            shift: shift + 7,
+                        };
+                    } else {
+                        self.state = Self::body(len | (bits << shift), 0);
+                    }
+                }
+                State::Body { len, received } => {
+                    let chunk = (len - received).min(data.len() - used);
+                    // Байты сверх буфера отбрасываются.
+                    if received < N {
+                        let stored = chunk.min(N - received);
+                        self.buf[received..received + stored]
+                            .copy_from_slice(&data[used..used + stored]);
+                    }
+                    used += chunk;
+                    self.state = Self::body(len, received + chunk);
+                }
+                State::Ready { .. } | State::Overflow => break,
+            }
+        }
+        used
+    }
+
+    /// Возвращает `true`, если [`Self::pull`] вернет результат.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        matches!(self.state, State::Ready { .. } | State::Overflow)
+    }
+
+    /// Забирает принятый кадр или ошибку приема. Возвращает `None`, если кадр
+    /// еще не принят целиком.
+    pub fn pull(&mut self) -> Option<Result<&[u8], FrameError>> {
+        let result = match self.state {
+            State::Ready { len } if len <= N => Ok(len),
+            State::Ready { .. } if self.truncate => Ok(N),
+            State::Ready { len } => Err(FrameError::TooLarge(len)),
+            State::Overflow => Err(FrameError::Overflow),
+            _ => return None,
+        };
+        self.state = State::START;
+        Some(result.map(|len| &self.buf[..len]))
+    }
+
+    fn body(len: usize, received: usize) -> State {
+        if received == len {
+            State::Ready { len }
+        } else {
+            State::Body { len, received }
+        }
+    }
+}
+
+impl<const N: usize> Default for FrameDecoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Сериализует `value` в кадр в буфере `buf` и возвращает байты кадра.
+///
+/// Буфера размером [`max_frame_size`] достаточно для любого сообщения типа.
+pub fn encode<'a, T: Serialize + ?Sized>(
+    value: &T,
+    buf: &'a mut [u8],
+) -> postcard::Result<&'a [u8]> {
+    // Место под длину: наименьшее, которого хватит телу на весь остаток буфера.
+    let mut reserved = 1;
+    while reserved < buf.len() && varint_size(buf.len() - reserved) > reserved {
+        reserved += 1;
+    }
+    if reserved > buf.len() {
+        return Err(postcard::Error::SerializeBufferFull);
+    }
+
+    let len = postcard::to_slice(value, &mut buf[reserved..])?.len();
+    let start = reserved - varint_size(len);
+    let mut val = len;
+    for byte in &mut buf[start..reserved] {
+        *byte = (val as u8 & 0x7F) | if val >= 0x80 { 0x80 } else { 0 };
+        val >>= 7;
+    }
+    Ok(&buf[start..reserved + len])
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::{envelope, request::Request, response::Response, session::Tagged};
+    use std::{vec, vec::Vec};
+
+    fn encoded<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut buf = [0u8; 512];
+        encode(value, &mut buf).unwrap().to_vec()
+    }
+
+    /// Передает в декодер все байты и собирает кадры.
+    fn decode_all<const N: usize>(
+        decoder: &mut FrameDecoder<N>,
+        mut data: &[u8],
+    ) -> Vec<Result<Vec<u8>, FrameError>> {
+        let mut frames = Vec::new();
+        while !data.is_empty() {
+            let used = decoder.push(data);
+            data = &data[used..];
+            if let Some(frame) = decoder.pull() {
+                frames.push(frame.map(<[u8]>::to_vec));
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let response = Tagged::new(Some(7), Response::PositionAck { free_slots: 3 });
+        let frame = encoded(&response);
+        assert_eq!(frame[0] as usize, frame.len() - 1);
+
+        let mut decoder = ResponseDecoder::new();
+        assert!(!decoder.is_ready());
+        assert_eq!(decoder.pull(), None);
+        assert_eq!(decoder.push(&frame), frame.len());
+        assert!(decoder.is_ready());
+        let body = decoder.pull().unwrap().unwrap();
+        assert_eq!(crate::from_bytes::<Tagged<Response>>(body), Ok(response));
+        assert_eq!(decoder.pull(), None);
+    }
+
+    #[test]
+    fn test_byte_by_byte() {
+        let frame = encoded(&envelope::seal(&Tagged::from(Request::GetStatus)));
+        let mut decoder = RequestDecoder::new();
+        for (i, byte) in frame.iter().enumerate() {
+            assert!(!decoder.is_ready());
+            assert_eq!(decoder.push(core::slice::from_ref(byte)), 1, "byte {i}");
+        }
+        assert_eq!(decoder.pull(), Some(Ok(&frame[1..])));
+    }
+
+    #[test]
+    fn test_push_stops_at_frame_boundary() {
+        let first = encoded(&envelope::seal(&Tagged::from(Request::GetStatus)));
+        let second = encoded(&envelope::seal(&Tagged::new(Some(1), Request::Keepalive)));
+        let stream = [first.as_slice(), &second].concat();
+
+        let mut decoder = RequestDecoder::new();
+        assert_eq!(decoder.push(&stream), first.len());
+        // Пока кадр не забран, байты не принимаются.
+        assert_eq!(decoder.push(&stream[first.len()..]), 0);
+        assert_eq!(decoder.pull(), Some(Ok(&first[1..])));
+        assert_eq!(decoder.push(&stream[first.len()..]), second.len());
+        assert_eq!(decoder.pull(), Some(Ok(&second[1..])));
+    }
+
+    #[test]
+    fn test_multibyte_length() {
+        let body = [0xAAu8; 300];
+        let mut decoder = FrameDecoder::<300>::new();
+        let stream = [[0xAC, 0x02].as_slice(), &body].concat();
+        assert_eq!(decoder.push(&stream), stream.len());
+        assert_eq!(decoder.pull(), Some(Ok(body.as_slice())));
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        let mut decoder = ResponseDecoder::new();
+        assert_eq!(decoder.push(&[0, 0]), 1);
+        assert_eq!(decoder.pull(), Some(Ok([].as_slice())));
+        assert_eq!(decoder.push(&[0]), 1);
+        assert!(decoder.is_ready());
+    }
+
+    #[test]
+    fn test_too_large_frame_is_skipped() {
+        let stream = [5, 1, 2, 3, 4, 5, 2, 6, 7];
+        let mut decoder = FrameDecoder::<4>::new();
+        assert_eq!(
+            decode_all(&mut decoder, &stream),
+            [Err(FrameError::TooLarge(5)), Ok(vec![6, 7])]
+        );
+
+        let mut decoder = FrameDecoder::<4>::truncating();
+        assert_eq!(
+            decode_all(&mut decoder, &stream),
+            [Ok(vec![1, 2, 3, 4]), Ok(vec![6, 7])]
+        );
+    }
+
+    #[test]
+    fn test_length_overflow() {
+        let mut stream = [0xFFu8; 12];
+        stream[11] = 0x01;
+        let mut decoder = ResponseDecoder::new();
+        let used = decoder.push(&stream);
+        assert!(used < stream.len());
+        assert_eq!(decoder.pull(), Some(Err(FrameError::Overflow)));
+
+        // Старшие биты, не помещающиеся в `usize`, тоже переполнение.
+        let mut stream = [0x80u8; 10];
+        stream[usize::BITS as usize / 7] = 0x7F;
+        let mut decoder = ResponseDecoder::new();
+        decoder.push(&stream);
+        assert_eq!(decoder.pull(), Some(Err(FrameError::Overflow)));
+    }
+
+    #[test]
+    fn test_encode_buffer_limits() {
+        // Тело в 127 байт занимает буфер в 128 байт целиком.
+        let body = [1u8; 126];
+        let frame = encoded(&body.as_slice());
+        assert_eq!(frame.len(), 128);
+        let mut buf = [0u8; 128];
+        assert_eq!(encode(&body.as_slice(), &mut buf), Ok(frame.as_slice()));
+        let mut buf = [0u8; 127];
+        assert_eq!(
+            encode(&body.as_slice(), &mut buf),
+            Err(postcard::Error::SerializeBufferFull)
+        );
+        assert_eq!(
+            encode(&(), &mut []),
+            Err(postcard::Error::SerializeBufferFull)
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! # Handshake
+//!
+//! Рукопожатие в начале соединения. Клиент первым кадром отправляет [`Hello`],
+//! прошивка отвечает [`Welcome`] и, если версии протокола не совпадают,
+//! закрывает соединение.
+//!
+//! Сессия длится, пока открыто соединение. Когда клиент закрывает его или
+//! вытесняется новым клиентом, прошивка очищает очередь позиционирования;
+//! при закрытии соединения она также останавливает движение. Сторожевой
+//! таймер сессии (`Request::SetWatchdog`) по умолчанию отключен: клиент,
+//! который может пропасть без закрытия соединения, включает его сам.
+//!
+//! Кодирование postcard зависит от порядка полей и вариантов, поэтому
+//! совместимость проверяется до обмена запросами. Сообщения рукопожатия
+//! начинаются с сигнатуры и версии протокола, и эти поля не меняются между
+//! версиями: новые поля допускается добавлять только в конец.
+//!
+//! Запросы и ответы передаются в конвертах (см. [`crate::envelope`]), поэтому
+//! новые типы сообщений версию протокола не меняют.
+
+use crate::{request::Command, session::SessionId};
+use postcard::experimental::max_size::MaxSize;
+use serde::{Deserialize, Serialize};
+
+/// Сигнатура протокола.
+pub const MAGIC: [u8; 4] = *b"RARM";
+
+/// Версия протокола. Увеличивается при несовместимом изменении конверта или
+/// данных существующих сообщений.
+pub const PROTOCOL_VERSION: u16 = 3;
+
+/// Максимальная длина строки версии программы.
+pub const MAX_VERSION_LEN: usize = 16;
+
+/// Версия программы клиента или прошивки.
+pub type Version = crate::String<MAX_VERSION_LEN>;
+
+/// Приветствие клиента. Открывает сессию (см. описание модуля).
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, MaxSize)]
+pub struct Hello {
+    pub magic: [u8; 4],
+    pub protocol_version: u16,
+    /// Версия программы клиента (для журнала).
+    pub client_version: Version,
+}
+
+impl Hello {
+    /// Приветствие текущей версии протокола.
+    pub fn new(client_version: Version) -> Self {
+        Self {
+            magic: MAGIC,
+            protocol_version: PROTOCOL_VERSION,
+            client_version,
+        }
+    }
+
+    /// Клиент говорит на текущей версии протокола.
+    pub fn is_compatible(&self) -> bool {
+        self.magic == MAGIC && self.protocol_version == PROTOCOL_VERSION
+    }
+}
+
+/// Ответ прошивки на приветствие.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, MaxSize)]
+pub struct Welcome {
+    pub magic: [u8; 4],
+    pub protocol_version: u16,
+    /// Версия прошивки.
+    pub firmware_version: Version,
+    /// Идентификатор назначенной сессии.
+    pub session: SessionId,
+    /// Количество осей манипулятора.
+    pub axes: u8,
+    /// Команды, которые поддерживает прошивка. На остальные команды
+    /// прошивка отвечает `Rejection::Unsupported`.
+    pub commands: CommandSet,
+    /// Свободное место в очереди позиционирования: начальные кредиты
+    /// клиента (см. [`crate::flow`]).
+    pub free_slots: u8,
+    /// В предыдущей сессии сработал сторожевой таймер: движение было
+    /// остановлено, а очередь очищена. В новой сессии таймер отключен.
+    pub watchdog_tripped: bool,
+}
+
+impl Welcome {
+    /// Прошивка говорит на текущей версии протокола.
+    pub fn is_compatible(&self) -> bool {
+        self.magic == MAGIC && self.protocol_version == PROTOCOL_VERSION
+    }
+}
+
+/// Набор команд [`Command`], заданный битовой маской по их номерам.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, MaxSize)]
+pub struct CommandSet(u32);
+
+const _: () = assert!(Command::COUNT <= u32::BITS as usize);
+
+impl CommandSet {
+    /// Пустой набор.
+    pub const EMPTY: Self = Self(0);
+
+    /// Все команды текущей версии.
+    pub const ALL: Self = Self(u32::MAX >> (u32::BITS as usize - Command::COUNT));
+
+    /// Набор содержит команду.
+    #[inline]
+    pub fn contains(&self, command: &Command) -> bool {
+        self.0 & (1 << command.index()) != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        calibration::ServoCalibration,
+        quantities::{Axis, Quantity},
+        units::{Radians, RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared},
+        wifi_config::WifiConfig,
+    };
+
+    /// Все варианты команд в порядке объявления.
+    fn all_commands() -> [Command; Command::COUNT] {
+        fn axes<T: Copy>(value: T) -> Quantity<T> {
+            Quantity {
+                rotation: value,
+                shoulder: value,
+                forearm: value,
+                claw: value,
+            }
+        }
+        let radians = axes(Radians::new(1.0));
+        [
+            Command::SetMaxSpeed(axes(RadiansPerSecond::new(1.0))),
+            Command::ConfigureWifi(WifiConfig::default()),
+            Command::SetInitPosition(radians),
+            Command::SetMaxAcceleration(axes(RadiansPerSecondSquared::new(1.0))),
+            Command::SetMaxJerk(axes(RadiansPerSecondCubed::new(1.0))),
+            Command::SetMotionProfile(Default::default()),
+            Command::SetBlendTolerance(Radians::new(0.0)),
+            Command::SetCalibration(Axis::Claw, ServoCalibration::default()),
+            Command::TestCalibration(Axis::Forearm, ServoCalibration::default()),
+            Command::GetCalibration,
+            Command::SetJointLimits(Default::default()),
+        ]
+    }
+
+    #[test]
+    fn test_command_index_matches_encoding() {
+        for (index, command) in all_commands().into_iter().enumerate() {
+            let bytes = crate::to_vec::<_, { Command::POSTCARD_MAX_SIZE }>(&command).unwrap();
+            assert_eq!(bytes[0] as usize, index);
+            assert_eq!(command.index(), index);
+            assert_eq!(crate::from_bytes::<Command>(&bytes), Ok(command));
+        }
+    }
+
+    #[test]
+    fn test_command_set_covers_commands() {
+        for command in all_commands() {
+            assert!(CommandSet::ALL.contains(&command));
+            assert!(!CommandSet::EMPTY.contains(&command));
+        }
+        // Код первого несуществующего варианта не декодируется.
+        let unknown = [Command::COUNT as u8];
+        assert!(crate::from_bytes::<Command>(&unknown).is_err());
+        assert_eq!(CommandSet::ALL.0.count_ones() as usize, Command::COUNT);
+    }
+
+    #[test]
+    fn test_version_prefix_is_stable() {
+        let hello = Hello::new(Version::try_from("1.0.0").unwrap());
+        assert!(hello.is_compatible());
+        let bytes = crate::to_vec::<_, { Hello::POSTCARD_MAX_SIZE }>(&hello).unwrap();
+        assert_eq!(
+            &bytes[..5],
+            &[b'R', b'A', b'R', b'M', PROTOCOL_VERSION as u8]
+        );
+
+        // Кадр другой версии разбирается, но признается несовместимым.
+        let mut other = bytes.clone();
+        other[4] = PROTOCOL_VERSION as u8 + 1;
+        let other = crate::from_bytes::<Hello>(&other).unwrap();
+        assert!(!other.is_compatible());
+    }
+}
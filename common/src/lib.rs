@@ -1,8 +1,18 @@
 #![no_std]
+pub mod actuator;
+pub mod api;
+pub mod calibration;
+pub mod configurator;
+pub mod envelope;
+pub mod flow;
+pub mod framing;
+pub mod handshake;
 pub mod mechanics_config;
+pub mod motion;
 pub mod quantities;
 pub mod request;
 pub mod response;
+pub mod session;
 pub mod units;
 pub mod wifi_config;
 
@@ -28,6 +38,13 @@ impl<const N: usize> String<N> {
     }
 }
 
+impl<const N: usize> Default for String<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const N: usize> MaxSize for String<N> {
     const POSTCARD_MAX_SIZE: usize = N + varint_size(N);
 }
@@ -50,6 +67,64 @@ impl<'a, const N: usize> TryFrom<&'a str> for String<N> {
     }
 }
 
+/// Вектор ограниченной емкости с известным максимальным размером сообщения.
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Vec<T, const N: usize>(heapless::Vec<T, N>);
+
+impl<T, const N: usize> Vec<T, N> {
+    #[inline]
+    pub const fn new() -> Self {
+        Self(heapless::Vec::new())
+    }
+
+    /// Добавляет элемент в конец. При нехватке места возвращает его обратно.
+    #[inline]
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        self.0.push(item)
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        self.0.as_slice()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<T, const N: usize> Default for Vec<T, N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: MaxSize, const N: usize> MaxSize for Vec<T, N> {
+    const POSTCARD_MAX_SIZE: usize = T::POSTCARD_MAX_SIZE * N + varint_size(N);
+}
+
+impl<T, const N: usize> From<heapless::Vec<T, N>> for Vec<T, N> {
+    #[inline]
+    fn from(value: heapless::Vec<T, N>) -> Self {
+        Self(value)
+    }
+}
+
+impl<'a, T: Clone, const N: usize> TryFrom<&'a [T]> for Vec<T, N> {
+    type Error = CapacityError;
+    #[inline]
+    fn try_from(items: &'a [T]) -> Result<Self, Self::Error> {
+        heapless::Vec::from_slice(items).map(Self)
+    }
+}
+
 /// Вычисляет количество байт, необходимых для кодирования длины n в формате LEB128 (varint).
 /// Применимо для длин строк и векторов в postcard.
 #[inline]
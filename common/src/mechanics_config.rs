@@ -1,4 +1,10 @@
-use crate::quantities::{Position, Velocity};
+use crate::{
+    motion::{MotionLimits, MotionProfile},
+    quantities::{Acceleration, Axis, Jerk, Position, Quantity, Velocity},
+    response::Parameter,
+    units::{Radians, RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared, WithUnit},
+};
+use core::{cmp::Ordering, f32::consts::PI};
 use postcard::experimental::max_size::MaxSize;
 use serde::{Deserialize, Serialize};
 
@@ -10,4 +16,283 @@ pub struct StartupMechanicsConfig {
 
     /// Ограничение максимальной скорости перемещения по осям.
     pub max_speed: Velocity,
+
+    /// Ограничение максимального ускорения по осям.
+    pub max_acceleration: Acceleration,
+
+    /// Ограничение максимального рывка по осям (для S-образного профиля).
+    pub max_jerk: Jerk,
+
+    /// Профиль скорости, используемый при перемещениях.
+    pub profile: MotionProfile,
+
+    /// Допустимое отклонение траектории от промежуточных точек по каждой оси
+    /// при скруглении углов. Нулевое значение отключает скругление.
+    pub blend_tolerance: Radians,
+
+    /// Программные ограничения углов сочленений.
+    pub joint_limits: JointLimits,
+}
+
+impl StartupMechanicsConfig {
+    /// Проверяет допустимость всех параметров.
+    ///
+    /// Возвращает первый недопустимый параметр.
+    pub fn validate(&self) -> Result<(), Parameter> {
+        let finite = |q: [f32; 4]| q.iter().all(|v| v.is_finite());
+        let positive = |q: [f32; 4]| q.iter().all(|v| v.is_finite() && *v > 0.0);
+
+        let checks = [
+            (finite(axes(self.init_position)), Parameter::InitPosition),
+            (positive(axes(self.max_speed)), Parameter::MaxSpeed),
+            (
+                positive(axes(self.max_acceleration)),
+                Parameter::MaxAcceleration,
+            ),
+            (positive(axes(self.max_jerk)), Parameter::MaxJerk),
+            (
+                f32::from(self.blend_tolerance) >= 0.0
+                    && f32::from(self.blend_tolerance).is_finite(),
+                Parameter::BlendTolerance,
+            ),
+            (self.joint_limits.is_valid(), Parameter::JointLimits),
+            // Начальная позиция вне ограничений заблокировала бы любое
+            // перемещение, кроме возврата в пределы.
+            (
+                self.joint_limits.check(&self.init_position).is_ok(),
+                Parameter::InitPosition,
+            ),
+        ];
+        match checks.into_iter().find(|(ok, _)| !ok) {
+            Some((_, parameter)) => Err(parameter),
+            None => Ok(()),
+        }
+    }
+
+    /// Кинематические ограничения осей.
+    #[inline]
+    pub fn limits(&self) -> MotionLimits {
+        MotionLimits {
+            max_speed: self.max_speed,
+            max_acceleration: self.max_acceleration,
+            max_jerk: self.max_jerk,
+        }
+    }
+}
+
+impl Default for StartupMechanicsConfig {
+    fn default() -> Self {
+        Self {
+            init_position: Position {
+                rotation: Radians::new(1.57),
+                shoulder: Radians::new(1.3),
+                forearm: Radians::new(0.7),
+                claw: Radians::new(2.5),
+            },
+            max_speed: Velocity {
+                rotation: RadiansPerSecond::new(PI / 3.0),
+                shoulder: RadiansPerSecond::new(PI / 2.0),
+                forearm: RadiansPerSecond::new(PI / 2.0),
+                claw: RadiansPerSecond::new(PI),
+            },
+            max_acceleration: Acceleration {
+                rotation: RadiansPerSecondSquared::new(PI / 2.0),
+                shoulder: RadiansPerSecondSquared::new(PI),
+                forearm: RadiansPerSecondSquared::new(PI),
+                claw: RadiansPerSecondSquared::new(2.0 * PI),
+            },
+            max_jerk: Jerk {
+                rotation: RadiansPerSecondCubed::new(2.0 * PI),
+                shoulder: RadiansPerSecondCubed::new(4.0 * PI),
+                forearm: RadiansPerSecondCubed::new(4.0 * PI),
+                claw: RadiansPerSecondCubed::new(8.0 * PI),
+            },
+            profile: MotionProfile::Trapezoidal,
+            blend_tolerance: Radians::new(0.05),
+            joint_limits: JointLimits::default(),
+        }
+    }
+}
+
+/// Программные ограничения углов сочленений по осям.
+///
+/// Целевые позиции за пределами `[min, max]` отклоняются до постановки в очередь.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize, MaxSize)]
+pub struct JointLimits {
+    /// Нижние границы углов.
+    pub min: Position,
+
+    /// Верхние границы углов.
+    pub max: Position,
+}
+
+impl JointLimits {
+    /// Границы конечны и нижняя не превышает верхнюю по каждой оси.
+    pub fn is_valid(&self) -> bool {
+        Axis::ALL.into_iter().all(|axis| {
+            let (min, max) = (
+                f32::from(*self.min.get(axis)),
+                f32::from(*self.max.get(axis)),
+            );
+            min.is_finite() && max.is_finite() && min <= max
+        })
+    }
+
+    /// Проверяет, что позиция находится в допустимых пределах по всем осям.
+    ///
+    /// Возвращает нарушение для первой оси, вышедшей за пределы.
+    pub fn check(&self, position: &Position) -> Result<(), LimitViolation> {
+        for axis in Axis::ALL {
+            let value = *position.get(axis);
+            let (min, max) = (*self.min.get(axis), *self.max.get(axis));
+
+            // Несравнимое значение (`NaN`) также считается нарушением.
+            let violation = match (value.partial_cmp(&min), value.partial_cmp(&max)) {
+                (Some(Ordering::Less) | None, _) => Some((Bound::Lower, min)),
+                (_, Some(Ordering::Greater) | None) => Some((Bound::Upper, max)),
+                _ => None,
+            };
+
+            if let Some((bound, limit)) = violation {
+                return Err(LimitViolation {
+                    axis,
+                    bound,
+                    limit,
+                    value,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for JointLimits {
+    /// Полный диапазон стандартного сервопривода `[0, π]`.
+    fn default() -> Self {
+        let all = |angle| Position {
+            rotation: angle,
+            shoulder: angle,
+            forearm: angle,
+            claw: angle,
+        };
+        Self {
+            min: all(Radians::new(0.0)),
+            max: all(Radians::new(PI)),
+        }
+    }
+}
+
+/// Значения по осям.
+fn axes<Unit>(q: Quantity<WithUnit<Unit>>) -> [f32; 4] {
+    [
+        q.rotation.into(),
+        q.shoulder.into(),
+        q.forearm.into(),
+        q.claw.into(),
+    ]
+}
+
+/// Граница допустимого диапазона.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize, MaxSize)]
+pub enum Bound {
+    Lower,
+    Upper,
+}
+
+/// Выход целевой позиции за программные ограничения.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize, MaxSize)]
+pub struct LimitViolation {
+    /// Ось, нарушившая ограничение.
+    pub axis: Axis,
+
+    /// Нарушенная граница.
+    pub bound: Bound,
+
+    /// Значение границы.
+    pub limit: Radians,
+
+    /// Запрошенный угол.
+    pub value: Radians,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(r: f32, s: f32, f: f32, c: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(f),
+            claw: Radians::new(c),
+        }
+    }
+
+    fn limits() -> JointLimits {
+        JointLimits {
+            min: pos(0.0, 0.5, 0.0, 1.0),
+            max: pos(3.0, 2.5, 3.0, 2.0),
+        }
+    }
+
+    #[test]
+    fn test_within_limits() {
+        assert_eq!(limits().check(&pos(1.0, 1.0, 1.0, 1.5)), Ok(()));
+        // Границы включаются в допустимый диапазон.
+        assert_eq!(limits().check(&pos(0.0, 0.5, 3.0, 2.0)), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_axis_and_bound() {
+        assert_eq!(
+            limits().check(&pos(1.0, 0.4, 1.0, 1.5)),
+            Err(LimitViolation {
+                axis: Axis::Shoulder,
+                bound: Bound::Lower,
+                limit: Radians::new(0.5),
+                value: Radians::new(0.4),
+            })
+        );
+        assert_eq!(
+            limits().check(&pos(1.0, 1.0, 1.0, 2.5)),
+            Err(LimitViolation {
+                axis: Axis::Claw,
+                bound: Bound::Upper,
+                limit: Radians::new(2.0),
+                value: Radians::new(2.5),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = StartupMechanicsConfig::default();
+        assert_eq!(config.validate(), Ok(()));
+
+        let mut bad = config.clone();
+        bad.max_speed.claw = RadiansPerSecond::new(0.0);
+        assert_eq!(bad.validate(), Err(Parameter::MaxSpeed));
+
+        let mut bad = config.clone();
+        bad.max_jerk.rotation = RadiansPerSecondCubed::new(f32::INFINITY);
+        assert_eq!(bad.validate(), Err(Parameter::MaxJerk));
+
+        let mut bad = config.clone();
+        bad.blend_tolerance = Radians::new(-0.1);
+        assert_eq!(bad.validate(), Err(Parameter::BlendTolerance));
+
+        let mut bad = config.clone();
+        bad.joint_limits.min.forearm = Radians::new(3.5);
+        assert_eq!(bad.validate(), Err(Parameter::JointLimits));
+
+        let mut bad = config;
+        bad.init_position.shoulder = bad.joint_limits.max.shoulder + Radians::new(0.1);
+        assert_eq!(bad.validate(), Err(Parameter::InitPosition));
+    }
+
+    #[test]
+    fn test_nan_is_rejected() {
+        let err = limits().check(&pos(f32::NAN, 1.0, 1.0, 1.5)).unwrap_err();
+        assert_eq!(err.axis, Axis::Rotation);
+    }
 }
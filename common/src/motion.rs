@@ -0,0 +1,557 @@
+//! # Motion Profiles
+//!
+//! Профили движения манипулятора между двумя позициями.
+//!
+//! Все оси движутся синхронно по прямой в пространстве сочленений: положение
+//! описывается нормированным параметром пути `s(t) ∈ [0, 1]`, общим для всех осей.
+//! Ограничения каждой оси пересчитываются в ограничения на `s(t)`, поэтому ни одна
+//! ось не превышает своих пределов, а все оси начинают и заканчивают движение
+//! одновременно.
+
+use crate::{
+    quantities::{Acceleration, Jerk, MaxAbsComponent, Position, Quantity, Velocity},
+    units::{Radians, Seconds, WithUnit},
+};
+use postcard::experimental::max_size::MaxSize;
+use s_curve::SCurve;
+use serde::{Deserialize, Serialize};
+
+pub mod blend;
+pub mod executor;
+pub mod hold;
+pub mod jog;
+pub mod positioner;
+mod s_curve;
+pub mod spline;
+
+/// Форма профиля скорости при перемещении.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize, MaxSize)]
+pub enum MotionProfile {
+    /// Постоянная скорость на всем участке (мгновенный разгон и остановка).
+    ConstantVelocity,
+
+    /// Трапециевидный профиль: разгон с ограниченным ускорением, движение
+    /// с постоянной скоростью и торможение.
+    #[default]
+    Trapezoidal,
+
+    /// S-образный профиль: ускорение нарастает и спадает с ограниченным рывком,
+    /// что исключает скачки ускорения.
+    SCurve,
+}
+
+/// Темп перемещения.
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize, MaxSize)]
+pub enum Timing {
+    /// Перемещение за заданное время. Время не может быть меньше, чем
+    /// позволяют ограничения осей.
+    Duration(Seconds),
+
+    /// Движение с долей предельных скоростей осей в диапазоне `(0, 1]`.
+    SpeedScale(f32),
+}
+
+impl Timing {
+    /// Проверяет, что время конечно и положительно, а доля скорости лежит
+    /// в диапазоне `(0, 1]`.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Self::Duration(duration) => {
+                let duration = f32::from(duration);
+                duration.is_finite() && duration > 0.0
+            }
+            Self::SpeedScale(scale) => scale > 0.0 && scale <= 1.0,
+        }
+    }
+
+    /// Ограничения, с которыми перемещение от `src` до `dst` идет в этом темпе.
+    ///
+    /// Профиль замедляется целиком, поэтому ограничения `limits` не
+    /// превышаются. Если заданное время меньше наименьшего достижимого,
+    /// возвращает наименьшее.
+    pub fn limits(
+        &self,
+        src: Position,
+        dst: Position,
+        profile: MotionProfile,
+        limits: &MotionLimits,
+    ) -> Result<MotionLimits, Seconds> {
+        let scale = match *self {
+            Self::SpeedScale(scale) => scale,
+            Self::Duration(duration) => {
+                let minimum = Segment::new(src, dst, profile, limits).duration();
+                if duration < minimum {
+                    return Err(minimum);
+                }
+                // Нулевой путь проходится мгновенно при любом темпе.
+                if minimum <= Seconds::new(0.0) {
+                    return Ok(*limits);
+                }
+                minimum / duration
+            }
+        };
+        Ok(limits.slowed(scale))
+    }
+}
+
+/// Кинематические ограничения осей.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionLimits {
+    /// Максимальные скорости осей.
+    pub max_speed: Velocity,
+
+    /// Максимальные ускорения осей.
+    pub max_acceleration: Acceleration,
+
+    /// Максимальные рывки осей.
+    pub max_jerk: Jerk,
+}
+
+impl MotionLimits {
+    /// Ограничения для движения, замедленного в `1 / scale` раз: скорости
+    /// умножаются на `scale`, ускорения — на `scale²`, рывки — на `scale³`.
+    /// Форма любого профиля при этом сохраняется, а длительность растет
+    /// в `1 / scale` раз.
+    pub fn slowed(&self, scale: f32) -> Self {
+        Self {
+            max_speed: self.max_speed * scale,
+            max_acceleration: self.max_acceleration * (scale * scale),
+            max_jerk: self.max_jerk * (scale * scale * scale),
+        }
+    }
+}
+
+/// Участок траектории между двумя позициями.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment {
+    src: Position,
+    delta: Position,
+    shape: Shape,
+}
+
+impl Segment {
+    /// Строит участок от `src` до `dst` с заданным профилем, соблюдая ограничения
+    /// всех осей.
+    pub fn new(
+        src: Position,
+        dst: Position,
+        profile: MotionProfile,
+        limits: &MotionLimits,
+    ) -> Self {
+        let delta = dst - src;
+
+        // Время прохождения пути на максимальной скорости самой медленной осью.
+        let k_v = max_ratio(delta, limits.max_speed);
+
+        let shape = match profile {
+            MotionProfile::ConstantVelocity => Shape::trapezoid(k_v, 0.0),
+            MotionProfile::Trapezoidal => {
+                Shape::trapezoid(k_v, max_ratio(delta, limits.max_acceleration))
+            }
+            MotionProfile::SCurve => Shape::s_curve(
+                k_v,
+                max_ratio(delta, limits.max_acceleration),
+                max_ratio(delta, limits.max_jerk),
+            ),
+        };
+
+        Self { src, delta, shape }
+    }
+
+    /// Начальная позиция участка.
+    #[inline]
+    pub fn source(&self) -> Position {
+        self.src
+    }
+
+    /// Конечная позиция участка.
+    #[inline]
+    pub fn target(&self) -> Position {
+        self.src + self.delta
+    }
+
+    /// Длительность движения по участку.
+    #[inline]
+    pub fn duration(&self) -> Seconds {
+        Seconds::new(self.shape.duration())
+    }
+
+    /// Позиция в момент времени `t` от начала участка.
+    ///
+    /// За пределами участка возвращает начальную или конечную позицию.
+    pub fn position_at(&self, t: Seconds) -> Position {
+        let s = self.shape.at(t.into());
+        if s >= 1.0 {
+            return self.target();
+        }
+        self.src + self.delta * s
+    }
+
+    /// Длительность разгона (и торможения) на участке.
+    #[inline]
+    pub fn ramp_time(&self) -> Seconds {
+        Seconds::new(self.shape.ramp_time())
+    }
+
+    /// Время, за которое участок проходит путь `distance` (по наиболее
+    /// нагруженной оси) от начала. Профили симметричны, поэтому столько же
+    /// длится прохождение последних `distance` перед концом участка.
+    ///
+    /// Результат не превышает половины длительности участка.
+    pub fn time_to_travel(&self, distance: Radians) -> Seconds {
+        let length = f32::from(self.delta.max_abs_component());
+        if length <= 0.0 {
+            return Seconds::new(0.0);
+        }
+        let fraction = libm::fminf(f32::from(distance) / length, 0.5);
+        Seconds::new(self.shape.time_at(fraction))
+    }
+
+    /// Скорость, ускорение и рывок осей в момент времени `t` от начала участка.
+    #[cfg(test)]
+    fn derivatives_at(&self, t: Seconds) -> (Velocity, Acceleration, Jerk) {
+        let [_, v, a, j] = self.shape.kinematics(t.into());
+        (
+            along(self.delta, v),
+            along(self.delta, a),
+            along(self.delta, j),
+        )
+    }
+}
+
+/// Закон изменения нормированного параметра пути `s(t)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Shape {
+    /// Трапеция скорости. Постоянная скорость — вырожденный случай с нулевым
+    /// временем разгона.
+    Trapezoid {
+        /// Максимальная скорость изменения `s`, 1/с.
+        peak: f32,
+        /// Время разгона (и торможения), с.
+        t_acc: f32,
+        /// Полная длительность, с.
+        total: f32,
+    },
+
+    /// S-образный профиль с ограниченным рывком.
+    SCurve(SCurve),
+}
+
+impl Shape {
+    /// Строит трапецию по приведенным ограничениям.
+    ///
+    /// * `k_v` — наибольшее по осям `|Δ| / v_max`, с.
+    /// * `k_a` — наибольшее по осям `|Δ| / a_max`, с². Ноль означает
+    ///   неограниченное ускорение.
+    fn trapezoid(k_v: f32, k_a: f32) -> Self {
+        if k_v <= 0.0 {
+            return Self::Trapezoid {
+                peak: 0.0,
+                t_acc: 0.0,
+                total: 0.0,
+            };
+        }
+
+        // Время разгона до максимальной скорости.
+        let t_acc = k_a / k_v;
+        if t_acc < k_v {
+            Self::Trapezoid {
+                peak: 1.0 / k_v,
+                t_acc,
+                total: k_v + t_acc,
+            }
+        } else {
+            // Путь слишком короткий, чтобы разогнаться: треугольный профиль.
+            let t_acc = libm::sqrtf(k_a);
+            Self::Trapezoid {
+                peak: 1.0 / t_acc,
+                t_acc,
+                total: 2.0 * t_acc,
+            }
+        }
+    }
+
+    /// Строит S-образный профиль по приведенным ограничениям.
+    ///
+    /// * `k_j` — наибольшее по осям `|Δ| / j_max`, с³. Ноль означает
+    ///   неограниченный рывок, и профиль вырождается в трапецию.
+    fn s_curve(k_v: f32, k_a: f32, k_j: f32) -> Self {
+        if k_v <= 0.0 || k_j <= 0.0 {
+            return Self::trapezoid(k_v, k_a);
+        }
+        Self::SCurve(SCurve::new(k_v, k_a, k_j))
+    }
+
+    fn duration(&self) -> f32 {
+        match self {
+            Self::Trapezoid { total, .. } => *total,
+            Self::SCurve(curve) => curve.duration(),
+        }
+    }
+
+    /// Значение `s(t)` и его производные по времени: `[s, s', s'', s''']`.
+    fn kinematics(&self, t: f32) -> [f32; 4] {
+        match self {
+            &Self::Trapezoid { peak, t_acc, total } => {
+                if t >= total {
+                    [1.0, 0.0, 0.0, 0.0]
+                } else if t <= 0.0 {
+                    [0.0; 4]
+                } else if t < t_acc {
+                    let a = peak / t_acc;
+                    [0.5 * a * t * t, a * t, a, 0.0]
+                } else if t <= total - t_acc {
+                    [peak * (t - 0.5 * t_acc), peak, 0.0, 0.0]
+                } else {
+                    let a = peak / t_acc;
+                    let r = total - t;
+                    [1.0 - 0.5 * a * r * r, a * r, -a, 0.0]
+                }
+            }
+            Self::SCurve(curve) => curve.kinematics(t),
+        }
+    }
+
+    fn at(&self, t: f32) -> f32 {
+        self.kinematics(t)[0]
+    }
+
+    fn ramp_time(&self) -> f32 {
+        match self {
+            Self::Trapezoid { t_acc, .. } => *t_acc,
+            Self::SCurve(curve) => curve.ramp_time(),
+        }
+    }
+
+    /// Момент времени, в который `s(t)` достигает значения `s`.
+    fn time_at(&self, s: f32) -> f32 {
+        // `s(t)` монотонна, поэтому достаточно деления отрезка пополам.
+        let (mut lo, mut hi) = (0.0, self.duration());
+        for _ in 0..32 {
+            let mid = 0.5 * (lo + hi);
+            if self.at(mid) < s {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        hi
+    }
+}
+
+/// Производная положения вдоль прямой `delta` по производной параметра пути.
+#[cfg(test)]
+fn along<Unit>(delta: Position, k: f32) -> Quantity<WithUnit<Unit>> {
+    Quantity {
+        rotation: WithUnit::new(f32::from(delta.rotation) * k),
+        shoulder: WithUnit::new(f32::from(delta.shoulder) * k),
+        forearm: WithUnit::new(f32::from(delta.forearm) * k),
+        claw: WithUnit::new(f32::from(delta.claw) * k),
+    }
+}
+
+/// Наибольшее по осям отношение длины пути к ограничению `|Δ| / limit`.
+fn max_ratio<Unit>(delta: Position, limit: Quantity<WithUnit<Unit>>) -> f32 {
+    let ratio = |d: f32, l: f32| libm::fabsf(d) / libm::fabsf(l);
+    let r = ratio(delta.rotation.into(), limit.rotation.into());
+    let r = libm::fmaxf(r, ratio(delta.shoulder.into(), limit.shoulder.into()));
+    let r = libm::fmaxf(r, ratio(delta.forearm.into(), limit.forearm.into()));
+    libm::fmaxf(r, ratio(delta.claw.into(), limit.claw.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::{RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared};
+
+    const EPS: f32 = 1e-3;
+
+    fn pos(r: f32, s: f32, f: f32, c: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(f),
+            claw: Radians::new(c),
+        }
+    }
+
+    fn limits(v: f32, a: f32) -> MotionLimits {
+        let j = RadiansPerSecondCubed::new(1.0);
+        MotionLimits {
+            max_speed: Velocity {
+                rotation: RadiansPerSecond::new(v),
+                shoulder: RadiansPerSecond::new(v),
+                forearm: RadiansPerSecond::new(v),
+                claw: RadiansPerSecond::new(v),
+            },
+            max_acceleration: Acceleration {
+                rotation: RadiansPerSecondSquared::new(a),
+                shoulder: RadiansPerSecondSquared::new(a),
+                forearm: RadiansPerSecondSquared::new(a),
+                claw: RadiansPerSecondSquared::new(a),
+            },
+            max_jerk: Jerk {
+                rotation: j,
+                shoulder: j,
+                forearm: j,
+                claw: j,
+            },
+        }
+    }
+
+    fn assert_close(a: Position, b: Position) {
+        let d = (a - b).max_abs_component();
+        assert!(f32::from(d) < EPS, "{a:?} != {b:?}");
+    }
+
+    /// Проверяет, что численные скорость и ускорение каждой оси не выходят
+    /// за пределы ограничений.
+    fn assert_within_limits(seg: &Segment, v: f32, a: f32) {
+        let dt = 1e-3;
+        let steps = (f32::from(seg.duration()) / dt) as usize + 2;
+        let axes = |p: Position| -> [f32; 4] {
+            [
+                p.rotation.into(),
+                p.shoulder.into(),
+                p.forearm.into(),
+                p.claw.into(),
+            ]
+        };
+        let sample = |i: usize| axes(seg.position_at(Seconds::new(i as f32 * dt)));
+
+        for i in 1..steps {
+            let (p0, p1, p2) = (sample(i - 1), sample(i), sample(i + 1));
+            for ax in 0..4 {
+                let vel = (p2[ax] - p1[ax]) / dt;
+                let acc = (p2[ax] - 2.0 * p1[ax] + p0[ax]) / (dt * dt);
+                assert!(
+                    libm::fabsf(vel) <= v * (1.0 + EPS) + EPS,
+                    "vel {vel} at {i}"
+                );
+                assert!(libm::fabsf(acc) <= a * 1.05 + 1.0, "acc {acc} at {i}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_constant_velocity_duration() {
+        let seg = Segment::new(
+            pos(0.0, 0.0, 0.0, 0.0),
+            pos(1.0, 2.0, 0.5, 0.0),
+            MotionProfile::ConstantVelocity,
+            &limits(1.0, 1.0),
+        );
+        assert!(libm::fabsf(f32::from(seg.duration()) - 2.0) < EPS);
+        assert_close(seg.position_at(Seconds::new(1.0)), pos(0.5, 1.0, 0.25, 0.0));
+    }
+
+    #[test]
+    fn test_trapezoid_duration() {
+        // Разгон до 1 рад/с занимает 0.5 с и 0.25 рад, столько же торможение.
+        let seg = Segment::new(
+            pos(0.0, 0.0, 0.0, 0.0),
+            pos(2.0, 0.0, 0.0, 0.0),
+            MotionProfile::Trapezoidal,
+            &limits(1.0, 2.0),
+        );
+        assert!(libm::fabsf(f32::from(seg.duration()) - 2.5) < EPS);
+        assert_within_limits(&seg, 1.0, 2.0);
+    }
+
+    #[test]
+    fn test_triangle_duration() {
+        // Пик скорости не достигается: 2 * sqrt(0.5 / 2).
+        let seg = Segment::new(
+            pos(0.0, 0.0, 0.0, 0.0),
+            pos(0.0, -0.5, 0.0, 0.0),
+            MotionProfile::Trapezoidal,
+            &limits(10.0, 2.0),
+        );
+        assert!(libm::fabsf(f32::from(seg.duration()) - 1.0) < EPS);
+        assert_within_limits(&seg, 10.0, 2.0);
+    }
+
+    #[test]
+    fn test_axes_are_synchronized() {
+        let src = pos(0.0, 1.0, 2.0, 3.0);
+        let dst = pos(1.0, -1.0, 2.5, 3.0);
+        let seg = Segment::new(src, dst, MotionProfile::Trapezoidal, &limits(1.0, 1.0));
+
+        // Все оси проходят одинаковую долю пути в любой момент времени.
+        let half = seg.position_at(seg.duration() * 0.5);
+        assert_close(half, src + (dst - src) * 0.5);
+        assert_within_limits(&seg, 1.0, 1.0);
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let src = pos(0.3, 1.0, 2.0, 3.0);
+        let dst = pos(1.0, -1.0, 2.5, 3.0);
+        let seg = Segment::new(src, dst, MotionProfile::Trapezoidal, &limits(1.0, 1.0));
+
+        assert_eq!(seg.position_at(Seconds::new(0.0)), src);
+        assert_eq!(seg.position_at(seg.duration()), dst);
+        assert_eq!(seg.position_at(seg.duration() * 2.0), dst);
+    }
+
+    #[test]
+    fn test_speed_scale_stretches_duration() {
+        let (src, dst) = (pos(0.0, 0.0, 0.0, 0.0), pos(2.0, 1.0, 0.0, 0.0));
+        for profile in [
+            MotionProfile::ConstantVelocity,
+            MotionProfile::Trapezoidal,
+            MotionProfile::SCurve,
+        ] {
+            let full = Segment::new(src, dst, profile, &limits(1.0, 2.0));
+            let scaled = Timing::SpeedScale(0.25)
+                .limits(src, dst, profile, &limits(1.0, 2.0))
+                .unwrap();
+            let seg = Segment::new(src, dst, profile, &scaled);
+            let ratio = seg.duration() / full.duration();
+            assert!(libm::fabsf(ratio - 4.0) < EPS, "{profile:?}: {ratio}");
+        }
+    }
+
+    #[test]
+    fn test_timed_segment() {
+        let (src, dst) = (pos(0.0, 0.0, 0.0, 0.0), pos(2.0, 0.0, 0.0, 0.0));
+        let profile = MotionProfile::Trapezoidal;
+        let timed = Timing::Duration(Seconds::new(5.0))
+            .limits(src, dst, profile, &limits(1.0, 2.0))
+            .unwrap();
+        let seg = Segment::new(src, dst, profile, &timed);
+        assert!(libm::fabsf(f32::from(seg.duration()) - 5.0) < EPS);
+        assert_within_limits(&seg, 1.0, 2.0);
+
+        // Быстрее 2.5 с ограничения не позволяют.
+        let minimum = Timing::Duration(Seconds::new(2.0))
+            .limits(src, dst, profile, &limits(1.0, 2.0))
+            .unwrap_err();
+        assert!(libm::fabsf(f32::from(minimum) - 2.5) < EPS);
+
+        // Нулевой путь проходится мгновенно.
+        let timed =
+            Timing::Duration(Seconds::new(1.0)).limits(src, src, profile, &limits(1.0, 2.0));
+        assert_eq!(timed, Ok(limits(1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_timing_validity() {
+        assert!(Timing::SpeedScale(1.0).is_valid());
+        assert!(!Timing::SpeedScale(1.5).is_valid());
+        assert!(!Timing::SpeedScale(0.0).is_valid());
+        assert!(!Timing::SpeedScale(f32::NAN).is_valid());
+        assert!(Timing::Duration(Seconds::new(0.5)).is_valid());
+        assert!(!Timing::Duration(Seconds::new(-1.0)).is_valid());
+        assert!(!Timing::Duration(Seconds::new(f32::INFINITY)).is_valid());
+    }
+
+    #[test]
+    fn test_zero_length_segment() {
+        let p = pos(0.3, 1.0, 2.0, 3.0);
+        let seg = Segment::new(p, p, MotionProfile::Trapezoidal, &limits(1.0, 1.0));
+
+        assert_eq!(seg.duration(), Seconds::new(0.0));
+        assert_eq!(seg.position_at(Seconds::new(0.0)), p);
+    }
+}
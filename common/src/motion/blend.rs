@@ -0,0 +1,501 @@
+//! # Path Blending
+//!
+//! Непрерывное движение через последовательность целевых точек.
+//!
+//! Вместо полной остановки в каждой точке следующий участок запускается
+//! до завершения текущего, и на время перекрытия их перемещения складываются
+//! (суперпозиция). Угол траектории скругляется, а отклонение от угловой точки
+//! по каждой оси не превышает заданного допуска: каждый участок проходит за время
+//! перекрытия не более половины допуска.
+//!
+//! Перекрытие также не длиннее разгона и торможения участков, поэтому
+//! скорости складываются только на участках их нарастания и спада. При нулевом
+//! допуске или профиле с постоянной скоростью скругление отключается.
+//!
+//! Сплайны сами по себе гладкие и начинаются и заканчиваются в покое, поэтому
+//! с соседними участками не перекрываются.
+
+use super::{MotionLimits, MotionProfile, Segment, spline::Spline};
+use crate::{
+    quantities::Position,
+    units::{Radians, Seconds},
+};
+
+/// Участок траектории.
+// Без аллокатора сплайн хранится по значению, в очереди не больше двух участков.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+enum Path {
+    Segment(Segment),
+    Spline(Spline),
+}
+
+impl Path {
+    fn source(&self) -> Position {
+        match self {
+            Self::Segment(segment) => segment.source(),
+            Self::Spline(spline) => spline.source(),
+        }
+    }
+
+    fn target(&self) -> Position {
+        match self {
+            Self::Segment(segment) => segment.target(),
+            Self::Spline(spline) => spline.target(),
+        }
+    }
+
+    fn duration(&self) -> Seconds {
+        match self {
+            Self::Segment(segment) => segment.duration(),
+            Self::Spline(spline) => spline.duration(),
+        }
+    }
+
+    fn position_at(&self, t: Seconds) -> Position {
+        match self {
+            Self::Segment(segment) => segment.position_at(t),
+            Self::Spline(spline) => spline.position_at(t),
+        }
+    }
+}
+
+/// Участок в процессе исполнения.
+#[derive(Clone, Debug)]
+struct Running {
+    path: Path,
+    /// Время от начала участка.
+    elapsed: Seconds,
+}
+
+/// Участок, ожидающий запуска на фоне текущего.
+#[derive(Clone, Debug)]
+struct Pending {
+    path: Path,
+    /// Момент запуска по часам текущего участка.
+    start: Seconds,
+}
+
+/// Результат одного шага исполнения.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Позиция, в которую нужно перевести приводы.
+    pub position: Position,
+
+    /// Признак того, что на этом шаге пройдена очередная целевая точка.
+    pub passed: bool,
+}
+
+/// Исполнитель траектории со скруглением углов между участками.
+#[derive(Clone, Debug)]
+pub struct Blender {
+    /// Допустимое отклонение от угловой точки по каждой оси.
+    tolerance: Radians,
+    /// Последняя запланированная цель — начало следующего участка.
+    target: Position,
+    /// Текущая позиция.
+    position: Position,
+    active: Option<Running>,
+    next: Option<Pending>,
+}
+
+impl Blender {
+    /// Создает исполнитель, находящийся в покое в позиции `position`.
+    pub fn new(position: Position, tolerance: Radians) -> Self {
+        Self {
+            tolerance,
+            target: position,
+            position,
+            active: None,
+            next: None,
+        }
+    }
+
+    /// Задает допуск скругления для следующих участков.
+    #[inline]
+    pub fn set_tolerance(&mut self, tolerance: Radians) {
+        self.tolerance = tolerance;
+    }
+
+    /// Текущая позиция.
+    #[inline]
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Цель исполняемого участка. В покое совпадает с текущей позицией.
+    #[inline]
+    pub fn active_target(&self) -> Position {
+        match &self.active {
+            Some(active) => active.path.target(),
+            None => self.position,
+        }
+    }
+
+    /// Последняя запланированная цель: от нее начнется следующий участок.
+    /// В покое совпадает с текущей позицией.
+    #[inline]
+    pub fn target(&self) -> Position {
+        self.target
+    }
+
+    /// Возвращает `true`, если движение завершено и новых участков нет.
+    #[inline]
+    pub fn is_idle(&self) -> bool {
+        self.active.is_none()
+    }
+
+    /// Возвращает `true`, если исполнитель готов принять следующую точку.
+    #[inline]
+    pub fn can_accept(&self) -> bool {
+        self.next.is_none()
+    }
+
+    /// Добавляет следующую целевую точку.
+    ///
+    /// Точка принимается, только если [`Blender::can_accept`] возвращает `true`.
+    /// Иначе она возвращается вызывающему коду.
+    pub fn push(
+        &mut self,
+        dst: Position,
+        profile: MotionProfile,
+        limits: &MotionLimits,
+    ) -> Result<(), Position> {
+        if !self.can_accept() {
+            return Err(dst);
+        }
+
+        let segment = Segment::new(self.target, dst, profile, limits);
+        self.enqueue(Path::Segment(segment));
+        Ok(())
+    }
+
+    /// Добавляет сплайн, построенный от последней целевой точки
+    /// ([`Blender::target`]).
+    ///
+    /// Сплайн принимается, только если [`Blender::can_accept`] возвращает `true`,
+    /// иначе возвращается `false`.
+    #[must_use]
+    pub fn push_spline(&mut self, spline: Spline) -> bool {
+        if !self.can_accept() {
+            return false;
+        }
+
+        self.enqueue(Path::Spline(spline));
+        true
+    }
+
+    /// Ставит участок в очередь исполнения вслед за текущим.
+    fn enqueue(&mut self, path: Path) {
+        self.target = path.target();
+
+        match self.active {
+            None => {
+                self.active = Some(Running {
+                    path,
+                    elapsed: Seconds::new(0.0),
+                })
+            }
+            Some(ref active) => {
+                let duration = active.path.duration();
+                let overlap = self.overlap(&active.path, &path);
+                // Если точка пришла поздно, перекрытие сокращается до оставшегося
+                // времени текущего участка.
+                let start = duration - overlap;
+                let start = if active.elapsed > start {
+                    active.elapsed
+                } else {
+                    start
+                };
+                self.next = Some(Pending { path, start });
+            }
+        }
+    }
+
+    /// Прерывает движение: незавершенные участки отбрасываются, манипулятор
+    /// остается в текущей позиции, и следующий участок начнется из нее.
+    ///
+    /// Прерывать движение резко можно только после остановки, например
+    /// с помощью [`super::hold::FeedHold`].
+    pub fn halt(&mut self) {
+        self.active = None;
+        self.next = None;
+        self.target = self.position;
+    }
+
+    /// Продвигает движение на интервал `dt`.
+    pub fn step(&mut self, dt: Seconds) -> Step {
+        let Some(active) = self.active.as_mut() else {
+            return Step {
+                position: self.position,
+                passed: false,
+            };
+        };
+
+        active.elapsed += dt;
+        let mut position = active.path.position_at(active.elapsed);
+
+        // Перемещение следующего участка, если он уже запущен. Без перекрытия
+        // участок стартует с нового шага, чтобы точка была пройдена точно.
+        let duration = active.path.duration();
+        let next_elapsed = self.next.as_ref().and_then(|next| {
+            (active.elapsed > next.start && next.start < duration).then(|| {
+                let elapsed = active.elapsed - next.start;
+                position += next.path.position_at(elapsed) - next.path.source();
+                elapsed
+            })
+        });
+
+        let passed = active.elapsed >= duration;
+        if passed {
+            self.active = self.next.take().map(|next| Running {
+                path: next.path,
+                elapsed: next_elapsed.unwrap_or(Seconds::new(0.0)),
+            });
+        }
+
+        self.position = position;
+        Step { position, passed }
+    }
+
+    /// Длительность перекрытия участков `a` и `b`.
+    fn overlap(&self, a: &Path, b: &Path) -> Seconds {
+        let (Path::Segment(a), Path::Segment(b)) = (a, b) else {
+            return Seconds::new(0.0);
+        };
+        let half = self.tolerance * 0.5;
+        let min = |x: Seconds, y: Seconds| if x < y { x } else { y };
+
+        let overlap = min(a.time_to_travel(half), b.time_to_travel(half));
+        min(overlap, min(a.ramp_time(), b.ramp_time()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        motion::spline::SplinePoints,
+        quantities::{Acceleration, Jerk, MaxAbsComponent, Velocity},
+        units::{RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared},
+    };
+
+    use heapless::Vec;
+
+    const DT: Seconds = Seconds::new(0.02);
+    const MAX_STEPS: usize = 1024;
+
+    fn pos(r: f32, s: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(0.0),
+            claw: Radians::new(0.0),
+        }
+    }
+
+    fn limits() -> MotionLimits {
+        MotionLimits {
+            max_speed: Velocity {
+                rotation: RadiansPerSecond::new(1.0),
+                shoulder: RadiansPerSecond::new(1.0),
+                forearm: RadiansPerSecond::new(1.0),
+                claw: RadiansPerSecond::new(1.0),
+            },
+            max_acceleration: Acceleration {
+                rotation: RadiansPerSecondSquared::new(2.0),
+                shoulder: RadiansPerSecondSquared::new(2.0),
+                forearm: RadiansPerSecondSquared::new(2.0),
+                claw: RadiansPerSecondSquared::new(2.0),
+            },
+            max_jerk: Jerk {
+                rotation: RadiansPerSecondCubed::new(10.0),
+                shoulder: RadiansPerSecondCubed::new(10.0),
+                forearm: RadiansPerSecondCubed::new(10.0),
+                claw: RadiansPerSecondCubed::new(10.0),
+            },
+        }
+    }
+
+    /// Проводит исполнитель через угол (0, 0) -> (1, 0) -> (1, 1).
+    /// Возвращает траекторию и номера шагов, на которых пройдены точки.
+    fn run_corner(
+        tolerance: f32,
+        profile: MotionProfile,
+    ) -> (Vec<Position, MAX_STEPS>, Vec<usize, 4>) {
+        let mut blender = Blender::new(pos(0.0, 0.0), Radians::new(tolerance));
+        let mut waypoints = [pos(1.0, 0.0), pos(1.0, 1.0)].into_iter();
+        let (mut path, mut passed) = (Vec::new(), Vec::new());
+
+        blender
+            .push(waypoints.next().unwrap(), profile, &limits())
+            .unwrap();
+        for i in 0..MAX_STEPS {
+            if blender.can_accept()
+                && let Some(wp) = waypoints.next()
+            {
+                blender.push(wp, profile, &limits()).unwrap();
+            }
+            let step = blender.step(DT);
+            path.push(step.position).unwrap();
+            if step.passed {
+                passed.push(i).unwrap();
+            }
+            if blender.is_idle() {
+                break;
+            }
+        }
+        (path, passed)
+    }
+
+    fn distance(a: Position, b: Position) -> f32 {
+        (a - b).max_abs_component().into()
+    }
+
+    #[test]
+    fn test_every_waypoint_is_acknowledged() {
+        for profile in [
+            MotionProfile::ConstantVelocity,
+            MotionProfile::Trapezoidal,
+            MotionProfile::SCurve,
+        ] {
+            let (path, passed) = run_corner(0.2, profile);
+            assert_eq!(passed.len(), 2);
+            assert_eq!(*passed.last().unwrap(), path.len() - 1);
+            assert_eq!(*path.last().unwrap(), pos(1.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn test_zero_tolerance_stops_at_corner() {
+        let (path, passed) = run_corner(0.0, MotionProfile::Trapezoidal);
+        assert_eq!(path[passed[0]], pos(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_corner_is_rounded_within_tolerance() {
+        let tolerance = 0.2;
+        let (sharp, _) = run_corner(0.0, MotionProfile::Trapezoidal);
+        let (path, _) = run_corner(tolerance, MotionProfile::Trapezoidal);
+
+        // Движение без остановки в угловой точке быстрее.
+        assert!(path.len() < sharp.len());
+
+        // Траектория не проходит через угол, но и не отходит от него дальше допуска.
+        let corner = pos(1.0, 0.0);
+        let closest = path
+            .iter()
+            .map(|p| distance(*p, corner))
+            .fold(f32::INFINITY, f32::min);
+        assert!(closest > 0.0);
+        assert!(closest <= tolerance);
+
+        // Вне зоны скругления траектория совпадает с ломаной.
+        for p in &path {
+            let off_first = libm::fabsf(f32::from(p.shoulder));
+            let off_second = libm::fabsf(1.0 - f32::from(p.rotation));
+            assert!(off_first.min(off_second) <= tolerance + 1e-4, "{p:?}");
+        }
+    }
+
+    #[test]
+    fn test_no_blending_with_constant_velocity() {
+        let (path, passed) = run_corner(0.2, MotionProfile::ConstantVelocity);
+        assert_eq!(path[passed[0]], pos(1.0, 0.0));
+    }
+
+    #[test]
+    fn test_late_waypoint_does_not_jump() {
+        let mut blender = Blender::new(pos(0.0, 0.0), Radians::new(0.5));
+        blender
+            .push(pos(1.0, 0.0), MotionProfile::Trapezoidal, &limits())
+            .unwrap();
+
+        // Следующая точка приходит почти в конце первого участка.
+        let mut last = blender.position();
+        while blender.step(DT).position.rotation < Radians::new(0.999) {
+            last = blender.position();
+        }
+        blender
+            .push(pos(1.0, 1.0), MotionProfile::Trapezoidal, &limits())
+            .unwrap();
+
+        while !blender.is_idle() {
+            let p = blender.step(DT).position;
+            // Скорость не превышает 1 рад/с по каждой оси.
+            assert!(distance(p, last) <= f32::from(DT) * 1.01 + 1e-3);
+            last = p;
+        }
+        assert_eq!(blender.position(), pos(1.0, 1.0));
+    }
+
+    #[test]
+    fn test_push_is_rejected_while_lookahead_is_full() {
+        let mut blender = Blender::new(pos(0.0, 0.0), Radians::new(0.1));
+        let profile = MotionProfile::Trapezoidal;
+        assert!(blender.push(pos(1.0, 0.0), profile, &limits()).is_ok());
+        assert!(blender.push(pos(2.0, 0.0), profile, &limits()).is_ok());
+        assert_eq!(
+            blender.push(pos(3.0, 0.0), profile, &limits()),
+            Err(pos(3.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_active_target() {
+        let mut blender = Blender::new(pos(0.0, 0.0), Radians::new(0.0));
+        assert_eq!(blender.active_target(), pos(0.0, 0.0));
+
+        let profile = MotionProfile::Trapezoidal;
+        blender.push(pos(1.0, 0.0), profile, &limits()).unwrap();
+        blender.push(pos(1.0, 1.0), profile, &limits()).unwrap();
+        assert_eq!(blender.active_target(), pos(1.0, 0.0));
+        assert_eq!(blender.target(), pos(1.0, 1.0));
+
+        while !blender.step(DT).passed {}
+        assert_eq!(blender.active_target(), pos(1.0, 1.0));
+    }
+
+    #[test]
+    fn test_halt_starts_next_segment_from_current_position() {
+        let mut blender = Blender::new(pos(0.0, 0.0), Radians::new(0.0));
+        let profile = MotionProfile::Trapezoidal;
+        blender.push(pos(1.0, 0.0), profile, &limits()).unwrap();
+        blender.push(pos(1.0, 1.0), profile, &limits()).unwrap();
+        for _ in 0..10 {
+            blender.step(DT);
+        }
+
+        let stopped = blender.position();
+        blender.halt();
+        assert!(blender.is_idle());
+        assert_eq!(blender.target(), stopped);
+        assert_eq!(blender.step(DT).position, stopped);
+
+        blender.push(pos(0.0, 0.0), profile, &limits()).unwrap();
+        assert_eq!(blender.step(Seconds::new(0.0)).position, stopped);
+    }
+
+    #[test]
+    fn test_spline_follows_segment_without_overlap() {
+        let mut blender = Blender::new(pos(0.0, 0.0), Radians::new(0.2));
+        let profile = MotionProfile::Trapezoidal;
+        let points = SplinePoints::try_from(&[pos(1.0, 1.0), pos(0.0, 1.0)][..]).unwrap();
+
+        blender.push(pos(1.0, 0.0), profile, &limits()).unwrap();
+        let spline = Spline::new(blender.target(), &points, &limits());
+        assert!(blender.push_spline(spline));
+
+        let mut passed: Vec<Position, 4> = Vec::new();
+        for _ in 0..MAX_STEPS {
+            let step = blender.step(DT);
+            if step.passed {
+                passed.push(step.position).unwrap();
+            }
+            if blender.is_idle() {
+                break;
+            }
+        }
+        assert_eq!(passed.as_slice(), &[pos(1.0, 0.0), pos(0.0, 1.0)]);
+    }
+}
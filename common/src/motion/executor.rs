@@ -0,0 +1,361 @@
+//! # Motion Executor
+//!
+//! Исполнение перемещений из очереди позиционирования без привязки к
+//! оборудованию и времени.
+//!
+//! Исполнитель принимает перемещения с метками запросов, продвигает
+//! траекторию по шагам и накапливает итоги перемещений для подтверждения.
+//! Цикл позиционирования прошивки и симулятор отличаются только тем, откуда
+//! берутся перемещения, куда уходят позиции и итоги и как отмеряется шаг.
+
+use super::{
+    blend::Blender,
+    hold::FeedHold,
+    jog::ContinuousJog,
+    spline::{Spline, SplinePoints},
+};
+use crate::{
+    mechanics_config::StartupMechanicsConfig,
+    quantities::Position,
+    request::{MotionControl, Waypoint},
+    response::Rejection,
+    units::Seconds,
+};
+use heapless::Deque;
+
+/// Емкость очереди меток перемещений, переданных блендеру: текущее
+/// и следующее.
+const IN_FLIGHT_LEN: usize = 2;
+
+/// Емкость очереди итогов перемещений, ожидающих передачи. Новое перемещение
+/// принимается, только если в ней хватит места для итогов всех перемещений
+/// в исполнении.
+const ACK_QUEUE_LEN: usize = 4;
+
+/// Элемент очереди позиционирования.
+#[derive(Clone, Debug)]
+pub enum Motion {
+    /// Перемещение в целевую точку.
+    Waypoint(Waypoint),
+    /// Движение по сплайну через набор точек.
+    Spline(SplinePoints),
+    /// Смещение относительно последней запланированной цели.
+    Jog(Position),
+    /// Непрерывное движение оси.
+    JogContinuous(ContinuousJog),
+}
+
+/// Итог перемещения из очереди позиционирования.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// Целевая точка пройдена.
+    Passed,
+    /// Перемещение отменено остановкой.
+    Cancelled,
+    /// Перемещение отклонено позиционером.
+    Rejected(Rejection),
+}
+
+/// Исполнитель перемещений, помеченных метками `T`.
+///
+/// Итоги перемещений не должны останавливать движение, поэтому они копятся в
+/// собственной очереди, а новое перемещение принимается, только если в ней
+/// хватит места.
+#[derive(Clone, Debug)]
+pub struct Executor<T> {
+    config: StartupMechanicsConfig,
+    blender: Blender,
+    hold: FeedHold,
+    /// Запрошена остановка: после замедления перемещения будут отменены.
+    stopping: bool,
+    /// Метки перемещений, переданных блендеру, в порядке исполнения.
+    in_flight: Deque<T, IN_FLIGHT_LEN>,
+    /// Итоги завершенных перемещений, еще не переданные клиенту.
+    acks: Deque<(T, Outcome), ACK_QUEUE_LEN>,
+}
+
+impl<T: Copy> Executor<T> {
+    /// Создает исполнитель в покое в начальной позиции конфигурации.
+    pub fn new(config: StartupMechanicsConfig) -> Self {
+        Self {
+            blender: Blender::new(config.init_position, config.blend_tolerance),
+            config,
+            hold: FeedHold::new(),
+            stopping: false,
+            in_flight: Deque::new(),
+            acks: Deque::new(),
+        }
+    }
+
+    /// Задает конфигурацию механики. Применяется к следующему перемещению.
+    pub fn set_config(&mut self, config: StartupMechanicsConfig) {
+        self.blender.set_tolerance(config.blend_tolerance);
+        self.config = config;
+    }
+
+    /// Текущая позиция.
+    #[inline]
+    pub fn position(&self) -> Position {
+        self.blender.position()
+    }
+
+    /// Цель исполняемого перемещения. В покое совпадает с текущей позицией.
+    #[inline]
+    pub fn active_target(&self) -> Position {
+        self.blender.active_target()
+    }
+
+    /// Возвращает `true`, если движение завершено и новых перемещений нет.
+    #[inline]
+    pub fn is_idle(&self) -> bool {
+        self.blender.is_idle()
+    }
+
+    /// Возвращает `true`, если движение приостановлено командой.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.hold.is_holding() && !self.stopping
+    }
+
+    /// Возвращает `true`, если можно принять следующее перемещение.
+    pub fn can_accept(&self) -> bool {
+        self.blender.can_accept()
+            && self.in_flight.len() + self.acks.len() < ACK_QUEUE_LEN
+            && !self.stopping
+    }
+
+    /// Применяет команду управления движением.
+    ///
+    /// В покое переход не нужен: приостановка действует сразу, остановка и
+    /// продолжение возвращают полный ход. Очистку очереди выполняет сетевое API.
+    pub fn control(&mut self, action: MotionControl) {
+        let limits = self.config.limits();
+        match (action, self.blender.is_idle()) {
+            (MotionControl::Stop, false) => {
+                self.hold.hold(&limits);
+                self.stopping = true;
+            }
+            (MotionControl::Pause, false) => self.hold.hold(&limits),
+            // Остановку нельзя отменить продолжением.
+            (MotionControl::Resume, false) if !self.stopping => self.hold.release(&limits),
+            (MotionControl::Pause, true) => self.hold.hold_immediately(),
+            (MotionControl::Stop | MotionControl::Resume, true) => self.hold = FeedHold::new(),
+            _ => {}
+        }
+    }
+
+    /// Принимает перемещение с меткой `tag`.
+    ///
+    /// Вызывается, только если [`Self::can_accept`] возвращает `true`.
+    /// Перемещение, которое нельзя исполнить, сразу получает итог с отказом.
+    pub fn accept(&mut self, tag: T, motion: Motion) {
+        let (blender, config) = (&mut self.blender, &self.config);
+        let rejection = match motion {
+            Motion::Waypoint(waypoint) => {
+                let profile = waypoint.profile.unwrap_or(config.profile);
+                let limits = match waypoint.timing {
+                    Some(timing) => timing.limits(
+                        blender.target(),
+                        waypoint.position,
+                        profile,
+                        &config.limits(),
+                    ),
+                    None => Ok(config.limits()),
+                };
+                match limits {
+                    Ok(limits) => {
+                        let _ = blender.push(waypoint.position, profile, &limits);
+                        None
+                    }
+                    Err(minimum) => Some(Rejection::InfeasibleDuration(minimum)),
+                }
+            }
+            Motion::Spline(points) => {
+                let spline = Spline::new(blender.target(), &points, &config.limits());
+                match spline.check(&config.joint_limits) {
+                    Ok(()) => {
+                        let _ = blender.push_spline(spline);
+                        None
+                    }
+                    Err(violation) => Some(Rejection::JointLimit(violation)),
+                }
+            }
+            Motion::Jog(offset) => {
+                let target = blender.target() + offset;
+                match config.joint_limits.check(&target) {
+                    Ok(()) => {
+                        let _ = blender.push(target, config.profile, &config.limits());
+                        None
+                    }
+                    Err(violation) => Some(Rejection::JointLimit(violation)),
+                }
+            }
+            Motion::JogContinuous(jog) => {
+                let (target, limits) =
+                    jog.plan(blender.target(), &config.joint_limits, &config.limits());
+                let _ = blender.push(target, config.profile, &limits);
+                None
+            }
+        };
+        // Место в очередях проверено в `can_accept`.
+        let _ = match rejection {
+            None => self.in_flight.push_back(tag),
+            Some(rejection) => self
+                .acks
+                .push_back((tag, Outcome::Rejected(rejection)))
+                .map_err(|(tag, _)| tag),
+        };
+    }
+
+    /// Продвигает движение на интервал `dt` и возвращает позицию, в которую
+    /// нужно перевести приводы.
+    pub fn step(&mut self, dt: Seconds) -> Position {
+        let step = self.blender.step(self.hold.step(dt));
+        if step.passed
+            && let Some(tag) = self.in_flight.pop_front()
+        {
+            let _ = self.acks.push_back((tag, Outcome::Passed));
+        }
+        if self.stopping && self.hold.is_held() {
+            // Манипулятор остановлен: непройденные перемещения отменяются,
+            // а следующее начнется из текущей позиции на полном ходу.
+            self.blender.halt();
+            while let Some(tag) = self.in_flight.pop_front() {
+                let _ = self.acks.push_back((tag, Outcome::Cancelled));
+            }
+            self.hold = FeedHold::new();
+            self.stopping = false;
+        }
+        step.position
+    }
+
+    /// Первый итог, ожидающий передачи.
+    #[inline]
+    pub fn peek_ack(&self) -> Option<(T, Outcome)> {
+        self.acks.front().copied()
+    }
+
+    /// Забирает первый итог, ожидающий передачи.
+    #[inline]
+    pub fn pop_ack(&mut self) -> Option<(T, Outcome)> {
+        self.acks.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mechanics_config::JointLimits, request::Request};
+
+    const DT: Seconds = Seconds::new(0.02);
+
+    fn pos(r: f32) -> Position {
+        Position {
+            rotation: r.into(),
+            shoulder: 0.0.into(),
+            forearm: 0.0.into(),
+            claw: 0.0.into(),
+        }
+    }
+
+    fn executor() -> Executor<u32> {
+        Executor::new(StartupMechanicsConfig {
+            init_position: pos(0.0),
+            ..Default::default()
+        })
+    }
+
+    /// Исполняет шаги, пока исполнитель не придет в покой.
+    fn run(executor: &mut Executor<u32>) -> Position {
+        let mut position = executor.position();
+        for _ in 0..10_000 {
+            if executor.is_idle() {
+                break;
+            }
+            position = executor.step(DT);
+        }
+        assert!(executor.is_idle());
+        position
+    }
+
+    #[test]
+    fn test_waypoints_are_acked_in_order() {
+        let mut executor = executor();
+        executor.accept(1, Motion::Waypoint(pos(0.5).into()));
+        assert!(executor.can_accept());
+        executor.accept(2, Motion::Waypoint(pos(1.0).into()));
+        assert!(!executor.can_accept());
+
+        assert_eq!(run(&mut executor), pos(1.0));
+        assert_eq!(executor.pop_ack(), Some((1, Outcome::Passed)));
+        assert_eq!(executor.pop_ack(), Some((2, Outcome::Passed)));
+        assert_eq!(executor.pop_ack(), None);
+    }
+
+    #[test]
+    fn test_stop_cancels_motions_in_flight() {
+        let mut executor = executor();
+        executor.accept(1, Motion::Waypoint(pos(1.0).into()));
+        executor.accept(2, Motion::Waypoint(pos(2.0).into()));
+        executor.step(DT);
+        executor.control(MotionControl::Stop);
+        assert!(!executor.can_accept());
+        assert!(!executor.is_paused());
+
+        let position = run(&mut executor);
+        assert!(f32::from(position.rotation) < 1.0);
+        assert_eq!(executor.pop_ack(), Some((1, Outcome::Cancelled)));
+        assert_eq!(executor.pop_ack(), Some((2, Outcome::Cancelled)));
+        assert!(executor.can_accept());
+    }
+
+    #[test]
+    fn test_rejections_are_acked_without_motion() {
+        let mut executor = executor();
+        executor.set_config(StartupMechanicsConfig {
+            joint_limits: JointLimits {
+                min: pos(-1.0),
+                max: pos(1.0),
+            },
+            ..executor.config.clone()
+        });
+        executor.accept(1, Motion::Jog(pos(2.0)));
+        assert!(executor.is_idle());
+        assert!(matches!(
+            executor.pop_ack(),
+            Some((1, Outcome::Rejected(Rejection::JointLimit(_))))
+        ));
+    }
+
+    #[test]
+    fn test_spline_overshoot_is_rejected() {
+        let mut executor = executor();
+        executor.set_config(StartupMechanicsConfig {
+            joint_limits: JointLimits {
+                min: pos(-1.0),
+                max: pos(1.0),
+            },
+            ..executor.config.clone()
+        });
+        // Все точки в пределах, но сплайн проскакивает верхнюю границу между
+        // ними.
+        let points = SplinePoints::try_from(&[pos(0.95), pos(0.99), pos(0.0)][..]).unwrap();
+        assert!(
+            Request::EnqueueSpline(points.clone())
+                .validate(&executor.config.joint_limits)
+                .is_ok()
+        );
+        executor.accept(1, Motion::Spline(points));
+        assert!(executor.is_idle());
+        assert!(matches!(
+            executor.pop_ack(),
+            Some((1, Outcome::Rejected(Rejection::JointLimit(_))))
+        ));
+
+        // Тот же сплайн с запасом до границы исполняется.
+        let points = SplinePoints::try_from(&[pos(0.5), pos(0.8), pos(0.0)][..]).unwrap();
+        executor.accept(2, Motion::Spline(points));
+        run(&mut executor);
+        assert_eq!(executor.pop_ack(), Some((2, Outcome::Passed)));
+    }
+}
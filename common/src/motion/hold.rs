@@ -0,0 +1,159 @@
+//! # Feed Hold
+//!
+//! Плавная приостановка и возобновление движения.
+//!
+//! Движение замедляется масштабированием времени траектории: манипулятор
+//! остается на запланированном пути, а его скорость плавно спадает до нуля.
+//! Коэффициент масштаба меняется линейно за время разгона, поэтому при
+//! наибольшей скорости дополнительное ускорение не превышает ограничений осей.
+
+use super::MotionLimits;
+use crate::{quantities::MaxAbsComponent, units::Seconds};
+
+/// Масштаб времени траектории с плавным переходом между ходом и остановкой.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FeedHold {
+    /// Текущий масштаб времени: `1` — полный ход, `0` — остановка.
+    scale: f32,
+    /// Масштаб, к которому идет переход.
+    target: f32,
+    /// Время перехода между полным ходом и остановкой.
+    ramp: Seconds,
+}
+
+impl FeedHold {
+    /// Создает масштаб полного хода.
+    pub const fn new() -> Self {
+        Self {
+            scale: 1.0,
+            target: 1.0,
+            ramp: Seconds::new(0.0),
+        }
+    }
+
+    /// Начинает плавную остановку с ограничениями `limits`.
+    pub fn hold(&mut self, limits: &MotionLimits) {
+        self.target = 0.0;
+        self.ramp = ramp_time(limits);
+    }
+
+    /// Останавливает ход сразу, без перехода. Допустимо только в покое.
+    pub fn hold_immediately(&mut self) {
+        self.target = 0.0;
+        self.scale = 0.0;
+    }
+
+    /// Начинает плавное возобновление хода с ограничениями `limits`.
+    pub fn release(&mut self, limits: &MotionLimits) {
+        self.target = 1.0;
+        self.ramp = ramp_time(limits);
+    }
+
+    /// Возвращает `true`, если запрошена остановка (в том числе если она еще
+    /// не завершена).
+    #[inline]
+    pub fn is_holding(&self) -> bool {
+        self.target == 0.0
+    }
+
+    /// Возвращает `true`, если движение полностью остановлено.
+    #[inline]
+    pub fn is_held(&self) -> bool {
+        self.is_holding() && self.scale == 0.0
+    }
+
+    /// Продвигает переход на интервал `dt` и возвращает интервал времени
+    /// траектории, соответствующий этому шагу.
+    pub fn step(&mut self, dt: Seconds) -> Seconds {
+        let start = self.scale;
+        let delta = if self.ramp > Seconds::new(0.0) {
+            dt / self.ramp
+        } else {
+            1.0
+        };
+        self.scale = if self.target > start {
+            (start + delta).min(self.target)
+        } else {
+            (start - delta).max(self.target)
+        };
+        // Среднее значение масштаба на шаге.
+        dt * ((start + self.scale) / 2.0)
+    }
+}
+
+impl Default for FeedHold {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Время остановки с наибольшей скорости при наибольшем ускорении: самое
+/// долгое среди осей.
+fn ramp_time(limits: &MotionLimits) -> Seconds {
+    (limits.max_speed / limits.max_acceleration).max_abs_component()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantities::{Acceleration, Jerk, Velocity};
+
+    const DT: Seconds = Seconds::new(0.01);
+
+    fn limits() -> MotionLimits {
+        MotionLimits {
+            max_speed: Velocity {
+                rotation: 1.0.into(),
+                shoulder: 2.0.into(),
+                forearm: 1.0.into(),
+                claw: 1.0.into(),
+            },
+            max_acceleration: Acceleration {
+                rotation: 10.0.into(),
+                shoulder: 10.0.into(),
+                forearm: 10.0.into(),
+                claw: 10.0.into(),
+            },
+            max_jerk: Jerk {
+                rotation: 100.0.into(),
+                shoulder: 100.0.into(),
+                forearm: 100.0.into(),
+                claw: 100.0.into(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_full_speed_by_default() {
+        let mut hold = FeedHold::new();
+        assert!(!hold.is_holding());
+        assert_eq!(hold.step(DT), DT);
+    }
+
+    #[test]
+    fn test_hold_and_release_ramp() {
+        let mut hold = FeedHold::new();
+        hold.hold(&limits());
+        assert!(hold.is_holding());
+
+        // Время остановки — 0.2 с для самой быстрой оси.
+        let mut traveled = Seconds::new(0.0);
+        let mut steps = 0;
+        while !hold.is_held() {
+            let dt = hold.step(DT);
+            assert!(dt < DT);
+            traveled += dt;
+            steps += 1;
+        }
+        assert_eq!(steps, 20);
+        // Путь торможения — половина пути за то же время на полном ходу.
+        assert!((f32::from(traveled) - 0.1).abs() < 1e-4);
+        assert_eq!(hold.step(DT), Seconds::new(0.0));
+
+        hold.release(&limits());
+        for _ in 0..20 {
+            hold.step(DT);
+        }
+        assert_eq!(hold.step(DT), DT);
+    }
+}
@@ -0,0 +1,126 @@
+//! # Jog
+//!
+//! Непрерывное движение одной оси с заданной скоростью при ручном
+//! позиционировании.
+//!
+//! Движение планируется обычным участком: ось проходит путь, который она
+//! преодолела бы на заданной скорости за время ожидания, но не дальше
+//! программных ограничений. Прервать движение раньше можно командой
+//! остановки.
+
+use super::MotionLimits;
+use crate::{
+    mechanics_config::JointLimits,
+    quantities::{Axis, Position},
+    units::{Radians, RadiansPerSecond},
+};
+use postcard::experimental::max_size::MaxSize;
+use serde::{Deserialize, Serialize};
+
+/// Непрерывное движение оси.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, MaxSize)]
+pub struct ContinuousJog {
+    /// Ось, которая движется.
+    pub axis: Axis,
+    /// Скорость оси; знак задает направление.
+    pub speed: RadiansPerSecond,
+    /// Наибольшая длительность движения, мс.
+    pub timeout_ms: u32,
+}
+
+impl ContinuousJog {
+    /// Проверяет, что скорость конечна и не равна нулю, а время ожидания
+    /// положительно.
+    pub fn is_valid(&self) -> bool {
+        let speed = f32::from(self.speed);
+        speed.is_finite() && speed != 0.0 && self.timeout_ms > 0
+    }
+
+    /// Планирует движение от позиции `from`.
+    ///
+    /// Возвращает цель и ограничения участка: скорость оси не превышает ни
+    /// заданной, ни предельной из `limits`. Если ось уже за границей в сторону
+    /// движения, цель совпадает с `from`.
+    pub fn plan(
+        &self,
+        from: Position,
+        joint_limits: &JointLimits,
+        limits: &MotionLimits,
+    ) -> (Position, MotionLimits) {
+        let speed = f32::from(self.speed);
+        let start = f32::from(*from.get(self.axis));
+        let end = start + speed * (self.timeout_ms as f32 / 1000.0);
+        let (min, max) = (
+            joint_limits.min.get(self.axis),
+            joint_limits.max.get(self.axis),
+        );
+        let end = if speed > 0.0 {
+            libm::fmaxf(libm::fminf(end, f32::from(*max)), start)
+        } else {
+            libm::fminf(libm::fmaxf(end, f32::from(*min)), start)
+        };
+
+        let mut target = from;
+        *target.get_mut(self.axis) = Radians::new(end);
+
+        let mut limits = *limits;
+        let max_speed = limits.max_speed.get_mut(self.axis);
+        *max_speed = RadiansPerSecond::new(libm::fminf(f32::from(*max_speed), libm::fabsf(speed)));
+        (target, limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mechanics_config::StartupMechanicsConfig;
+
+    fn pos(r: f32, s: f32, f: f32, c: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(f),
+            claw: Radians::new(c),
+        }
+    }
+
+    fn jog(speed: f32, timeout_ms: u32) -> ContinuousJog {
+        ContinuousJog {
+            axis: Axis::Shoulder,
+            speed: RadiansPerSecond::new(speed),
+            timeout_ms,
+        }
+    }
+
+    #[test]
+    fn test_plan_moves_one_axis_for_timeout() {
+        let config = StartupMechanicsConfig::default();
+        let joint_limits = JointLimits {
+            min: pos(0.0, 0.0, 0.0, 0.0),
+            max: pos(3.0, 3.0, 3.0, 3.0),
+        };
+        let from = pos(1.0, 1.0, 1.0, 1.0);
+
+        let (target, limits) = jog(0.5, 2000).plan(from, &joint_limits, &config.limits());
+        assert_eq!(target, pos(1.0, 2.0, 1.0, 1.0));
+        assert_eq!(limits.max_speed.shoulder, RadiansPerSecond::new(0.5));
+        assert_eq!(limits.max_speed.rotation, config.max_speed.rotation);
+
+        // Движение в обратную сторону останавливается на границе.
+        let (target, _) = jog(-1.0, 5000).plan(from, &joint_limits, &config.limits());
+        assert_eq!(target, pos(1.0, 0.0, 1.0, 1.0));
+
+        // За границей ось в ее сторону не движется.
+        let outside = pos(1.0, 3.5, 1.0, 1.0);
+        let (target, _) = jog(1.0, 1000).plan(outside, &joint_limits, &config.limits());
+        assert_eq!(target, outside);
+    }
+
+    #[test]
+    fn test_validity() {
+        assert!(jog(0.1, 1).is_valid());
+        assert!(!jog(0.0, 1000).is_valid());
+        assert!(!jog(f32::NAN, 1000).is_valid());
+        assert!(!jog(1.0, 0).is_valid());
+    }
+}
@@ -0,0 +1,460 @@
+//! # Positioner
+//!
+//! Цикл позиционирования без привязки к оборудованию и времени.
+//!
+//! Позиционер забирает перемещения из очереди, продвигает их исполнителем с
+//! постоянным интервалом и задает углы приводам. Прошивка и симулятор
+//! отличаются только приводами `A`, часами [`Clock`] и связями [`Links`] с
+//! остальной системой.
+
+use super::executor::{Executor, Motion, Outcome};
+use crate::{
+    actuator::{Actuator, Joints},
+    calibration::Calibration,
+    mechanics_config::StartupMechanicsConfig,
+    quantities::Position,
+    request::MotionControl,
+    units::Seconds,
+};
+
+/// Часы цикла позиционирования.
+pub trait Clock {
+    /// Момент времени.
+    type Instant: Copy + Ord;
+
+    /// Текущий момент.
+    fn now(&mut self) -> Self::Instant;
+
+    /// Момент через `interval` после `instant`.
+    fn after(&self, instant: Self::Instant, interval: Seconds) -> Self::Instant;
+
+    /// Ожидает наступления момента `instant`.
+    fn wait_until(&mut self, instant: Self::Instant);
+
+    /// Пауза между опросами очередей в покое.
+    fn idle(&mut self);
+}
+
+/// Состояние движения после шага позиционера.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// Текущая позиция.
+    pub position: Position,
+    /// Цель текущего перемещения.
+    pub target: Position,
+    /// Манипулятор движется.
+    pub moving: bool,
+    /// Движение приостановлено.
+    pub paused: bool,
+}
+
+/// Связи позиционера с остальной системой. Методы не блокируют.
+pub trait Links {
+    /// Метка перемещения.
+    type Tag: Copy;
+    /// Момент времени снимка состояния.
+    type Instant;
+
+    /// Следующее перемещение из очереди позиционирования.
+    fn try_motion(&mut self) -> Option<(Self::Tag, Motion)>;
+
+    /// Следующая команда управления движением.
+    fn try_control(&mut self) -> Option<MotionControl>;
+
+    /// Обновленная конфигурация механики.
+    fn try_config(&mut self) -> Option<StartupMechanicsConfig>;
+
+    /// Обновленная калибровка приводов.
+    fn try_calibration(&mut self) -> Option<Calibration>;
+
+    /// Передает итог перемещения, если в канале есть место.
+    fn try_ack(&mut self, ack: (Self::Tag, Outcome)) -> bool;
+
+    /// Публикует снимок состояния движения на момент `timestamp`.
+    fn publish(&mut self, snapshot: Snapshot, timestamp: Self::Instant);
+}
+
+/// Позиционер, управляющий приводами `A` всех осей по часам `C`.
+pub struct Positioner<A, C: Clock, T> {
+    joints: Joints<A>,
+    clock: C,
+    executor: Executor<T>,
+    next_tick: C::Instant,
+}
+
+impl<A: Actuator, C: Clock, T: Copy> Positioner<A, C, T> {
+    /// Дожидается конфигурации механики и калибровки и включает приводы в
+    /// начальной позиции.
+    ///
+    /// Калибровка применяется до первой установки угла, а приводы включаются
+    /// после нее, чтобы сразу удерживать начальную позицию.
+    pub fn start<L>(mut joints: Joints<A>, mut clock: C, links: &mut L) -> Self
+    where
+        L: Links<Tag = T, Instant = C::Instant>,
+    {
+        let executor = Executor::new(wait(&mut clock, || links.try_config()));
+        joints.calibrate(&wait(&mut clock, || links.try_calibration()));
+        joints.set_pos(executor.position());
+        joints.enable();
+        let next_tick = clock.now();
+        let positioner = Self {
+            joints,
+            clock,
+            executor,
+            next_tick,
+        };
+        links.publish(positioner.snapshot(), next_tick);
+        positioner
+    }
+
+    /// Приводы всех осей.
+    pub fn joints(&self) -> &Joints<A> {
+        &self.joints
+    }
+
+    /// Исполнитель перемещений.
+    pub fn executor(&self) -> &Executor<T> {
+        &self.executor
+    }
+
+    /// Задача управления траекторией движения манипулятора.
+    ///
+    /// Получает целевые позиции и плавно перемещает приводы, соблюдая временные
+    /// интервалы `interval`. Следующая точка забирается из очереди заранее,
+    /// чтобы скруглить угол траектории в пределах допуска, не останавливаясь в
+    /// каждой точке. Подтверждение с меткой запроса отправляется, когда точка
+    /// пройдена.
+    ///
+    /// Ограничения осей, профиль скорости и допуск скругления берутся из
+    /// конфигурации механики. Обновления применяются к следующему перемещению.
+    ///
+    /// Калибровка приводов применяется сразу, в том числе в покое: для проверки
+    /// калибровки приводы заново устанавливаются в текущую позицию.
+    ///
+    /// Команды управления движением применяются на ближайшем шаге: приостановка
+    /// плавно замедляет ход до нуля, не сходя с траектории, остановка после
+    /// замедления отменяет непройденные перемещения.
+    ///
+    /// После каждого шага публикует снимок состояния движения.
+    pub fn run<L>(mut self, links: &mut L, interval: Seconds) -> !
+    where
+        L: Links<Tag = T, Instant = C::Instant>,
+    {
+        loop {
+            self.tick(links, interval);
+        }
+    }
+
+    /// Один проход цикла позиционирования.
+    ///
+    /// В покое без новых перемещений только опрашивает связи и делает паузу
+    /// [`Clock::idle`], иначе дожидается следующего шага и продвигает
+    /// траекторию на `interval`.
+    pub fn tick<L>(&mut self, links: &mut L, interval: Seconds)
+    where
+        L: Links<Tag = T, Instant = C::Instant>,
+    {
+        let mut controlled = false;
+        while let Some(action) = links.try_control() {
+            self.executor.control(action);
+            controlled = true;
+        }
+
+        let motion = if self.executor.is_idle() {
+            // Итоги, которые некому принять (например, клиент отключился),
+            // остаются в исполнителе: ожидание канала остановило бы опрос
+            // команд и публикацию снимков.
+            self.deliver_acks(links);
+            if let Some(calibration) = links.try_calibration() {
+                self.joints.calibrate(&calibration);
+                self.joints.set_pos(self.executor.position());
+            }
+            let motion = if self.executor.can_accept() {
+                links.try_motion()
+            } else {
+                None
+            };
+            if motion.is_none() {
+                if controlled {
+                    let now = self.clock.now();
+                    links.publish(self.snapshot(), now);
+                }
+                self.clock.idle();
+                return;
+            }
+            motion
+        } else if self.executor.can_accept() {
+            links.try_motion()
+        } else {
+            None
+        };
+
+        if let Some((tag, motion)) = motion {
+            if let Some(config) = links.try_config() {
+                self.executor.set_config(config);
+            }
+            self.executor.accept(tag, motion);
+        }
+
+        // Защита от накопления задержек: если мы отстали, выравниваем время.
+        let next_tick = self.clock.after(self.next_tick, interval);
+        self.next_tick = next_tick.max(self.clock.now());
+        self.clock.wait_until(self.next_tick);
+
+        if let Some(calibration) = links.try_calibration() {
+            self.joints.calibrate(&calibration);
+        }
+
+        self.joints.set_pos(self.executor.step(interval));
+        links.publish(self.snapshot(), self.next_tick);
+        self.deliver_acks(links);
+    }
+
+    /// Передает итоги перемещений, пока в канале есть место.
+    fn deliver_acks<L>(&mut self, links: &mut L)
+    where
+        L: Links<Tag = T, Instant = C::Instant>,
+    {
+        while let Some(ack) = self.executor.peek_ack()
+            && links.try_ack(ack)
+        {
+            self.executor.pop_ack();
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            position: self.executor.position(),
+            target: self.executor.active_target(),
+            moving: !self.executor.is_idle(),
+            paused: self.executor.is_paused(),
+        }
+    }
+}
+
+/// Опрашивает `poll`, пока он не вернет значение, делая паузы между опросами.
+fn wait<C: Clock, T>(clock: &mut C, mut poll: impl FnMut() -> Option<T>) -> T {
+    loop {
+        if let Some(value) = poll() {
+            return value;
+        }
+        clock.idle();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actuator::{ActuatorEvent, RecordingActuator},
+        calibration::ServoCalibration,
+    };
+    use heapless::Deque;
+
+    const DT: Seconds = Seconds::new(0.02);
+
+    type Recording = RecordingActuator<16>;
+
+    /// Часы, которые отсчитывают микросекунды и не ждут.
+    #[derive(Default)]
+    struct FakeClock(u64);
+
+    impl Clock for FakeClock {
+        type Instant = u64;
+
+        fn now(&mut self) -> u64 {
+            self.0
+        }
+
+        fn after(&self, instant: u64, interval: Seconds) -> u64 {
+            instant + (f32::from(interval) * 1e6) as u64
+        }
+
+        fn wait_until(&mut self, instant: u64) {
+            self.0 = self.0.max(instant);
+        }
+
+        fn idle(&mut self) {
+            self.0 += 1_000;
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeLinks {
+        motions: Deque<(u32, Motion), 4>,
+        controls: Deque<MotionControl, 4>,
+        config: Option<StartupMechanicsConfig>,
+        calibration: Option<Calibration>,
+        acks: Deque<(u32, Outcome), 8>,
+        /// Канал итогов занят: итоги не принимаются.
+        acks_blocked: bool,
+        snapshots: Deque<(Snapshot, u64), 64>,
+    }
+
+    impl Links for FakeLinks {
+        type Tag = u32;
+        type Instant = u64;
+
+        fn try_motion(&mut self) -> Option<(u32, Motion)> {
+            self.motions.pop_front()
+        }
+
+        fn try_control(&mut self) -> Option<MotionControl> {
+            self.controls.pop_front()
+        }
+
+        fn try_config(&mut self) -> Option<StartupMechanicsConfig> {
+            self.config.take()
+        }
+
+        fn try_calibration(&mut self) -> Option<Calibration> {
+            self.calibration.take()
+        }
+
+        fn try_ack(&mut self, ack: (u32, Outcome)) -> bool {
+            !self.acks_blocked && self.acks.push_back(ack).is_ok()
+        }
+
+        fn publish(&mut self, snapshot: Snapshot, timestamp: u64) {
+            if self.snapshots.is_full() {
+                self.snapshots.pop_front();
+            }
+            let _ = self.snapshots.push_back((snapshot, timestamp));
+        }
+    }
+
+    fn pos(r: f32) -> Position {
+        Position {
+            rotation: r.into(),
+            shoulder: 0.0.into(),
+            forearm: 0.0.into(),
+            claw: 0.0.into(),
+        }
+    }
+
+    fn calibration(min_pulse_us: u16) -> Calibration {
+        let mut calibration = Calibration::default();
+        calibration.rotation = ServoCalibration {
+            min_pulse_us,
+            ..calibration.rotation
+        };
+        calibration
+    }
+
+    fn start() -> (Positioner<Recording, FakeClock, u32>, FakeLinks) {
+        let mut links = FakeLinks {
+            config: Some(StartupMechanicsConfig {
+                init_position: pos(0.25),
+                ..Default::default()
+            }),
+            calibration: Some(calibration(600)),
+            ..Default::default()
+        };
+        let joints = Joints {
+            rotation: Recording::new(),
+            shoulder: Recording::new(),
+            forearm: Recording::new(),
+            claw: Recording::new(),
+        };
+        let positioner = Positioner::start(joints, FakeClock::default(), &mut links);
+        (positioner, links)
+    }
+
+    fn rotation_events(
+        positioner: &Positioner<Recording, FakeClock, u32>,
+    ) -> heapless::Vec<ActuatorEvent, 16> {
+        positioner.joints().rotation.events().copied().collect()
+    }
+
+    #[test]
+    fn test_start_calibrates_positions_then_enables() {
+        let (positioner, links) = start();
+
+        assert_eq!(
+            rotation_events(&positioner)[..],
+            [
+                ActuatorEvent::Calibrated(calibration(600).rotation),
+                ActuatorEvent::Angle(0.25.into()),
+                ActuatorEvent::Enabled,
+            ]
+        );
+        assert!(positioner.joints().claw.is_enabled());
+        assert_eq!(links.snapshots.len(), 1);
+        assert_eq!(links.snapshots.front().unwrap().0.position, pos(0.25));
+    }
+
+    #[test]
+    fn test_calibration_is_reapplied_while_idle() {
+        let (mut positioner, mut links) = start();
+        positioner.tick(&mut links, DT);
+        assert_eq!(rotation_events(&positioner).len(), 3);
+
+        links.calibration = Some(calibration(700));
+        positioner.tick(&mut links, DT);
+
+        assert!(positioner.executor().is_idle());
+        assert_eq!(
+            rotation_events(&positioner)[3..],
+            [
+                ActuatorEvent::Calibrated(calibration(700).rotation),
+                ActuatorEvent::Angle(0.25.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_motion_is_stepped_and_acked() {
+        let (mut positioner, mut links) = start();
+        links
+            .motions
+            .push_back((7, Motion::Waypoint(pos(0.5).into())))
+            .unwrap();
+
+        for _ in 0..1_000 {
+            positioner.tick(&mut links, DT);
+            if !links.acks.is_empty() {
+                break;
+            }
+        }
+
+        assert_eq!(links.acks.pop_front(), Some((7, Outcome::Passed)));
+        assert_eq!(
+            positioner.joints().rotation.events().last(),
+            Some(&ActuatorEvent::Angle(0.5.into()))
+        );
+        let (snapshot, timestamp) = *links.snapshots.back().unwrap();
+        assert_eq!(snapshot.position, pos(0.5));
+        assert!(timestamp > 0);
+    }
+
+    #[test]
+    fn test_idle_does_not_wait_for_ack_channel() {
+        let (mut positioner, mut links) = start();
+        links.acks_blocked = true;
+        links
+            .motions
+            .push_back((7, Motion::Waypoint(pos(0.5).into())))
+            .unwrap();
+        for _ in 0..1_000 {
+            positioner.tick(&mut links, DT);
+            if positioner.executor().is_idle() {
+                break;
+            }
+        }
+        assert!(positioner.executor().is_idle());
+
+        // В покое итог остается в исполнителе, а команды и снимки
+        // обрабатываются.
+        let published = links.snapshots.len();
+        links.controls.push_back(MotionControl::Stop).unwrap();
+        positioner.tick(&mut links, DT);
+        assert!(links.acks.is_empty());
+        assert_eq!(positioner.executor().peek_ack(), Some((7, Outcome::Passed)));
+        assert_eq!(links.snapshots.len(), published + 1);
+
+        links.acks_blocked = false;
+        positioner.tick(&mut links, DT);
+        assert_eq!(links.acks.pop_front(), Some((7, Outcome::Passed)));
+        assert_eq!(positioner.executor().peek_ack(), None);
+    }
+}
@@ -0,0 +1,387 @@
+//! # S-Curve Profile
+//!
+//! Профиль третьего порядка с ограниченным рывком. Разгон состоит из трех фаз:
+//! нарастание ускорения с постоянным рывком, движение с постоянным ускорением
+//! и спад ускорения. Торможение зеркально разгону, между ними — движение
+//! с постоянной скоростью.
+
+/// S-образный закон изменения нормированного параметра пути `s(t)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SCurve {
+    /// Рывок, 1/с³.
+    jerk: f32,
+    /// Длительность фазы нарастания (и спада) ускорения, с.
+    t_j: f32,
+    /// Длительность разгона (и торможения), с.
+    t_a: f32,
+    /// Максимальная скорость изменения `s`, 1/с.
+    peak: f32,
+    /// Полная длительность, с.
+    total: f32,
+}
+
+impl SCurve {
+    /// Строит профиль по приведенным ограничениям:
+    ///
+    /// * `k_v` — `|Δ| / v_max`, с (больше нуля);
+    /// * `k_a` — `|Δ| / a_max`, с² (ноль — ускорение не ограничено);
+    /// * `k_j` — `|Δ| / j_max`, с³ (больше нуля).
+    pub fn new(k_v: f32, k_a: f32, k_j: f32) -> Self {
+        let v = 1.0 / k_v;
+        let a = if k_a > 0.0 { 1.0 / k_a } else { f32::INFINITY };
+        let jerk = 1.0 / k_j;
+
+        // Разгон до максимальной скорости.
+        let (mut t_j, mut t_a) = if a * a / jerk <= v {
+            (a / jerk, a / jerk + v / a)
+        } else {
+            // Максимальное ускорение не достигается.
+            let t_j = libm::sqrtf(v / jerk);
+            (t_j, 2.0 * t_j)
+        };
+        let mut peak = v;
+
+        if peak * t_a > 1.0 {
+            // Путь слишком короткий, чтобы выйти на максимальную скорость.
+            // Сначала пробуем профиль с участком постоянного ускорения:
+            // peak² / a + t_j * peak = 1.
+            let t_j_a = a / jerk;
+            let reduced = 0.5 * a * (libm::sqrtf(t_j_a * t_j_a + 4.0 / a) - t_j_a);
+            if a.is_finite() && reduced >= a * t_j_a {
+                peak = reduced;
+                t_j = t_j_a;
+                t_a = t_j_a + reduced / a;
+            } else {
+                // Ускорение не достигает предела: 2 * jerk * t_j³ = 1.
+                t_j = libm::cbrtf(0.5 * k_j);
+                peak = jerk * t_j * t_j;
+                t_a = 2.0 * t_j;
+            }
+        }
+
+        let cruise = libm::fmaxf(0.0, 1.0 / peak - t_a);
+        Self {
+            jerk,
+            t_j,
+            t_a,
+            peak,
+            total: 2.0 * t_a + cruise,
+        }
+    }
+
+    /// Полная длительность движения, с.
+    #[inline]
+    pub fn duration(&self) -> f32 {
+        self.total
+    }
+
+    /// Длительность разгона (и торможения), с.
+    #[inline]
+    pub fn ramp_time(&self) -> f32 {
+        self.t_a
+    }
+
+    /// Значение `s(t)` и его производные по времени: `[s, s', s'', s''']`.
+    pub fn kinematics(&self, t: f32) -> [f32; 4] {
+        if t >= self.total {
+            [1.0, 0.0, 0.0, 0.0]
+        } else if t <= 0.0 {
+            [0.0; 4]
+        } else if t <= self.t_a {
+            self.acceleration_phase(t)
+        } else if t <= self.total - self.t_a {
+            let s = self.peak * (t - 0.5 * self.t_a);
+            [s, self.peak, 0.0, 0.0]
+        } else {
+            // Торможение — разгон, отраженный во времени.
+            let [s, v, a, j] = self.acceleration_phase(self.total - t);
+            [1.0 - s, v, -a, j]
+        }
+    }
+
+    /// Кинематика на участке разгона, `t ∈ [0, t_a]`.
+    fn acceleration_phase(&self, t: f32) -> [f32; 4] {
+        let Self {
+            jerk,
+            t_j,
+            t_a,
+            peak,
+            ..
+        } = *self;
+
+        if t <= t_j {
+            [jerk * t * t * t / 6.0, 0.5 * jerk * t * t, jerk * t, jerk]
+        } else if t <= t_a - t_j {
+            let a = jerk * t_j;
+            let tau = t - t_j;
+            let v_j = 0.5 * jerk * t_j * t_j;
+            let s = jerk * t_j * t_j * t_j / 6.0 + v_j * tau + 0.5 * a * tau * tau;
+            [s, v_j + a * tau, a, 0.0]
+        } else {
+            // Скорость симметрична относительно середины разгона:
+            // v(t) = peak - v(t_a - t).
+            let r = t_a - t;
+            let s = peak * (t - 0.5 * t_a) + jerk * r * r * r / 6.0;
+            [s, peak - 0.5 * jerk * r * r, jerk * r, -jerk]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{MotionLimits, MotionProfile, Segment};
+    use super::*;
+    use crate::{
+        quantities::{Acceleration, Jerk, Position, Quantity, Velocity},
+        units::{
+            Radians, RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared, Seconds,
+        },
+    };
+
+    /// Относительный допуск на погрешность вычислений в `f32`.
+    const TOL: f32 = 1e-3;
+
+    /// Количество отсчетов на участок при проверке ограничений.
+    const SAMPLES: usize = 4000;
+
+    fn pos(r: f32, s: f32, f: f32, c: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(f),
+            claw: Radians::new(c),
+        }
+    }
+
+    fn axes<U>(q: Quantity<crate::units::WithUnit<U>>) -> [f32; 4] {
+        [
+            q.rotation.into(),
+            q.shoulder.into(),
+            q.forearm.into(),
+            q.claw.into(),
+        ]
+    }
+
+    fn limits(v: [f32; 4], a: [f32; 4], j: [f32; 4]) -> MotionLimits {
+        MotionLimits {
+            max_speed: Velocity {
+                rotation: RadiansPerSecond::new(v[0]),
+                shoulder: RadiansPerSecond::new(v[1]),
+                forearm: RadiansPerSecond::new(v[2]),
+                claw: RadiansPerSecond::new(v[3]),
+            },
+            max_acceleration: Acceleration {
+                rotation: RadiansPerSecondSquared::new(a[0]),
+                shoulder: RadiansPerSecondSquared::new(a[1]),
+                forearm: RadiansPerSecondSquared::new(a[2]),
+                claw: RadiansPerSecondSquared::new(a[3]),
+            },
+            max_jerk: Jerk {
+                rotation: RadiansPerSecondCubed::new(j[0]),
+                shoulder: RadiansPerSecondCubed::new(j[1]),
+                forearm: RadiansPerSecondCubed::new(j[2]),
+                claw: RadiansPerSecondCubed::new(j[3]),
+            },
+        }
+    }
+
+    fn uniform(v: f32, a: f32, j: f32) -> MotionLimits {
+        limits([v; 4], [a; 4], [j; 4])
+    }
+
+    fn s_curve(src: Position, dst: Position, limits: &MotionLimits) -> Segment {
+        Segment::new(src, dst, MotionProfile::SCurve, limits)
+    }
+
+    fn exceeds(value: f32, bound: f32) -> bool {
+        libm::fabsf(value) > bound * (1.0 + TOL) + TOL * TOL
+    }
+
+    /// Проверяет, что скорость, ускорение и рывок каждой оси ни в один момент
+    /// времени не превышают ограничений.
+    fn assert_within_limits(seg: &Segment, limits: &MotionLimits) {
+        let v_max = axes(limits.max_speed);
+        let a_max = axes(limits.max_acceleration);
+        let j_max = axes(limits.max_jerk);
+        let total = f32::from(seg.duration());
+
+        for i in 0..=SAMPLES {
+            let t = Seconds::new(total * i as f32 / SAMPLES as f32);
+            let (v, a, j) = seg.derivatives_at(t);
+            let (v, a, j) = (axes(v), axes(a), axes(j));
+            for ax in 0..4 {
+                assert!(
+                    !exceeds(v[ax], v_max[ax]),
+                    "axis {ax}: v = {} at {t:?}",
+                    v[ax]
+                );
+                assert!(
+                    !exceeds(a[ax], a_max[ax]),
+                    "axis {ax}: a = {} at {t:?}",
+                    a[ax]
+                );
+                assert!(
+                    !exceeds(j[ax], j_max[ax]),
+                    "axis {ax}: j = {} at {t:?}",
+                    j[ax]
+                );
+            }
+        }
+    }
+
+    /// Проверяет, что производные согласованы между собой: численная производная
+    /// каждой величины совпадает с аналитической следующего порядка.
+    fn assert_consistent(curve: &SCurve) {
+        let dt = curve.duration() / SAMPLES as f32;
+        for i in 0..SAMPLES {
+            let t = i as f32 * dt;
+            let k0 = curve.kinematics(t);
+            let k1 = curve.kinematics(t + dt);
+            let mid = curve.kinematics(t + 0.5 * dt);
+            for order in 0..2 {
+                let numeric = (k1[order] - k0[order]) / dt;
+                let scale = libm::fmaxf(1.0, libm::fabsf(mid[order + 1]));
+                assert!(
+                    libm::fabsf(numeric - mid[order + 1]) <= 1e-2 * scale,
+                    "order {order} at {t}: {numeric} != {}",
+                    mid[order + 1]
+                );
+            }
+        }
+    }
+
+    /// Проверяет непрерывность скорости и ускорения (отсутствие скачков).
+    fn assert_continuous(curve: &SCurve) {
+        let [_, _, a_peak, j] = curve.kinematics(0.5 * curve.t_j);
+        let dt = curve.duration() / SAMPLES as f32;
+        for i in 0..SAMPLES {
+            let t = i as f32 * dt;
+            let k0 = curve.kinematics(t);
+            let k1 = curve.kinematics(t + dt);
+            // За шаг dt ускорение меняется не более чем на j * dt.
+            assert!(libm::fabsf(k1[2] - k0[2]) <= j * dt * (1.0 + TOL) + TOL * a_peak);
+            assert!(k1[0] >= k0[0], "s must be monotonic at {t}");
+        }
+
+        // Путь подходит к концу без скачка.
+        let end = curve.kinematics(curve.duration() - dt)[0];
+        assert!(libm::fabsf(1.0 - end) < TOL, "s jumps at the end: {end}");
+    }
+
+    #[test]
+    fn test_endpoints_and_rest() {
+        let src = pos(0.2, 1.0, 2.0, 3.0);
+        let dst = pos(1.5, -1.0, 2.5, 3.0);
+        let seg = s_curve(src, dst, &uniform(1.0, 2.0, 8.0));
+
+        assert_eq!(seg.position_at(Seconds::new(0.0)), src);
+        assert_eq!(seg.position_at(seg.duration()), dst);
+
+        // Движение начинается и заканчивается в покое с нулевым ускорением.
+        for t in [Seconds::new(0.0), seg.duration()] {
+            let (v, a, _) = seg.derivatives_at(t);
+            assert_eq!(axes(v), [0.0; 4]);
+            assert_eq!(axes(a), [0.0; 4]);
+        }
+    }
+
+    #[test]
+    fn test_full_profile_duration() {
+        // v = 1, a = 1, j = 2: t_j = 0.5, t_a = 1.5, путь разгона 0.75.
+        // Путь 3 рад: крейсерский участок 1.5 с, всего 4.5 с.
+        let seg = s_curve(
+            pos(0.0, 0.0, 0.0, 0.0),
+            pos(3.0, 0.0, 0.0, 0.0),
+            &uniform(1.0, 1.0, 2.0),
+        );
+        assert!(libm::fabsf(f32::from(seg.duration()) - 4.5) < TOL);
+    }
+
+    #[test]
+    fn test_jerk_limited_only_duration() {
+        // Предельные скорость и ускорение не достигаются: 2 * j * t_j³ = d,
+        // t_j = cbrt(0.5 / (2 * 4)) = 0.3969..., всего 4 * t_j.
+        let seg = s_curve(
+            pos(0.0, 0.0, 0.0, 0.0),
+            pos(0.0, 0.5, 0.0, 0.0),
+            &uniform(100.0, 100.0, 4.0),
+        );
+        let expected = 4.0 * libm::cbrtf(0.5 / 8.0);
+        assert!(libm::fabsf(f32::from(seg.duration()) - expected) < TOL);
+    }
+
+    #[test]
+    fn test_not_slower_than_trapezoid_with_infinite_jerk() {
+        let src = pos(0.0, 0.0, 0.0, 0.0);
+        let dst = pos(2.0, 1.0, 0.0, 0.0);
+        let lim = uniform(1.0, 2.0, 1e6);
+
+        let trap = Segment::new(src, dst, MotionProfile::Trapezoidal, &lim);
+        let curve = s_curve(src, dst, &lim);
+        assert!(libm::fabsf(f32::from(curve.duration() - trap.duration())) < 1e-2);
+    }
+
+    #[test]
+    fn test_limits_are_never_exceeded() {
+        let src = pos(0.0, 0.0, 0.0, 0.0);
+        let targets = [
+            pos(3.0, 0.0, 0.0, 0.0),
+            pos(0.01, 0.0, 0.0, 0.0),
+            pos(0.3, -0.2, 0.1, 0.0),
+            pos(-2.5, 1.2, 3.1, -0.4),
+            pos(0.0, 0.0, 0.0, 3.0),
+        ];
+        let limit_sets = [
+            uniform(1.0, 1.0, 2.0),
+            uniform(1.0, 10.0, 1.0),
+            uniform(10.0, 0.5, 100.0),
+            uniform(0.2, 0.2, 0.2),
+            limits(
+                [1.0, 1.5, 1.5, 3.0],
+                [0.5, 1.0, 1.0, 2.0],
+                [1.0, 4.0, 4.0, 8.0],
+            ),
+            limits(
+                [3.0, 0.5, 2.0, 1.0],
+                [8.0, 0.3, 1.0, 5.0],
+                [2.0, 9.0, 0.5, 50.0],
+            ),
+        ];
+
+        for dst in targets {
+            for lim in &limit_sets {
+                let seg = s_curve(src, dst, lim);
+                assert!(f32::from(seg.duration()).is_finite());
+                assert_within_limits(&seg, lim);
+                assert_eq!(seg.position_at(seg.duration()), dst);
+            }
+        }
+    }
+
+    #[test]
+    fn test_derivatives_consistent_in_all_regimes() {
+        // Полный профиль, без участка постоянного ускорения, с пониженной
+        // скоростью и чисто рывковый профиль.
+        let cases = [
+            (1.0, 1.0, 0.5),
+            (1.0, 0.1, 0.5),
+            (0.2, 0.1, 0.5),
+            (0.2, 0.1, 0.001),
+            (0.01, 0.01, 0.01),
+        ];
+        for (k_v, k_a, k_j) in cases {
+            let curve = SCurve::new(k_v, k_a, k_j);
+            assert_consistent(&curve);
+            assert_continuous(&curve);
+            assert!(libm::fabsf(curve.kinematics(curve.duration())[0] - 1.0) < TOL);
+        }
+    }
+
+    #[test]
+    fn test_unlimited_acceleration() {
+        let curve = SCurve::new(1.0, 0.0, 0.5);
+        assert!(curve.duration().is_finite());
+        assert_consistent(&curve);
+        assert_continuous(&curve);
+    }
+}
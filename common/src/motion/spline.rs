@@ -0,0 +1,527 @@
+//! # Cubic Spline
+//!
+//! Гладкая траектория через набор точек в пространстве сочленений.
+//!
+//! Каждая ось описывается кубическим сплайном с непрерывными скоростью и
+//! ускорением (C2). На концах скорость равна нулю (закрепленный сплайн):
+//! в отличие от естественного сплайна, движение начинается и заканчивается
+//! в покое без скачка скорости.
+//!
+//! Длительности участков сначала выбираются пропорционально времени прохождения
+//! хорды самой медленной осью, затем вся траектория равномерно растягивается
+//! во времени так, чтобы ни одна ось не превышала ограничений скорости и
+//! ускорения. При равномерном масштабировании времени геометрия кривой не
+//! меняется, а скорости и ускорения уменьшаются в `λ` и `λ²` раз соответственно.
+
+use super::{MotionLimits, max_ratio};
+use crate::{
+    mechanics_config::{JointLimits, LimitViolation},
+    quantities::{MaxAbsComponent, Position, Quantity, Velocity},
+    units::{Seconds, WithUnit},
+};
+
+/// Максимальное количество точек в одном сплайне.
+pub const MAX_SPLINE_POINTS: usize = 8;
+
+/// Точки, через которые проходит сплайн (без начальной позиции).
+pub type SplinePoints = crate::Vec<Position, MAX_SPLINE_POINTS>;
+
+/// Количество узлов с учетом начальной позиции.
+const MAX_KNOTS: usize = MAX_SPLINE_POINTS + 1;
+
+type Axes = [f32; 4];
+
+/// Узел сплайна.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Knot {
+    time: f32,
+    position: Axes,
+    velocity: Axes,
+}
+
+/// Сплайновая траектория, начинающаяся в текущей позиции.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spline {
+    knots: heapless::Vec<Knot, MAX_KNOTS>,
+}
+
+impl Spline {
+    /// Строит сплайн от `src` через `points`, соблюдая ограничения скорости и
+    /// ускорения всех осей.
+    ///
+    /// Точки, совпадающие с предыдущей, пропускаются.
+    pub fn new(src: Position, points: &SplinePoints, limits: &MotionLimits) -> Self {
+        let mut knots = heapless::Vec::<Knot, MAX_KNOTS>::new();
+        let mut push = |time, position: Position| {
+            let knot = Knot {
+                time,
+                position: axes(position),
+                velocity: [0.0; 4],
+            };
+            // Емкость рассчитана на все точки и начальную позицию.
+            let _ = knots.push(knot);
+        };
+
+        push(0.0, src);
+        let (mut prev, mut time) = (src, 0.0);
+        for &point in points.as_slice() {
+            let delta = point - prev;
+            if f32::from(delta.max_abs_component()) == 0.0 {
+                continue;
+            }
+            // Время прохождения хорды на максимальной скорости самой медленной осью.
+            time += max_ratio(delta, limits.max_speed);
+            push(time, point);
+            prev = point;
+        }
+
+        let mut spline = Self { knots };
+        spline.solve_velocities();
+        spline.fit_limits(limits);
+        spline
+    }
+
+    /// Длительность движения по сплайну.
+    #[inline]
+    pub fn duration(&self) -> Seconds {
+        Seconds::new(self.knots.last().map_or(0.0, |k| k.time))
+    }
+
+    /// Начальная позиция.
+    #[inline]
+    pub fn source(&self) -> Position {
+        quantity(self.knots[0].position)
+    }
+
+    /// Конечная позиция.
+    #[inline]
+    pub fn target(&self) -> Position {
+        quantity(self.knots[self.knots.len() - 1].position)
+    }
+
+    /// Позиция в момент времени `t` от начала движения.
+    ///
+    /// За пределами сплайна возвращает начальную или конечную позицию.
+    pub fn position_at(&self, t: Seconds) -> Position {
+        match self.locate(t.into()) {
+            Some((i, tau)) => quantity(self.polynomial(i, |c| eval(c, tau))),
+            None if f32::from(t) <= 0.0 => self.source(),
+            None => self.target(),
+        }
+    }
+
+    /// Скорость осей в момент времени `t` от начала движения.
+    pub fn velocity_at(&self, t: Seconds) -> Velocity {
+        match self.locate(t.into()) {
+            Some((i, tau)) => quantity(self.polynomial(i, |c| eval_velocity(c, tau))),
+            None => quantity([0.0; 4]),
+        }
+    }
+
+    /// Проверяет, что траектория не выходит за ограничения сочленений.
+    ///
+    /// Кубический участок может выйти за границу между узлами, даже если все
+    /// узлы в пределах, поэтому кроме узлов проверяются экстремумы каждого
+    /// участка. Возвращает нарушение в первой точке, вышедшей за пределы.
+    pub fn check(&self, limits: &JointLimits) -> Result<(), LimitViolation> {
+        for knot in self.knots.iter() {
+            limits.check(&quantity(knot.position))?;
+        }
+        for i in 0..self.knots.len().saturating_sub(1) {
+            let h = self.knots[i + 1].time - self.knots[i].time;
+            let extrema = self.polynomial(i, |c| extrema(c, h));
+            for tau in extrema.iter().flatten().flatten() {
+                limits.check(&quantity(self.polynomial(i, |c| eval(c, *tau))))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Находит участок, содержащий момент `t`, и время от его начала.
+    fn locate(&self, t: f32) -> Option<(usize, f32)> {
+        if t <= 0.0 {
+            return None;
+        }
+        self.knots
+            .windows(2)
+            .position(|w| t < w[1].time)
+            .map(|i| (i, t - self.knots[i].time))
+    }
+
+    /// Вычисляет `f` от коэффициентов полинома участка `i` по каждой оси.
+    fn polynomial<R>(&self, i: usize, f: impl Fn([f32; 4]) -> R) -> [R; 4] {
+        let (a, b) = (&self.knots[i], &self.knots[i + 1]);
+        let h = b.time - a.time;
+        core::array::from_fn(|axis| {
+            f(coefficients(
+                h,
+                a.position[axis],
+                b.position[axis],
+                a.velocity[axis],
+                b.velocity[axis],
+            ))
+        })
+    }
+
+    /// Рассчитывает скорости во внутренних узлах из условия непрерывности
+    /// ускорения (трехдиагональная система, метод прогонки).
+    fn solve_velocities(&mut self) {
+        let n = self.knots.len();
+        if n < 3 {
+            return;
+        }
+
+        let k = &mut self.knots;
+        for axis in 0..4 {
+            // Прямой ход: уравнения для узлов 1..n-1 с v_0 = v_{n-1} = 0.
+            let mut c_prime = [0.0f32; MAX_KNOTS];
+            let mut d_prime = [0.0f32; MAX_KNOTS];
+            for i in 1..n - 1 {
+                let inv_l = 1.0 / (k[i].time - k[i - 1].time);
+                let inv_r = 1.0 / (k[i + 1].time - k[i].time);
+                let lower = inv_l;
+                let diag = 2.0 * (inv_l + inv_r);
+                let upper = if i + 1 < n - 1 { inv_r } else { 0.0 };
+                let rhs = 3.0
+                    * ((k[i].position[axis] - k[i - 1].position[axis]) * inv_l * inv_l
+                        + (k[i + 1].position[axis] - k[i].position[axis]) * inv_r * inv_r);
+
+                let (prev_c, prev_d) = if i > 1 {
+                    (c_prime[i - 1], d_prime[i - 1])
+                } else {
+                    (0.0, 0.0)
+                };
+                let denom = diag - lower * prev_c;
+                c_prime[i] = upper / denom;
+                d_prime[i] = (rhs - lower * prev_d) / denom;
+            }
+
+            // Обратный ход.
+            let mut next = 0.0;
+            for i in (1..n - 1).rev() {
+                next = d_prime[i] - c_prime[i] * next;
+                k[i].velocity[axis] = next;
+            }
+        }
+    }
+
+    /// Равномерно масштабирует время так, чтобы самая нагруженная ось достигала
+    /// своего ограничения скорости или ускорения, но не превышала его.
+    fn fit_limits(&mut self, limits: &MotionLimits) {
+        let speed = axes(limits.max_speed);
+        let accel = axes(limits.max_acceleration);
+
+        let (mut v_ratio, mut a_ratio) = (0.0f32, 0.0f32);
+        for i in 0..self.knots.len().saturating_sub(1) {
+            let h = self.knots[i + 1].time - self.knots[i].time;
+            let v = self.polynomial(i, |c| max_abs_velocity(c, h));
+            let a = self.polynomial(i, |c| max_abs_acceleration(c, h));
+            for axis in 0..4 {
+                v_ratio = v_ratio.max(v[axis] / libm::fabsf(speed[axis]));
+                a_ratio = a_ratio.max(a[axis] / libm::fabsf(accel[axis]));
+            }
+        }
+
+        let scale = v_ratio.max(libm::sqrtf(a_ratio));
+        if !(scale > 0.0 && scale.is_finite()) {
+            return;
+        }
+        for knot in self.knots.iter_mut() {
+            knot.time *= scale;
+            knot.velocity.iter_mut().for_each(|v| *v /= scale);
+        }
+    }
+}
+
+/// Коэффициенты `[c0, c1, c2, c3]` полинома `p(τ) = c0 + c1·τ + c2·τ² + c3·τ³`
+/// по значениям и производным на концах участка длительностью `h`.
+fn coefficients(h: f32, p0: f32, p1: f32, v0: f32, v1: f32) -> [f32; 4] {
+    let slope = (p1 - p0) / h;
+    [
+        p0,
+        v0,
+        (3.0 * slope - 2.0 * v0 - v1) / h,
+        (v0 + v1 - 2.0 * slope) / (h * h),
+    ]
+}
+
+fn eval(c: [f32; 4], tau: f32) -> f32 {
+    c[0] + tau * (c[1] + tau * (c[2] + tau * c[3]))
+}
+
+fn eval_velocity(c: [f32; 4], tau: f32) -> f32 {
+    c[1] + tau * (2.0 * c[2] + tau * 3.0 * c[3])
+}
+
+fn eval_acceleration(c: [f32; 4], tau: f32) -> f32 {
+    2.0 * c[2] + 6.0 * c[3] * tau
+}
+
+/// Моменты внутри участка длительностью `h`, в которых позиция достигает
+/// экстремума: корни квадратного уравнения `p'(τ) = 0` на интервале `(0, h)`.
+fn extrema(c: [f32; 4], h: f32) -> [Option<f32>; 2] {
+    let inside = |tau: f32| (tau > 0.0 && tau < h).then_some(tau);
+    let (a, b, c) = (3.0 * c[3], 2.0 * c[2], c[1]);
+    if a == 0.0 {
+        return match b {
+            0.0 => [None, None],
+            b => [inside(-c / b), None],
+        };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return [None, None];
+    }
+    let root = libm::sqrtf(discriminant);
+    [
+        inside((-b - root) / (2.0 * a)),
+        inside((-b + root) / (2.0 * a)),
+    ]
+}
+
+/// Максимум модуля скорости на участке: на концах или в экстремуме параболы.
+fn max_abs_velocity(c: [f32; 4], h: f32) -> f32 {
+    let mut max = libm::fabsf(eval_velocity(c, 0.0)).max(libm::fabsf(eval_velocity(c, h)));
+    if c[3] != 0.0 {
+        let tau = -c[2] / (3.0 * c[3]);
+        if tau > 0.0 && tau < h {
+            max = max.max(libm::fabsf(eval_velocity(c, tau)));
+        }
+    }
+    max
+}
+
+/// Максимум модуля ускорения на участке: ускорение линейно, поэтому он на концах.
+fn max_abs_acceleration(c: [f32; 4], h: f32) -> f32 {
+    libm::fabsf(eval_acceleration(c, 0.0)).max(libm::fabsf(eval_acceleration(c, h)))
+}
+
+fn axes<Unit>(q: Quantity<WithUnit<Unit>>) -> Axes {
+    [
+        q.rotation.into(),
+        q.shoulder.into(),
+        q.forearm.into(),
+        q.claw.into(),
+    ]
+}
+
+fn quantity<Unit>(a: Axes) -> Quantity<WithUnit<Unit>> {
+    Quantity {
+        rotation: a[0].into(),
+        shoulder: a[1].into(),
+        forearm: a[2].into(),
+        claw: a[3].into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        quantities::{Acceleration, Jerk},
+        units::{Radians, RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared},
+    };
+
+    const EPS: f32 = 1e-3;
+
+    fn pos(r: f32, s: f32, f: f32, c: f32) -> Position {
+        Position {
+            rotation: Radians::new(r),
+            shoulder: Radians::new(s),
+            forearm: Radians::new(f),
+            claw: Radians::new(c),
+        }
+    }
+
+    fn limits() -> MotionLimits {
+        MotionLimits {
+            max_speed: Velocity {
+                rotation: RadiansPerSecond::new(1.0),
+                shoulder: RadiansPerSecond::new(2.0),
+                forearm: RadiansPerSecond::new(2.0),
+                claw: RadiansPerSecond::new(3.0),
+            },
+            max_acceleration: Acceleration {
+                rotation: RadiansPerSecondSquared::new(2.0),
+                shoulder: RadiansPerSecondSquared::new(4.0),
+                forearm: RadiansPerSecondSquared::new(4.0),
+                claw: RadiansPerSecondSquared::new(6.0),
+            },
+            max_jerk: Jerk {
+                rotation: RadiansPerSecondCubed::new(10.0),
+                shoulder: RadiansPerSecondCubed::new(10.0),
+                forearm: RadiansPerSecondCubed::new(10.0),
+                claw: RadiansPerSecondCubed::new(10.0),
+            },
+        }
+    }
+
+    fn points(items: &[Position]) -> SplinePoints {
+        SplinePoints::try_from(items).unwrap()
+    }
+
+    fn zigzag() -> Spline {
+        Spline::new(
+            pos(0.0, 0.0, 0.0, 0.0),
+            &points(&[
+                pos(1.0, 0.5, 0.0, 0.2),
+                pos(1.5, -0.5, 0.3, 0.2),
+                pos(0.5, 0.0, 0.6, -0.4),
+                pos(1.0, 1.0, 0.0, 0.0),
+            ]),
+            &limits(),
+        )
+    }
+
+    fn close(a: Position, b: Position) -> bool {
+        f32::from((a - b).max_abs_component()) < EPS
+    }
+
+    fn samples(spline: &Spline) -> impl Iterator<Item = Seconds> {
+        let duration = f32::from(spline.duration());
+        (0..=2000).map(move |i| Seconds::new(duration * i as f32 / 2000.0))
+    }
+
+    #[test]
+    fn test_passes_through_points() {
+        let spline = zigzag();
+        for knot in spline.knots.iter() {
+            let t = Seconds::new(knot.time);
+            assert!(close(spline.position_at(t), quantity(knot.position)));
+        }
+        assert_eq!(spline.knots.len(), 5);
+        assert_eq!(
+            spline.position_at(spline.duration()),
+            pos(1.0, 1.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_starts_and_stops_at_rest() {
+        let spline = zigzag();
+        let zero: Velocity = quantity([0.0; 4]);
+        assert_eq!(spline.velocity_at(Seconds::new(0.0)), zero);
+        assert_eq!(spline.velocity_at(spline.duration()), zero);
+        assert!(close(
+            spline.position_at(Seconds::new(-1.0)),
+            pos(0.0, 0.0, 0.0, 0.0)
+        ));
+    }
+
+    #[test]
+    fn test_acceleration_is_continuous() {
+        let spline = zigzag();
+        for i in 1..spline.knots.len() - 1 {
+            let h_l = spline.knots[i].time - spline.knots[i - 1].time;
+            let left = spline.polynomial(i - 1, |c| eval_acceleration(c, h_l));
+            let right = spline.polynomial(i, |c| eval_acceleration(c, 0.0));
+            let left_v = spline.polynomial(i - 1, |c| eval_velocity(c, h_l));
+            let right_v = spline.polynomial(i, |c| eval_velocity(c, 0.0));
+            for axis in 0..4 {
+                assert!((left[axis] - right[axis]).abs() < EPS, "{left:?} {right:?}");
+                assert!((left_v[axis] - right_v[axis]).abs() < EPS);
+            }
+        }
+    }
+
+    #[test]
+    fn test_respects_limits() {
+        let spline = zigzag();
+        let speed = axes(limits().max_speed);
+        let accel = axes(limits().max_acceleration);
+
+        let mut prev: Option<(Axes, Axes)> = None;
+        let mut peak = 0.0f32;
+        for t in samples(&spline) {
+            let v = axes(spline.velocity_at(t));
+            let p = axes(spline.position_at(t));
+            for axis in 0..4 {
+                let ratio = v[axis].abs() / speed[axis];
+                assert!(ratio <= 1.0 + EPS, "t={t:?}, axis={axis}");
+                peak = peak.max(ratio);
+            }
+            if let Some((_, prev_v)) = prev {
+                let dt = f32::from(spline.duration()) / 2000.0;
+                for axis in 0..4 {
+                    let a = (v[axis] - prev_v[axis]) / dt;
+                    assert!(a.abs() <= accel[axis] * (1.0 + 1e-2), "t={t:?}");
+                }
+            }
+            prev = Some((p, v));
+        }
+
+        // Траектория не растянута сильнее необходимого.
+        let (mut v, mut a) = (0.0f32, 0.0f32);
+        for i in 0..spline.knots.len() - 1 {
+            let h = spline.knots[i + 1].time - spline.knots[i].time;
+            let vs = spline.polynomial(i, |c| max_abs_velocity(c, h));
+            let acc = spline.polynomial(i, |c| max_abs_acceleration(c, h));
+            for axis in 0..4 {
+                v = v.max(vs[axis] / speed[axis]);
+                a = a.max(acc[axis] / accel[axis]);
+            }
+        }
+        assert!((v.max(a) - 1.0).abs() < EPS, "v={v}, a={a}");
+        assert!(peak > 0.9 || a > 0.99);
+    }
+
+    #[test]
+    fn test_check_finds_overshoot_between_knots() {
+        // Узлы в пределах [-1, 1], но между ними сплайн выходит за границу.
+        let spline = Spline::new(
+            pos(0.0, 0.0, 0.0, 0.0),
+            &points(&[
+                pos(0.95, 0.0, 0.0, 0.0),
+                pos(0.99, 0.0, 0.0, 0.0),
+                pos(0.0, 0.0, 0.0, 0.0),
+            ]),
+            &limits(),
+        );
+        let peak = samples(&spline)
+            .map(|t| f32::from(spline.position_at(t).rotation))
+            .fold(f32::MIN, f32::max);
+        assert!(peak > 1.0);
+
+        let bounds = |max: f32| JointLimits {
+            min: pos(-1.0, -1.0, -1.0, -1.0),
+            max: pos(max, 1.0, 1.0, 1.0),
+        };
+        let violation = spline.check(&bounds(1.0)).unwrap_err();
+        assert_eq!(violation.axis, crate::quantities::Axis::Rotation);
+        assert!((f32::from(violation.value) - peak).abs() < EPS);
+        assert!(spline.check(&bounds(peak + EPS)).is_ok());
+    }
+
+    #[test]
+    fn test_duplicate_points_are_skipped() {
+        let src = pos(0.0, 0.0, 0.0, 0.0);
+        let spline = Spline::new(
+            src,
+            &points(&[src, pos(1.0, 0.0, 0.0, 0.0), pos(1.0, 0.0, 0.0, 0.0)]),
+            &limits(),
+        );
+        assert_eq!(spline.knots.len(), 2);
+        assert_eq!(spline.target(), pos(1.0, 0.0, 0.0, 0.0));
+        assert!(f32::from(spline.duration()).is_finite());
+    }
+
+    #[test]
+    fn test_empty_spline_stays_in_place() {
+        let src = pos(0.3, 0.0, 0.0, 0.0);
+        let spline = Spline::new(src, &SplinePoints::new(), &limits());
+        assert_eq!(spline.duration(), Seconds::new(0.0));
+        assert_eq!(spline.position_at(Seconds::new(1.0)), src);
+    }
+
+    #[test]
+    fn test_single_point_is_a_smooth_move() {
+        let spline = Spline::new(
+            pos(0.0, 0.0, 0.0, 0.0),
+            &points(&[pos(1.0, 0.0, 0.0, 0.0)]),
+            &limits(),
+        );
+        // Кубический полином с нулевыми скоростями на концах: пиковое ускорение
+        // 6Δ/T² = 2 рад/с², пиковая скорость 1.5Δ/T ≈ 0.87 рад/с.
+        assert!((f32::from(spline.duration()) - libm::sqrtf(3.0)).abs() < EPS);
+    }
+}
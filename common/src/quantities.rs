@@ -2,7 +2,10 @@ use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 use postcard::experimental::max_size::MaxSize;
 use serde::{Deserialize, Serialize};
 
-use crate::units::{Abs, Max, Radians, RadiansPerSecond, Seconds, WithUnit};
+use crate::units::{
+    Abs, Max, Radians, RadiansPerSecond, RadiansPerSecondCubed, RadiansPerSecondSquared, Seconds,
+    WithUnit,
+};
 
 pub trait MaxAbsComponent {
     type Output;
@@ -34,6 +37,44 @@ pub struct Quantity<Unit> {
     pub claw: Unit,
 }
 
+/// Ось (сочленение) манипулятора.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, MaxSize)]
+pub enum Axis {
+    Rotation,
+    Shoulder,
+    Forearm,
+    Claw,
+}
+
+impl Axis {
+    /// Все оси в порядке полей [`Quantity`].
+    pub const ALL: [Axis; 4] = [Axis::Rotation, Axis::Shoulder, Axis::Forearm, Axis::Claw];
+}
+
+impl<Unit> Quantity<Unit> {
+    /// Значение по оси `axis`.
+    #[inline]
+    pub fn get(&self, axis: Axis) -> &Unit {
+        match axis {
+            Axis::Rotation => &self.rotation,
+            Axis::Shoulder => &self.shoulder,
+            Axis::Forearm => &self.forearm,
+            Axis::Claw => &self.claw,
+        }
+    }
+
+    /// Изменяемое значение по оси `axis`.
+    #[inline]
+    pub fn get_mut(&mut self, axis: Axis) -> &mut Unit {
+        match axis {
+            Axis::Rotation => &mut self.rotation,
+            Axis::Shoulder => &mut self.shoulder,
+            Axis::Forearm => &mut self.forearm,
+            Axis::Claw => &mut self.claw,
+        }
+    }
+}
+
 impl<Unit> AddAssign for Quantity<Unit>
 where
     Unit: AddAssign,
@@ -222,4 +263,6 @@ where
 
 pub type Position = Quantity<Radians>;
 pub type Velocity = Quantity<RadiansPerSecond>;
+pub type Acceleration = Quantity<RadiansPerSecondSquared>;
+pub type Jerk = Quantity<RadiansPerSecondCubed>;
 pub type Duration = Quantity<Seconds>;
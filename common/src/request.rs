@@ -1,15 +1,356 @@
-use crate::{quantities::Position, units::RadianPerSecond, wifi_config::WifiConfig};
+use crate::{
+    calibration::ServoCalibration,
+    envelope::impl_message,
+    mechanics_config::JointLimits,
+    motion::{MotionProfile, Timing, jog::ContinuousJog, spline::SplinePoints},
+    quantities::{Acceleration, Axis, Jerk, Position, Velocity},
+    response::{Parameter, Rejection},
+    units::Radians,
+    wifi_config::WifiConfig,
+};
 use postcard::experimental::max_size::MaxSize;
 use serde::{Deserialize, Serialize};
 
+/// Наименьшая частота телеметрии, Гц.
+pub const MIN_TELEMETRY_RATE_HZ: u8 = 5;
+
+/// Наибольшая частота телеметрии, Гц.
+pub const MAX_TELEMETRY_RATE_HZ: u8 = 50;
+
+/// Наименьшее время ожидания сторожевого таймера, мс.
+pub const MIN_WATCHDOG_TIMEOUT_MS: u32 = 100;
+
+/// Наибольшее время ожидания сторожевого таймера, мс.
+pub const MAX_WATCHDOG_TIMEOUT_MS: u32 = 60_000;
+
+/// Максимальное количество точек в пакете перемещений.
+pub const MAX_BATCH_LEN: usize = 16;
+
+/// Пакет целевых точек, которые ставятся в очередь вместе.
+pub type WaypointBatch = crate::Vec<Waypoint, MAX_BATCH_LEN>;
+
+// Размер варианта с конфигурацией Wi-Fi намеренно не сокращается: без аллокатора
+// упаковка в `Box` невозможна, а объем сообщения ограничен `MaxSize`.
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Debug, PartialEq, MaxSize)]
 pub enum Request {
-    Enqueue(Position),
+    /// Целевая точка в очередь позиционирования. Если очередь заполнена,
+    /// запрос отклоняется с `Rejection::PositionQueueFull`; как этого
+    /// избежать, описано в [`crate::flow`].
+    Enqueue(Waypoint),
     Immediate(Command),
+    /// Движение по сплайну от последней целевой точки через набор точек.
+    /// Подтверждается одним `PositionAck` по завершении. Сплайн, который
+    /// между точками выходит за программные ограничения осей, отклоняется
+    /// с `Rejection::JointLimit`.
+    EnqueueSpline(SplinePoints),
+    /// Запрос текущего состояния манипулятора. Ответ — `Response::Status`.
+    GetStatus,
+    /// Подписка на телеметрию с частотой `rate_hz` в пределах
+    /// [`MIN_TELEMETRY_RATE_HZ`]..=[`MAX_TELEMETRY_RATE_HZ`]. Кадры
+    /// `Response::Telemetry` приходят до отписки или конца сессии.
+    Subscribe {
+        rate_hz: u8,
+    },
+    /// Отписка от телеметрии.
+    Unsubscribe,
+    /// Управление движением. Исполняется сразу, минуя очередь позиционирования
+    /// и очередь команд. Ответ — `Response::CommandAck`.
+    Control(MotionControl),
+    /// Перемещение на смещение относительно последней запланированной цели
+    /// (а не текущей позиции). Ставится в очередь позиционирования и
+    /// подтверждается `PositionAck`; цель вне программных ограничений
+    /// отклоняется при извлечении из очереди.
+    Jog(Position),
+    /// Непрерывное движение оси с заданной скоростью до остановки командой
+    /// `MotionControl::Stop` или истечения времени ожидания. Ставится в очередь
+    /// позиционирования; ось останавливается на программной границе.
+    JogContinuous(ContinuousJog),
+    /// Пакет целевых точек. Точки ставятся в очередь позиционирования все
+    /// вместе или пакет отклоняется целиком. Каждая точка подтверждается
+    /// отдельно, с идентификатором запроса.
+    EnqueueBatch(WaypointBatch),
+    /// Признак жизни клиента для сторожевого таймера. Ответа не требует:
+    /// таймер сбрасывает любой кадр.
+    Keepalive,
+    /// Сторожевой таймер сессии: если за `timeout_ms` от клиента не пришло ни
+    /// одного кадра, прошивка останавливает движение, очищает очередь
+    /// позиционирования и закрывает соединение, а следующий клиент узнает об
+    /// этом из `Welcome::watchdog_tripped`. Время задается в пределах
+    /// [`MIN_WATCHDOG_TIMEOUT_MS`]..=[`MAX_WATCHDOG_TIMEOUT_MS`], ноль
+    /// отключает таймер. В новой сессии таймер отключен.
+    /// Ответ — `Response::CommandAck`.
+    SetWatchdog {
+        timeout_ms: u32,
+    },
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, MaxSize)]
-pub enum Command {
-    SetMaxSpeed(RadianPerSecond),
-    ConfigureWifi(WifiConfig),
+// Типы сообщений совпадают с номерами вариантов. Удаленный вариант оставляет
+// свой тип неиспользуемым.
+impl_message!(Request {
+    0 => Enqueue(waypoint),
+    1 => Immediate(command: Command::decode),
+    2 => EnqueueSpline(points),
+    3 => GetStatus,
+    4 => Subscribe { rate_hz },
+    5 => Unsubscribe,
+    6 => Control(action),
+    7 => Jog(offset),
+    8 => JogContinuous(jog),
+    9 => EnqueueBatch(batch),
+    10 => Keepalive,
+    11 => SetWatchdog { timeout_ms },
+});
+
+impl Request {
+    /// Количество мест в очереди позиционирования, которые занимает запрос.
+    pub fn queue_slots(&self) -> usize {
+        match self {
+            Self::Enqueue(_) | Self::EnqueueSpline(_) | Self::Jog(_) | Self::JogContinuous(_) => 1,
+            Self::EnqueueBatch(batch) => batch.len(),
+            _ => 0,
+        }
+    }
+
+    /// Проверяет параметры запроса, не зависящие от состояния манипулятора.
+    ///
+    /// Целевые позиции проверяются по программным ограничениям осей раньше
+    /// остальных параметров; пакет точек отклоняется целиком. Цель
+    /// относительного перемещения и траектория сплайна между точками зависят
+    /// от начальной позиции, известной только позиционеру, и он проверяет их
+    /// сам.
+    pub fn validate(&self, limits: &JointLimits) -> Result<(), Rejection> {
+        let checked = match self {
+            Self::Enqueue(waypoint) => limits.check(&waypoint.position),
+            Self::EnqueueSpline(points) => {
+                points.as_slice().iter().try_for_each(|p| limits.check(p))
+            }
+            Self::EnqueueBatch(batch) => batch
+                .as_slice()
+                .iter()
+                .try_for_each(|waypoint| limits.check(&waypoint.position)),
+            _ => Ok(()),
+        };
+        checked.map_err(Rejection::JointLimit)?;
+
+        let invalid_timing = |waypoint: &Waypoint| waypoint.timing.is_some_and(|t| !t.is_valid());
+        let parameter = match *self {
+            Self::Enqueue(ref waypoint) if invalid_timing(waypoint) => Parameter::Timing,
+            Self::JogContinuous(ref jog) if !jog.is_valid() => Parameter::JogSpeed,
+            Self::EnqueueBatch(ref batch) if batch.is_empty() => Parameter::BatchLength,
+            Self::EnqueueBatch(ref batch) if batch.as_slice().iter().any(invalid_timing) => {
+                Parameter::Timing
+            }
+            Self::Subscribe { rate_hz }
+                if !(MIN_TELEMETRY_RATE_HZ..=MAX_TELEMETRY_RATE_HZ).contains(&rate_hz) =>
+            {
+                Parameter::TelemetryRate
+            }
+            Self::SetWatchdog { timeout_ms }
+                if timeout_ms != 0
+                    && !(MIN_WATCHDOG_TIMEOUT_MS..=MAX_WATCHDOG_TIMEOUT_MS)
+                        .contains(&timeout_ms) =>
+            {
+                Parameter::WatchdogTimeout
+            }
+            _ => return Ok(()),
+        };
+        Err(Rejection::InvalidParameter(parameter))
+    }
+}
+
+/// Управление движением.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, MaxSize)]
+pub enum MotionControl {
+    /// Плавная остановка с очисткой очереди. Прерванное и удаленные из очереди
+    /// перемещения подтверждаются `Response::MotionCancelled`.
+    Stop,
+    /// Плавная приостановка текущего перемещения.
+    Pause,
+    /// Продолжение приостановленного перемещения.
+    Resume,
+    /// Очистка очереди позиционирования. Удаленные перемещения подтверждаются
+    /// `Response::MotionCancelled`, текущее перемещение продолжается.
+    ClearQueue,
+}
+
+/// Целевая точка перемещения.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, MaxSize)]
+pub struct Waypoint {
+    /// Позиция манипулятора.
+    pub position: Position,
+
+    /// Профиль скорости перемещения. Если не задан, используется профиль
+    /// из конфигурации механики.
+    pub profile: Option<MotionProfile>,
+
+    /// Темп перемещения. Если не задан, оси движутся с предельными
+    /// скоростями. Недостижимое время перемещения отклоняется с
+    /// `Rejection::InfeasibleDuration` при извлечении из очереди.
+    pub timing: Option<Timing>,
+}
+
+impl From<Position> for Waypoint {
+    #[inline]
+    fn from(position: Position) -> Self {
+        Self {
+            position,
+            profile: None,
+            timing: None,
+        }
+    }
+}
+
+/// Объявляет перечисление команд и его нумерацию по одному списку
+/// вариантов.
+///
+/// Номера вариантов совпадают с кодами вариантов в postcard и задают биты
+/// `CommandSet`, поэтому выводятся из порядка объявления, а не пишутся
+/// вручную.
+macro_rules! commands {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $(
+                $(#[$variant_meta:meta])*
+                $variant:ident $(($($field:ty),+))?
+            ),+ $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        pub enum $name {
+            $(
+                $(#[$variant_meta])*
+                $variant $(($($field),+))?
+            ),+
+        }
+
+        impl $name {
+            /// Количество вариантов команд.
+            pub const COUNT: usize = [$(stringify!($variant)),+].len();
+
+            /// Разбирает команду. Возвращает `None`, если номер варианта
+            /// неизвестен: команду добавили в более новой версии протокола.
+            pub fn decode(payload: &[u8]) -> postcard::Result<Option<Self>> {
+                let (index, _) = postcard::take_from_bytes::<u32>(payload)?;
+                if index as usize >= Self::COUNT {
+                    return Ok(None);
+                }
+                postcard::from_bytes(payload).map(Some)
+            }
+
+            /// Номер варианта команды. Совпадает с кодом варианта в postcard.
+            pub fn index(&self) -> usize {
+                // Варианты без данных нумеруются так же, как исходные.
+                enum Index {
+                    $($variant),+
+                }
+                match self {
+                    $(Self::$variant { .. } => Index::$variant as usize),+
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    #[allow(clippy::large_enum_variant)]
+    #[derive(Serialize, Deserialize, Debug, PartialEq, MaxSize)]
+    pub enum Command {
+        /// Ограничение максимальной скорости перемещения по осям.
+        SetMaxSpeed(Velocity),
+        ConfigureWifi(WifiConfig),
+        /// Позиция манипулятора при включении (применяется после перезагрузки).
+        SetInitPosition(Position),
+        /// Ограничение максимального ускорения по осям.
+        SetMaxAcceleration(Acceleration),
+        /// Ограничение максимального рывка по осям.
+        SetMaxJerk(Jerk),
+        /// Профиль скорости, используемый при перемещениях.
+        SetMotionProfile(MotionProfile),
+        /// Допуск скругления траектории в промежуточных точках.
+        SetBlendTolerance(Radians),
+        /// Калибровка сервопривода оси: сохраняется во Flash-памяти и
+        /// применяется сразу.
+        SetCalibration(Axis, ServoCalibration),
+        /// Калибровка сервопривода оси для проверки: применяется сразу, но не
+        /// сохраняется и действует до перезагрузки.
+        TestCalibration(Axis, ServoCalibration),
+        /// Запрос действующей калибровки. Ответ — `Response::Calibration`.
+        GetCalibration,
+        /// Программные ограничения углов сочленений.
+        SetJointLimits(JointLimits),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::motion::Timing;
+
+    #[test]
+    fn test_validate() {
+        let limits = JointLimits {
+            min: Position {
+                rotation: Radians::new(-1.0),
+                shoulder: Radians::new(-1.0),
+                forearm: Radians::new(-1.0),
+                claw: Radians::new(-1.0),
+            },
+            max: Position {
+                rotation: Radians::new(1.0),
+                shoulder: Radians::new(1.0),
+                forearm: Radians::new(1.0),
+                claw: Radians::new(1.0),
+            },
+        };
+        let inside = Waypoint::from(Position {
+            rotation: Radians::new(0.5),
+            ..limits.min
+        });
+        let invalid_timing = Waypoint {
+            timing: Some(Timing::SpeedScale(0.0)),
+            ..inside
+        };
+        assert_eq!(Request::Enqueue(inside).validate(&limits), Ok(()));
+        assert_eq!(
+            Request::Enqueue(invalid_timing).validate(&limits),
+            Err(Rejection::InvalidParameter(Parameter::Timing))
+        );
+
+        // Ограничения осей проверяются раньше темпа.
+        let outside = Waypoint {
+            position: Position {
+                claw: Radians::new(2.0),
+                ..inside.position
+            },
+            ..invalid_timing
+        };
+        assert!(matches!(
+            Request::Enqueue(outside).validate(&limits),
+            Err(Rejection::JointLimit(_))
+        ));
+
+        let batch = WaypointBatch::try_from([inside, invalid_timing].as_slice()).unwrap();
+        assert_eq!(
+            Request::EnqueueBatch(batch).validate(&limits),
+            Err(Rejection::InvalidParameter(Parameter::Timing))
+        );
+        assert_eq!(
+            Request::EnqueueBatch(WaypointBatch::new()).validate(&limits),
+            Err(Rejection::InvalidParameter(Parameter::BatchLength))
+        );
+        assert_eq!(
+            Request::Subscribe { rate_hz: 0 }.validate(&limits),
+            Err(Rejection::InvalidParameter(Parameter::TelemetryRate))
+        );
+        assert_eq!(
+            Request::SetWatchdog { timeout_ms: 0 }.validate(&limits),
+            Ok(())
+        );
+        assert_eq!(
+            Request::SetWatchdog { timeout_ms: 1 }.validate(&limits),
+            Err(Rejection::InvalidParameter(Parameter::WatchdogTimeout))
+        );
+    }
 }
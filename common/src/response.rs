@@ -1,8 +1,151 @@
+use crate::{
+    calibration::Calibration,
+    envelope::impl_message,
+    mechanics_config::{Bound, LimitViolation},
+    quantities::Position,
+    units::Seconds,
+};
+use core::fmt;
 use postcard::experimental::max_size::MaxSize;
 use serde::{Deserialize, Serialize};
 
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, MaxSize)]
 pub enum Response {
-    PositionAck,
+    /// Перемещение завершено. Сообщает свободное место в очереди
+    /// позиционирования на момент отправки.
+    PositionAck {
+        free_slots: u8,
+    },
     CommandAck,
+    /// Действующая калибровка сервоприводов.
+    Calibration(Calibration),
+    /// Запрос отклонен и не исполнялся. Сессия при этом сохраняется.
+    Rejected(Rejection),
+    /// Текущее состояние манипулятора.
+    Status(Status),
+    /// Кадр телеметрии по подписке.
+    Telemetry(Telemetry),
+    /// Перемещение отменено остановкой или очисткой очереди и не будет
+    /// завершено.
+    MotionCancelled,
+}
+
+// Типы сообщений совпадают с номерами вариантов. Удаленный вариант оставляет
+// свой тип неиспользуемым.
+impl_message!(Response {
+    0 => PositionAck { free_slots },
+    1 => CommandAck,
+    2 => Calibration(calibration),
+    3 => Rejected(rejection),
+    4 => Status(status),
+    5 => Telemetry(telemetry),
+    6 => MotionCancelled,
+});
+
+/// Состояние движения манипулятора.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, MaxSize)]
+pub enum MotionState {
+    /// Движение завершено, очередь пуста.
+    Idle,
+    /// Манипулятор движется.
+    Moving,
+    /// Движение приостановлено.
+    Paused,
+}
+
+/// Текущее состояние манипулятора.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, MaxSize)]
+pub struct Status {
+    /// Текущая позиция приводов.
+    pub position: Position,
+    /// Цель исполняемого перемещения. В покое совпадает с текущей позицией.
+    pub target: Position,
+    /// Количество перемещений в очереди позиционирования.
+    pub queued: u8,
+    /// Емкость очереди позиционирования.
+    pub queue_capacity: u8,
+    /// Состояние движения.
+    pub state: MotionState,
+    /// Время с момента включения, мс.
+    pub uptime_ms: u64,
+}
+
+/// Кадр телеметрии.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, MaxSize)]
+pub struct Telemetry {
+    /// Момент, к которому относится позиция: время с момента включения, мкс.
+    pub timestamp_us: u64,
+    /// Позиция приводов.
+    pub position: Position,
+    /// Цель исполняемого перемещения.
+    pub target: Position,
+}
+
+/// Причина отклонения запроса.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, MaxSize)]
+pub enum Rejection {
+    /// Целевая позиция выходит за программные ограничения оси.
+    JointLimit(LimitViolation),
+    /// Очередь позиционирования заполнена.
+    PositionQueueFull,
+    /// Очередь команд заполнена: предыдущая команда еще выполняется.
+    CommandQueueFull,
+    /// Кадр не удалось разобрать или он превышает допустимый размер.
+    MalformedFrame,
+    /// Недопустимое значение параметра команды.
+    InvalidParameter(Parameter),
+    /// Не удалось сохранить настройки во Flash-памяти; настройки не применены.
+    StorageFailure,
+    /// Запрос не поддерживается прошивкой.
+    Unsupported,
+    /// Перемещение не успеть за заданное время; указано наименьшее
+    /// достижимое.
+    InfeasibleDuration(Seconds),
+}
+
+/// Параметр команды.
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Eq, MaxSize)]
+pub enum Parameter {
+    InitPosition,
+    MaxSpeed,
+    MaxAcceleration,
+    MaxJerk,
+    BlendTolerance,
+    JointLimits,
+    Calibration,
+    TelemetryRate,
+    JogSpeed,
+    Timing,
+    BatchLength,
+    WatchdogTimeout,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::JointLimit(v) => {
+                let bound = match v.bound {
+                    Bound::Lower => "ниже нижней",
+                    Bound::Upper => "выше верхней",
+                };
+                write!(
+                    f,
+                    "ось {:?}: угол {} рад {} границы {} рад",
+                    v.axis,
+                    f32::from(v.value),
+                    bound,
+                    f32::from(v.limit)
+                )
+            }
+            Self::PositionQueueFull => f.write_str("очередь позиционирования заполнена"),
+            Self::CommandQueueFull => f.write_str("предыдущая команда еще выполняется"),
+            Self::MalformedFrame => f.write_str("некорректный кадр"),
+            Self::InvalidParameter(p) => write!(f, "недопустимое значение параметра {p:?}"),
+            Self::StorageFailure => f.write_str("не удалось сохранить настройки во Flash-памяти"),
+            Self::Unsupported => f.write_str("запрос не поддерживается"),
+            Self::InfeasibleDuration(minimum) => {
+                write!(f, "перемещение займет не менее {} с", f32::from(*minimum))
+            }
+        }
+    }
 }
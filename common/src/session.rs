@@ -0,0 +1,62 @@
+//! # Sessions
+//!
+//! Идентификаторы сессий и запросов. Позволяют клиенту сопоставлять ответы с
+//! запросами и отбрасывать ответы, относящиеся к прошлому подключению.
+
+use postcard::experimental::max_size::MaxSize;
+use serde::{Deserialize, Serialize};
+
+/// Идентификатор сессии. Назначается прошивкой при установлении соединения.
+pub type SessionId = u32;
+
+/// Идентификатор запроса. Выбирается клиентом произвольно.
+pub type RequestId = u32;
+
+/// Сообщение с необязательным идентификатором запроса.
+///
+/// Клиент помечает запрос идентификатором, прошивка возвращает тот же
+/// идентификатор во всех ответах на этот запрос.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, MaxSize)]
+pub struct Tagged<T> {
+    /// Идентификатор запроса, если клиент его задал.
+    pub id: Option<RequestId>,
+
+    /// Запрос или ответ.
+    pub message: T,
+}
+
+impl<T> Tagged<T> {
+    /// Сообщение с идентификатором `id`.
+    #[inline]
+    pub fn new(id: Option<RequestId>, message: T) -> Self {
+        Self { id, message }
+    }
+}
+
+impl<T> From<T> for Tagged<T> {
+    /// Сообщение без идентификатора.
+    #[inline]
+    fn from(message: T) -> Self {
+        Self::new(None, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        request::{Command, Request},
+        response::Response,
+    };
+
+    #[test]
+    fn test_roundtrip() {
+        let request = Tagged::new(Some(42), Request::Immediate(Command::GetCalibration));
+        let bytes = crate::to_vec::<_, 16>(&request).unwrap();
+        assert_eq!(crate::from_bytes::<Tagged<Request>>(&bytes), Ok(request));
+
+        let response = Tagged::from(Response::PositionAck { free_slots: 16 });
+        let bytes = crate::to_vec::<_, 16>(&response).unwrap();
+        assert_eq!(crate::from_bytes::<Tagged<Response>>(&bytes), Ok(response));
+    }
+}
@@ -19,6 +19,8 @@ pub const RADIAN: Radians = Radians::new(1.0);
 pub const SECOND: Seconds = Seconds::new(1.0);
 pub const SQUARE_MILLIMETER: SquareMillimeters = SquareMillimeters::new(1.0);
 pub const RADIAN_PER_SECOND: RadiansPerSecond = RadiansPerSecond::new(1.0);
+pub const RADIAN_PER_SECOND_SQUARED: RadiansPerSecondSquared = RadiansPerSecondSquared::new(1.0);
+pub const RADIAN_PER_SECOND_CUBED: RadiansPerSecondCubed = RadiansPerSecondCubed::new(1.0);
 
 pub trait Max {
     /// Возвращает максимальное из двух чисел.
@@ -185,6 +187,16 @@ pub struct RadianPerSecond;
 /// Угловая скорость.
 pub type RadiansPerSecond = WithUnit<RadianPerSecond>;
 
+#[derive(Debug, Copy, Clone, MaxSize, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct RadianPerSecondSquared;
+/// Угловое ускорение.
+pub type RadiansPerSecondSquared = WithUnit<RadianPerSecondSquared>;
+
+#[derive(Debug, Copy, Clone, MaxSize, PartialEq, PartialOrd, Deserialize, Serialize)]
+pub struct RadianPerSecondCubed;
+/// Угловой рывок (скорость изменения ускорения).
+pub type RadiansPerSecondCubed = WithUnit<RadianPerSecondCubed>;
+
 // --- Геометрические взаимодействия ---
 
 impl Mul for Millimeters {
@@ -237,6 +249,70 @@ impl Mul<RadiansPerSecond> for Seconds {
     }
 }
 
+impl Div<Seconds> for RadiansPerSecond {
+    type Output = RadiansPerSecondSquared;
+    #[inline]
+    fn div(self, rhs: Seconds) -> Self::Output {
+        RadiansPerSecondSquared::new(self.0 / rhs.0)
+    }
+}
+
+impl Div<RadiansPerSecondSquared> for RadiansPerSecond {
+    type Output = Seconds;
+    #[inline]
+    fn div(self, rhs: RadiansPerSecondSquared) -> Self::Output {
+        Seconds::new(self.0 / rhs.0)
+    }
+}
+
+impl Mul<Seconds> for RadiansPerSecondSquared {
+    type Output = RadiansPerSecond;
+    #[inline]
+    fn mul(self, rhs: Seconds) -> Self::Output {
+        RadiansPerSecond::new(self.0 * rhs.0)
+    }
+}
+
+impl Mul<RadiansPerSecondSquared> for Seconds {
+    type Output = RadiansPerSecond;
+    #[inline]
+    fn mul(self, rhs: RadiansPerSecondSquared) -> Self::Output {
+        RadiansPerSecond::new(self.0 * rhs.0)
+    }
+}
+
+impl Div<Seconds> for RadiansPerSecondSquared {
+    type Output = RadiansPerSecondCubed;
+    #[inline]
+    fn div(self, rhs: Seconds) -> Self::Output {
+        RadiansPerSecondCubed::new(self.0 / rhs.0)
+    }
+}
+
+impl Div<RadiansPerSecondCubed> for RadiansPerSecondSquared {
+    type Output = Seconds;
+    #[inline]
+    fn div(self, rhs: RadiansPerSecondCubed) -> Self::Output {
+        Seconds::new(self.0 / rhs.0)
+    }
+}
+
+impl Mul<Seconds> for RadiansPerSecondCubed {
+    type Output = RadiansPerSecondSquared;
+    #[inline]
+    fn mul(self, rhs: Seconds) -> Self::Output {
+        RadiansPerSecondSquared::new(self.0 * rhs.0)
+    }
+}
+
+impl Mul<RadiansPerSecondCubed> for Seconds {
+    type Output = RadiansPerSecondSquared;
+    #[inline]
+    fn mul(self, rhs: RadiansPerSecondCubed) -> Self::Output {
+        RadiansPerSecondSquared::new(self.0 * rhs.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -331,4 +407,28 @@ mod tests {
         assert_eq!(expected_speed * time, distance);
         assert_eq!(time * expected_speed, distance);
     }
+
+    #[test]
+    fn test_acceleration() {
+        let speed = RadiansPerSecond::new(PI);
+        let time = Seconds::new(2.0);
+        let expected_accel = RadiansPerSecondSquared::new(PI / 2.0);
+
+        assert_eq!(speed / time, expected_accel);
+        assert_eq!(speed / expected_accel, time);
+        assert_eq!(expected_accel * time, speed);
+        assert_eq!(time * expected_accel, speed);
+    }
+
+    #[test]
+    fn test_jerk() {
+        let accel = RadiansPerSecondSquared::new(PI);
+        let time = Seconds::new(0.5);
+        let expected_jerk = RadiansPerSecondCubed::new(2.0 * PI);
+
+        assert_eq!(accel / time, expected_jerk);
+        assert_eq!(accel / expected_jerk, time);
+        assert_eq!(expected_jerk * time, accel);
+        assert_eq!(time * expected_jerk, accel);
+    }
 }
@@ -1,31 +1,180 @@
-use common::quantities::Position;
+use common::{
+    calibration::Calibration, mechanics_config::StartupMechanicsConfig, quantities::Position,
+    request::MotionControl, units::Radians,
+};
+use core::sync::atomic::{AtomicU32, Ordering, fence};
 use embassy_sync::{
     blocking_mutex::raw::CriticalSectionRawMutex,
     channel::{Channel, Receiver, Sender},
+    signal::Signal,
 };
+use embassy_time::Instant;
 
 use crate::mk_static;
 
+pub use common::{
+    api::Tag,
+    motion::executor::{Motion, Outcome},
+};
+
 const POS_QUEUE_LEN: usize = 16;
 
+// Пакет точек должен помещаться в пустую очередь.
+const _: () = assert!(common::request::MAX_BATCH_LEN <= POS_QUEUE_LEN);
+const CONTROL_QUEUE_LEN: usize = 4;
+
 // Канал-очередь для передачи позиций от сетевого API к позиционеру.
-pub type PosChan = Channel<CriticalSectionRawMutex, Position, POS_QUEUE_LEN>;
-pub type PosSender = Sender<'static, CriticalSectionRawMutex, Position, POS_QUEUE_LEN>;
-pub type PosReceiver = Receiver<'static, CriticalSectionRawMutex, Position, POS_QUEUE_LEN>;
+pub type PosChan = Channel<CriticalSectionRawMutex, (Tag, Motion), POS_QUEUE_LEN>;
+pub type PosSender = Sender<'static, CriticalSectionRawMutex, (Tag, Motion), POS_QUEUE_LEN>;
+pub type PosReceiver = Receiver<'static, CriticalSectionRawMutex, (Tag, Motion), POS_QUEUE_LEN>;
+
+// Канал для передачи итогов перемещений от позиционера к сетевому API.
+pub type PosAckChan = Channel<CriticalSectionRawMutex, (Tag, Outcome), 1>;
+pub type PosAckSender = Sender<'static, CriticalSectionRawMutex, (Tag, Outcome), 1>;
+pub type PosAckReceiver = Receiver<'static, CriticalSectionRawMutex, (Tag, Outcome), 1>;
+
+// Канал для передачи команд управления движением от сетевого API
+// к позиционеру в обход очереди позиционирования.
+pub type ControlChan = Channel<CriticalSectionRawMutex, MotionControl, CONTROL_QUEUE_LEN>;
+pub type ControlSender = Sender<'static, CriticalSectionRawMutex, MotionControl, CONTROL_QUEUE_LEN>;
+pub type ControlReceiver =
+    Receiver<'static, CriticalSectionRawMutex, MotionControl, CONTROL_QUEUE_LEN>;
+
+// Сигнал обновления конфигурации механики. Передает параметры, прочитанные из
+// хранилища при старте или измененные командой, от конфигуратора к позиционеру.
+pub type SignalMechanicsConfig = Signal<CriticalSectionRawMutex, StartupMechanicsConfig>;
+
+// Сигнал обновления калибровки сервоприводов. Передает калибровку от
+// конфигуратора к позиционеру.
+pub type SignalCalibration = Signal<CriticalSectionRawMutex, Calibration>;
+
+/// Состояние движения, которое публикует позиционер.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionSnapshot {
+    /// Текущая позиция приводов.
+    pub position: Position,
+    /// Цель исполняемого перемещения.
+    pub target: Position,
+    /// Манипулятор движется.
+    pub moving: bool,
+    /// Движение приостановлено командой.
+    pub paused: bool,
+    /// Момент, к которому относится позиция.
+    pub timestamp: Instant,
+}
+
+const SNAPSHOT_WORDS: usize = 12;
+
+impl MotionSnapshot {
+    fn to_words(self) -> [u32; SNAPSHOT_WORDS] {
+        let (p, t) = (self.position, self.target);
+        let us = self.timestamp.as_micros();
+        [
+            f32::from(p.rotation).to_bits(),
+            f32::from(p.shoulder).to_bits(),
+            f32::from(p.forearm).to_bits(),
+            f32::from(p.claw).to_bits(),
+            f32::from(t.rotation).to_bits(),
+            f32::from(t.shoulder).to_bits(),
+            f32::from(t.forearm).to_bits(),
+            f32::from(t.claw).to_bits(),
+            self.moving as u32,
+            us as u32,
+            (us >> 32) as u32,
+            self.paused as u32,
+        ]
+    }
+
+    fn from_words(w: &[u32; SNAPSHOT_WORDS]) -> Self {
+        let angle = |i: usize| Radians::new(f32::from_bits(w[i]));
+        Self {
+            position: Position {
+                rotation: angle(0),
+                shoulder: angle(1),
+                forearm: angle(2),
+                claw: angle(3),
+            },
+            target: Position {
+                rotation: angle(4),
+                shoulder: angle(5),
+                forearm: angle(6),
+                claw: angle(7),
+            },
+            moving: w[8] != 0,
+            timestamp: Instant::from_micros(w[9] as u64 | (w[10] as u64) << 32),
+            paused: w[11] != 0,
+        }
+    }
+}
+
+/// Снимок состояния движения, разделяемый между ядрами без блокировок.
+///
+/// Последовательная блокировка (seqlock): позиционер — единственный писатель и
+/// никогда не ждет, читатель повторяет чтение, если застал запись. Нечетный
+/// счетчик означает, что запись в процессе.
+pub struct SharedMotionSnapshot {
+    seq: AtomicU32,
+    words: [AtomicU32; SNAPSHOT_WORDS],
+}
+
+impl SharedMotionSnapshot {
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+            words: [const { AtomicU32::new(0) }; SNAPSHOT_WORDS],
+        }
+    }
 
-// Канал для передачи подтверждений применения позиции от позиционера
-// к сетевому API.
-pub type PosAckChan = Channel<CriticalSectionRawMutex, (), 1>;
-pub type PosAckSender = Sender<'static, CriticalSectionRawMutex, (), 1>;
-pub type PosAckReceiver = Receiver<'static, CriticalSectionRawMutex, (), 1>;
+    /// Публикует снимок. Вызывается только позиционером.
+    pub fn publish(&self, snapshot: MotionSnapshot) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+        for (word, value) in self.words.iter().zip(snapshot.to_words()) {
+            word.store(value, Ordering::Relaxed);
+        }
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Читает последний целостный снимок.
+    pub fn read(&self) -> MotionSnapshot {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                core::hint::spin_loop();
+                continue;
+            }
+            let words = core::array::from_fn(|i| self.words[i].load(Ordering::Relaxed));
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                return MotionSnapshot::from_words(&words);
+            }
+        }
+    }
+}
 
 pub struct Connectors {
     // Очередь для передачи позиций от сетевого API к позиционеру.
     pub pos: PosChan,
 
-    // Канал для передачи подтверждений применения позиции от позиционера
-    // к сетевому API.
+    // Канал для передачи итогов перемещений от позиционера к сетевому API.
     pub pos_ack: PosAckChan,
+
+    // Канал для передачи команд управления движением от сетевого API
+    // к позиционеру.
+    pub control: ControlChan,
+
+    // Сигнал обновления конфигурации механики. Передает параметры от
+    // конфигуратора к позиционеру.
+    pub mechanics_config: SignalMechanicsConfig,
+
+    // Сигнал обновления калибровки сервоприводов. Передает калибровку от
+    // конфигуратора к позиционеру.
+    pub calibration: SignalCalibration,
+
+    // Снимок состояния движения. Публикуется позиционером, читается сетевым
+    // API по запросу клиента.
+    pub motion_snapshot: SharedMotionSnapshot,
 }
 
 impl Connectors {
@@ -35,6 +184,10 @@ impl Connectors {
             Self {
                 pos: Channel::new(),
                 pos_ack: Channel::new(),
+                control: Channel::new(),
+                mechanics_config: Signal::new(),
+                calibration: Signal::new(),
+                motion_snapshot: SharedMotionSnapshot::new(),
             }
         )
     }
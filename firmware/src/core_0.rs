@@ -3,7 +3,10 @@ mod connectors;
 mod network;
 
 use crate::{
-    connectors::{PosAckReceiver, PosSender},
+    connectors::{
+        ControlSender, PosAckReceiver, PosReceiver, PosSender, SharedMotionSnapshot,
+        SignalCalibration, SignalMechanicsConfig,
+    },
     core_0::network::Network,
     mk_static,
 };
@@ -30,13 +33,23 @@ impl Core0 {
         Self { timg0, flash, wifi }
     }
 
-    pub async fn run(self, pos_tx: PosSender, pos_ack_rx: PosAckReceiver) -> ! {
+    pub async fn run(
+        self,
+        pos_tx: PosSender,
+        pos_rx: PosReceiver,
+        pos_ack_rx: PosAckReceiver,
+        control_tx: ControlSender,
+        mechanics_config: &'static SignalMechanicsConfig,
+        calibration: &'static SignalCalibration,
+        motion_snapshot: &'static SharedMotionSnapshot,
+    ) -> ! {
         let Self { timg0, flash, wifi } = self;
 
         let Connectors {
             cmd,
             cmd_ack,
             config_updated,
+            joint_limits,
         } = Connectors::new();
 
         esp_rtos::start(TimerGroup::new(timg0).timer0);
@@ -55,8 +68,25 @@ impl Core0 {
 
         // Запуск конфигуратора и сети.
         match select(
-            configurator.run(cmd_rx, cmd_ack_tx, config_updated_tx),
-            network.run(pos_tx, pos_ack_rx, cmd_tx, cmd_ack_rx, config_updated_rx),
+            configurator.run(
+                cmd_rx,
+                cmd_ack_tx,
+                config_updated_tx,
+                mechanics_config,
+                calibration,
+                joint_limits,
+            ),
+            network.run(
+                pos_tx,
+                pos_rx,
+                pos_ack_rx,
+                control_tx,
+                cmd_tx,
+                cmd_ack_rx,
+                config_updated_rx,
+                joint_limits,
+                motion_snapshot,
+            ),
         )
         .await
         {
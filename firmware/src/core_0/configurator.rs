@@ -1,14 +1,16 @@
 mod conf_stor;
 
 use crate::{
-    core_0::{
-        configurator::conf_stor::StorageError,
-        connectors::{CmdAckSender, CmdReceiver, SignalConfigUpdated},
-    },
+    connectors::{SignalCalibration, SignalMechanicsConfig},
+    core_0::connectors::{CmdAckSender, CmdReceiver, SharedJointLimits, SignalConfigUpdated},
     mk_static,
 };
-use common::request::Command;
+use common::{
+    calibration::Calibration, configurator::Listeners, mechanics_config::StartupMechanicsConfig,
+    wifi_config::WifiConfig,
+};
 use conf_stor::{ConfigStorage, flash_async::Flash};
+use core::fmt;
 use embassy_sync::blocking_mutex::raw::NoopRawMutex;
 use esp_hal::peripherals::FLASH;
 use esp_println::println;
@@ -30,44 +32,53 @@ impl Configurator {
         cmd_rx: CmdReceiver<'_>,
         cmd_ack_tx: CmdAckSender<'_>,
         config_updated: &SignalConfigUpdated,
+        mechanics_config: &SignalMechanicsConfig,
+        calibration: &SignalCalibration,
+        joint_limits: &SharedJointLimits,
     ) -> ! {
         let Self { flash } = self;
-        let mut storage = ConfigStorage::new(flash);
-
-        let initial_cfg = match storage.fetch_wifi().await {
-            Ok(cfg) => {
-                println!("CONFIGURATOR: config fetched");
-                cfg
-            }
-            Err(StorageError::NotFound) => {
-                println!("CONFIGURATOR: config not found");
-                println!("CONFIGURATOR: using default config");
-                common::wifi_config::WifiConfig::default()
-            }
-            Err(err) => {
-                println!("CONFIGURATOR ERROR: failed to fetched config: {err:?}");
-                println!("CONFIGURATOR: using default config");
-                common::wifi_config::WifiConfig::default()
-            }
+        let listeners = ConfigListeners {
+            config_updated,
+            mechanics_config,
+            calibration,
+            joint_limits,
         };
-        config_updated.signal(initial_cfg);
-        loop {
-            let command = cmd_rx.receive().await;
+        let mut configurator =
+            common::configurator::Configurator::start(ConfigStorage::new(flash), listeners).await;
 
-            match command {
-                Command::ConfigureWifi(new_cfg) => {
-                    println!("CONFIGURATOR: saving new WiFi config...");
-                    if let Err(e) = storage.store_wifi(new_cfg.clone()).await {
-                        println!(
-                            "CONFIGURATOR ERROR: failed to save to flash memory: {:?}",
-                            e
-                        );
-                    }
-                    config_updated.signal(new_cfg);
-                }
-                Command::SetMaxSpeed(_) => todo!(),
-            }
-            cmd_ack_tx.send(()).await
+        loop {
+            let (tag, command) = cmd_rx.receive().await;
+            let response = configurator.handle(command).await;
+            cmd_ack_tx.send((tag, response)).await
         }
     }
 }
+
+/// Получатели действующей конфигурации: сетевой менеджер, позиционер и
+/// сетевое API.
+struct ConfigListeners<'a> {
+    config_updated: &'a SignalConfigUpdated,
+    mechanics_config: &'a SignalMechanicsConfig,
+    calibration: &'a SignalCalibration,
+    joint_limits: &'a SharedJointLimits,
+}
+
+impl Listeners for ConfigListeners<'_> {
+    fn wifi(&self, config: WifiConfig) {
+        self.config_updated.signal(config);
+    }
+
+    fn mechanics(&self, config: StartupMechanicsConfig) {
+        self.joint_limits
+            .lock(|shared| shared.set(config.joint_limits));
+        self.mechanics_config.signal(config);
+    }
+
+    fn calibration(&self, calibration: Calibration) {
+        self.calibration.signal(calibration);
+    }
+
+    fn log(&self, args: fmt::Arguments<'_>) {
+        println!("{}", args);
+    }
+}
@@ -6,7 +6,10 @@
 pub mod flash_async;
 mod utils;
 
-use common::{mechanics_config::StartupMechanicsConfig, wifi_config::WifiConfig};
+use common::{
+    calibration::Calibration, configurator::Storage, mechanics_config::StartupMechanicsConfig,
+    wifi_config::WifiConfig,
+};
 use core::ops::Range;
 use embassy_sync::blocking_mutex::raw::RawMutex;
 use embedded_storage_async::nor_flash::ErrorType;
@@ -38,10 +41,12 @@ const STORAGE_PARTITION_RANGE: Range<u32> = Range {
 enum ConfigKey {
     Mechanics = 0,
     Wifi = 1,
+    Calibration = 2,
 }
 
 struct MechanicsEntry(StartupMechanicsConfig);
 struct WifiEntry(WifiConfig);
+struct CalibrationEntry(Calibration);
 
 impl<'a> Value<'a> for MechanicsEntry {
     fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, SerializationError> {
@@ -73,6 +78,21 @@ impl<'a> Value<'a> for WifiEntry {
     }
 }
 
+impl<'a> Value<'a> for CalibrationEntry {
+    fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, SerializationError> {
+        let data = to_slice(&self.0, buffer).map_err(|_| SerializationError::Custom(0))?;
+        Ok(data.len())
+    }
+
+    fn deserialize_from(buffer: &'a [u8]) -> Result<(Self, usize), SerializationError>
+    where
+        Self: Sized,
+    {
+        let item = from_bytes(buffer).map_err(|_| SerializationError::Custom(0))?;
+        Ok((CalibrationEntry(item), buffer.len()))
+    }
+}
+
 /// Асинхронный менеджер конфигурации.
 
 pub struct ConfigStorage<'a, M: RawMutex>(MapStorage<u8, &'a Flash<M>, NoCache>);
@@ -141,4 +161,80 @@ impl<'a, M: RawMutex> ConfigStorage<'a, M> {
 
         result.map(|w| w.0).ok_or(StorageError::NotFound)
     }
+
+    /// Сохраняет калибровку сервоприводов.
+    pub async fn store_calibration(
+        &mut self,
+        calibration: Calibration,
+    ) -> Result<(), sequential_storage::Error<<&Flash<M> as ErrorType>::Error>> {
+        let mut buf = [0u8; utils::buffer_size::<Calibration>()];
+        self.0
+            .store_item(
+                &mut buf,
+                &(ConfigKey::Calibration as u8),
+                &CalibrationEntry(calibration),
+            )
+            .await
+    }
+
+    /// Загружает калибровку сервоприводов. Возвращает `StorageError::NotFound`, если данных нет.
+    pub async fn fetch_calibration(
+        &mut self,
+    ) -> Result<Calibration, StorageError<<&Flash<M> as ErrorType>::Error>> {
+        let mut buf = [0u8; utils::buffer_size::<Calibration>()];
+
+        let result: Option<CalibrationEntry> = self
+            .0
+            .fetch_item(&mut buf, &(ConfigKey::Calibration as u8))
+            .await
+            .map_err(StorageError::Flash)?;
+
+        result.map(|w| w.0).ok_or(StorageError::NotFound)
+    }
+}
+
+impl<'a, M: RawMutex> Storage for ConfigStorage<'a, M> {
+    type Error = StorageError<<&'a Flash<M> as ErrorType>::Error>;
+
+    async fn fetch_wifi(&mut self) -> Result<Option<WifiConfig>, Self::Error> {
+        found(ConfigStorage::fetch_wifi(self).await)
+    }
+
+    async fn store_wifi(&mut self, config: &WifiConfig) -> Result<(), Self::Error> {
+        ConfigStorage::store_wifi(self, config.clone())
+            .await
+            .map_err(StorageError::Flash)
+    }
+
+    async fn fetch_mechanics(&mut self) -> Result<Option<StartupMechanicsConfig>, Self::Error> {
+        found(ConfigStorage::fetch_mechanics(self).await)
+    }
+
+    async fn store_mechanics(
+        &mut self,
+        config: &StartupMechanicsConfig,
+    ) -> Result<(), Self::Error> {
+        ConfigStorage::store_mechanics(self, config.clone())
+            .await
+            .map_err(StorageError::Flash)
+    }
+
+    async fn fetch_calibration(&mut self) -> Result<Option<Calibration>, Self::Error> {
+        found(ConfigStorage::fetch_calibration(self).await)
+    }
+
+    async fn store_calibration(&mut self, calibration: &Calibration) -> Result<(), Self::Error> {
+        ConfigStorage::store_calibration(self, *calibration)
+            .await
+            .map_err(StorageError::Flash)
+    }
+}
+
+/// Переводит отсутствие записи в `None`.
+fn found<T, E>(fetched: Result<T, StorageError<E>>) -> Result<Option<T>, StorageError<E>> {
+    match fetched {
+        Ok(value) => Ok(Some(value)),
+        Err(StorageError::NotFound) => Ok(None),
+        Err(err) => Err(err),
+    }
 }
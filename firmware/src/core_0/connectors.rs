@@ -1,38 +1,48 @@
-use common::{request::Command, wifi_config::WifiConfig};
+use common::{
+    mechanics_config::JointLimits, request::Command, response::Response, wifi_config::WifiConfig,
+};
+use core::cell::Cell;
 use embassy_sync::{
-    blocking_mutex::raw::NoopRawMutex,
+    blocking_mutex::{Mutex, raw::NoopRawMutex},
     channel::{Channel, Receiver, Sender},
     signal::Signal,
 };
 
-use crate::mk_static;
+use crate::{connectors::Tag, mk_static};
 
 // Канал для передачи команд от сетевого API обработчику команд.
-pub type CmdChan = Channel<NoopRawMutex, Command, 1>;
-pub type CmdSender<'a> = Sender<'a, NoopRawMutex, Command, 1>;
-pub type CmdReceiver<'a> = Receiver<'a, NoopRawMutex, Command, 1>;
+pub type CmdChan = Channel<NoopRawMutex, (Tag, Command), 1>;
+pub type CmdSender<'a> = Sender<'a, NoopRawMutex, (Tag, Command), 1>;
+pub type CmdReceiver<'a> = Receiver<'a, NoopRawMutex, (Tag, Command), 1>;
 
-// Канал для передачи подтверждений исполнения команд от обработчика команд
-// к сетевому API.
-pub type CmdAckChan = Channel<NoopRawMutex, (), 1>;
-pub type CmdAckSender<'a> = Sender<'a, NoopRawMutex, (), 1>;
-pub type CmdAckReceiver<'a> = Receiver<'a, NoopRawMutex, (), 1>;
+// Канал для передачи ответов на команды от обработчика команд к сетевому API.
+pub type CmdAckChan = Channel<NoopRawMutex, (Tag, Response), 1>;
+pub type CmdAckSender<'a> = Sender<'a, NoopRawMutex, (Tag, Response), 1>;
+pub type CmdAckReceiver<'a> = Receiver<'a, NoopRawMutex, (Tag, Response), 1>;
 
 // Сигнал обновления конфига. Для передачи обновленного конфига от обработчика
 // команд к сетевому менеджеру.
 pub type SignalConfigUpdated = Signal<NoopRawMutex, WifiConfig>;
 
+// Действующие программные ограничения углов. Обновляются обработчиком команд,
+// читаются сетевым API при проверке целевых позиций.
+pub type SharedJointLimits = Mutex<NoopRawMutex, Cell<JointLimits>>;
+
 pub struct Connectors {
     // Канал для передачи команд от сетевого API обработчику команд.
     pub cmd: CmdChan,
 
-    // Канал для передачи подтверждений исполнения команд от обработчика команд
-    // к сетевому API.
+    // Канал для передачи ответов на команды от обработчика команд к сетевому
+    // API.
     pub cmd_ack: CmdAckChan,
 
     // Сигнал обновления конфига. Для передачи обновленного конфига от обработчика
     // команд к сетевому менеджеру.
     pub config_updated: SignalConfigUpdated,
+
+    // Действующие программные ограничения углов. Обновляются обработчиком команд,
+    // читаются сетевым API при проверке целевых позиций.
+    pub joint_limits: SharedJointLimits,
 }
 
 impl Connectors {
@@ -43,6 +53,7 @@ impl Connectors {
                 cmd: Channel::new(),
                 cmd_ack: Channel::new(),
                 config_updated: Signal::new(),
+                joint_limits: Mutex::new(Cell::new(JointLimits::default())),
             }
         )
     }
@@ -4,9 +4,9 @@
 //! асинхронный TCP-сервер для приема команд управления и отправки подтверждений.
 
 use crate::{
-    connectors::{PosAckReceiver, PosSender},
+    connectors::{ControlSender, PosAckReceiver, PosReceiver, PosSender, SharedMotionSnapshot},
     core_0::{
-        connectors::{CmdAckReceiver, CmdSender, SignalConfigUpdated},
+        connectors::{CmdAckReceiver, CmdSender, SharedJointLimits, SignalConfigUpdated},
         network::connectors::ActiveWifiInterface,
     },
     mk_static,
@@ -80,10 +80,14 @@ impl Network {
     pub async fn run(
         &'static mut self,
         pos_tx: PosSender,
+        pos_rx: PosReceiver,
         pos_ack_rx: PosAckReceiver,
+        control_tx: ControlSender,
         cmd_tx: CmdSender<'static>,
         cmd_ack_rx: CmdAckReceiver<'static>,
         config_updated_rx: &SignalConfigUpdated,
+        joint_limits: &'static SharedJointLimits,
+        motion_snapshot: &'static SharedMotionSnapshot,
     ) -> ! {
         let Self {
             manager,
@@ -101,9 +105,13 @@ impl Network {
             manager.run(config_updated_rx, &active_wifi_interface, target_config),
             transport.run(
                 pos_tx,
+                pos_rx,
                 pos_ack_rx,
+                control_tx,
                 cmd_tx,
                 cmd_ack_rx,
+                joint_limits,
+                motion_snapshot,
                 &active_wifi_interface,
             ),
             wifi_provider.run(target_config),
@@ -1,174 +1,207 @@
+//! Сетевое API прошивки.
+//!
+//! Сессии ведет общее сетевое API из `common`; здесь только ввод-вывод на
+//! сокетах Embassy и очереди между задачами.
+
 use crate::{
-    connectors::{PosAckReceiver, PosSender},
-    core_0::connectors::{CmdAckReceiver, CmdSender},
+    connectors::{
+        ControlSender, Motion, PosAckReceiver, PosReceiver, PosSender, SharedMotionSnapshot, Tag,
+    },
+    core_0::connectors::{CmdAckReceiver, CmdSender, SharedJointLimits},
+};
+use common::{
+    api::{self, Outgoing, QueueFull},
+    mechanics_config::JointLimits,
+    motion::positioner::Snapshot,
+    request::{Command, MotionControl},
+    response::Response,
+    session::Tagged,
 };
-use common::{request::Request, response::Response};
-use embassy_futures::select::{Either, select};
-use embedded_io_async::{Read, ReadExactError, Write};
+use core::fmt;
+use embassy_futures::select::{Either, Either4, select, select4};
+use embassy_sync::{
+    blocking_mutex::raw::NoopRawMutex,
+    channel::{Channel, Receiver, Sender},
+    signal::Signal,
+};
+use embassy_time::{Duration, Instant, Ticker, Timer};
+use embedded_io_async::{Read, Write};
 use esp_println::println;
-use postcard::experimental::max_size::MaxSize;
-
-/// Максимально допустимые размеры пакетов на основе схем данных.
-pub const MAX_READ_PACKET_SIZE: usize =
-    header_size(Request::POSTCARD_MAX_SIZE) + Request::POSTCARD_MAX_SIZE;
-pub const MAX_WRITE_PACKET_SIZE: usize =
-    header_size(Response::POSTCARD_MAX_SIZE) + Response::POSTCARD_MAX_SIZE;
-
-/// Обрабатывает входящие запросы от клиента.
-pub async fn send_handle<W: Write>(
-    mut writer: W,
-    pos_ack: PosAckReceiver,
-    cmd_ack: CmdAckReceiver<'_>,
-) {
-    loop {
-        let response = match select(cmd_ack.receive(), pos_ack.receive()).await {
-            Either::First(()) => Response::CommandAck,
-            Either::Second(()) => Response::PositionAck,
-        };
 
-        let response = match common::to_vec::<Response, MAX_WRITE_PACKET_SIZE>(&response) {
-            Ok(data) => data,
-            Err(e) => {
-                println!("API OUTPUT ERROR: failed to serialize response: {:?}", e);
-                break;
-            }
-        };
+pub use common::api::{MAX_READ_PACKET_SIZE, MAX_WRITE_PACKET_SIZE};
 
-        if let Err(err) = write_packet(&mut writer, &response).await {
-            println!("API OUTPUT ERROR: failed to write packet: {:?}", err);
-            break;
-        };
+const REPLY_QUEUE_LEN: usize = 4;
 
-        if let Err(err) = writer.flush().await {
-            println!("API OUTPUT ERROR: failed to flush: {:?}", err);
-            break;
-        };
+// Канал для передачи ответов, сформированных самим API (например, отказов),
+// от обработчика входящих запросов к обработчику исходящих.
+pub type ReplyChan = Channel<NoopRawMutex, Tagged<Response>, REPLY_QUEUE_LEN>;
+pub type ReplySender<'a> = Sender<'a, NoopRawMutex, Tagged<Response>, REPLY_QUEUE_LEN>;
+pub type ReplyReceiver<'a> = Receiver<'a, NoopRawMutex, Tagged<Response>, REPLY_QUEUE_LEN>;
+
+// Сигнал изменения подписки на телеметрию: частота кадров в Гц или `None` при
+// отписке. Передается от обработчика входящих запросов к обработчику исходящих.
+pub type TelemetrySignal = Signal<NoopRawMutex, Option<u8>>;
+
+/// Часы Embassy, отсчитывающие время от запуска.
+pub struct EmbassyClock;
+
+impl api::Clock for EmbassyClock {
+    fn now_ms(&self) -> u64 {
+        Instant::now().as_millis()
+    }
+
+    async fn sleep_ms(&self, ms: u64) {
+        Timer::after_millis(ms).await
     }
 }
 
-/// Обрабатывает входящие запросы от клиента.
-pub async fn receive_handle<R: Read>(mut reader: R, pos: PosSender, cmd: CmdSender<'_>) {
-    let mut body = [0u8; MAX_READ_PACKET_SIZE];
-    loop {
-        let len = match read_varint(&mut reader).await {
-            Ok(l) => l,
-            Err(err) => {
-                println!("API INPUT ERROR: failed to read packet length: {err:?}");
-                break;
-            }
-        };
+/// Половина TCP-соединения.
+pub struct Socket<T>(pub T);
 
-        if len > body.len() {
-            println!("API INPUT ERROR: input packet too large: {len} bytes");
-            break;
-        }
+impl<T: Read> api::Read for Socket<T> {
+    type Error = T::Error;
 
-        if let Err(err) = reader.read_exact(&mut body[..len]).await {
-            println!("API INPUT ERROR: failed to read data: {err}");
-            break;
-        }
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, T::Error> {
+        self.0.read(buf).await
+    }
+}
 
-        match common::from_bytes::<Request>(&body[..len]) {
-            Ok(Request::Enqueue(data)) => {
-                if let Err(_) = pos.try_send(data) {
-                    println!("API INPUT ERROR: positioning queue is full");
-                    break;
-                };
-            }
-            Ok(Request::Immediate(data)) => {
-                if let Err(_) = cmd.try_send(data) {
-                    println!("API INPUT ERROR: command queue is full");
-                    break;
-                };
-            }
-            Err(err) => {
-                println!("API INPUT ERROR: failed to deserialize data: {err}");
-                break;
-            }
-        };
+impl<T: Write> api::Write for Socket<T> {
+    type Error = T::Error;
+
+    async fn write_frame(&mut self, frame: &[u8]) -> Result<(), T::Error> {
+        self.0.write_all(frame).await?;
+        self.0.flush().await
     }
 }
 
-/// Утилита для записи пакета с varint-префиксом длины.
-async fn write_packet<W: Write>(
-    writer: &mut W,
-    data: &[u8],
-) -> Result<(), ReadExactError<W::Error>> {
-    write_varint(writer, data.len()).await?;
-    writer.write_all(data).await?;
-    writer.flush().await?;
-    Ok(())
+/// Очереди к позиционеру и конфигуратору.
+pub struct Queues<'a> {
+    pub pos_tx: PosSender,
+    /// Приемник очереди позиционирования: нужен для ее очистки по команде.
+    pub pos_rx: PosReceiver,
+    pub control_tx: ControlSender,
+    pub cmd_tx: CmdSender<'a>,
+    pub joint_limits: &'a SharedJointLimits,
+    pub motion_snapshot: &'a SharedMotionSnapshot,
 }
 
-/// Записывает целое число в поток в формате LEB128.
-///
-/// Кодирует число максимально компактно, используя от 1 до 10 байт (для 64-бит).
-pub async fn write_varint<W: Write>(
-    writer: &mut W,
-    mut val: usize,
-) -> Result<(), ReadExactError<W::Error>> {
-    while val >= 0x80 {
-        // Записываем 7 бит и устанавливаем бит продолжения.
-        writer.write_all(&[(val as u8 & 0x7F) | 0x80]).await?;
-        val >>= 7;
-    }
-    // Последний байт (бит продолжения равен 0).
-    writer.write_all(&[val as u8]).await?;
-    Ok(())
+impl api::Device for Queues<'_> {
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    fn joint_limits(&self) -> JointLimits {
+        self.joint_limits.lock(|limits| limits.get())
+    }
+
+    fn try_enqueue(&self, tag: Tag, motion: Motion) -> Result<(), QueueFull> {
+        self.pos_tx.try_send((tag, motion)).map_err(|_| QueueFull)
+    }
+
+    fn try_dequeue(&self) -> Option<Tag> {
+        self.pos_rx.try_receive().ok().map(|(tag, _)| tag)
+    }
+
+    fn queued(&self) -> usize {
+        self.pos_tx.len()
+    }
+
+    fn queue_capacity(&self) -> usize {
+        self.pos_tx.capacity()
+    }
+
+    fn try_command(&self, tag: Tag, command: Command) -> Result<(), QueueFull> {
+        self.cmd_tx.try_send((tag, command)).map_err(|_| QueueFull)
+    }
+
+    fn try_control(&self, action: MotionControl) -> Result<(), QueueFull> {
+        self.control_tx.try_send(action).map_err(|_| QueueFull)
+    }
+
+    fn motion(&self) -> (Snapshot, u64) {
+        let snapshot = self.motion_snapshot.read();
+        let motion = Snapshot {
+            position: snapshot.position,
+            target: snapshot.target,
+            moving: snapshot.moving,
+            paused: snapshot.paused,
+        };
+        (motion, snapshot.timestamp.as_micros())
+    }
+
+    fn clear(&self) {
+        self.pos_tx.clear();
+    }
+
+    fn log(&self, args: fmt::Arguments<'_>) {
+        println!("{}", args);
+    }
 }
 
-// Ошибка, возвращаемая [`read_varint`]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub enum ReadVarintError<E> {
-    /// Значение превышает емкость целевого типа данных.
-    Overflow,
-    /// Ошибка, возвращаемая внутренним ридером.
-    Other(E),
+/// Передающие концы очередей ответов.
+pub struct ReplyQueue<'a> {
+    pub replies: ReplySender<'a>,
+    pub telemetry: &'a TelemetrySignal,
 }
 
-impl<E> From<E> for ReadVarintError<E> {
-    fn from(err: E) -> Self {
-        Self::Other(err)
+impl api::Replies for ReplyQueue<'_> {
+    async fn reply(&self, reply: Tagged<Response>) {
+        self.replies.send(reply).await
     }
-}
 
-/// Читает целое число из потока в формате LEB128.
-///
-/// Каждый байт хранит 7 бит данных и 1 бит-флаг продолжения (MSB).
-pub async fn read_varint<R: Read>(
-    reader: &mut R,
-) -> Result<usize, ReadVarintError<ReadExactError<R::Error>>> {
-    let mut res = 0;
-    let mut shift = 0;
-    let mut buf = [0u8; 1];
-
-    loop {
-        reader.read_exact(&mut buf).await?;
-        let byte = buf[0];
-
-        // Извлекаем 7 бит данных.
-        res |= ((byte & 0x7F) as usize) << shift;
-
-        // Если MSB не установлен, значит это последний байт.
-        if (byte & 0x80) == 0 {
-            return Ok(res);
-        }
+    fn subscribe(&self, rate_hz: Option<u8>) {
+        self.telemetry.signal(rate_hz);
+    }
+}
 
-        shift += 7;
+/// Приемные концы очередей ответов и таймер телеметрии.
+pub struct ResponseQueue<'a> {
+    pub pos_ack_rx: PosAckReceiver,
+    pub cmd_ack_rx: CmdAckReceiver<'a>,
+    pub replies: ReplyReceiver<'a>,
+    pub telemetry: &'a TelemetrySignal,
+    pub ticker: Option<Ticker>,
+}
 
-        // Проверка на переполнение разрядной сетки usize.
-        if shift >= (core::mem::size_of::<usize>() * 8) {
-            return Err(ReadVarintError::Overflow);
+impl api::Outbox for ResponseQueue<'_> {
+    async fn next(&mut self) -> Option<Outgoing> {
+        let Self {
+            pos_ack_rx,
+            cmd_ack_rx,
+            replies,
+            telemetry,
+            ticker,
+        } = self;
+        loop {
+            let tick = async {
+                match ticker.as_mut() {
+                    Some(ticker) => ticker.next().await,
+                    None => core::future::pending().await,
+                }
+            };
+            match select4(
+                cmd_ack_rx.receive(),
+                pos_ack_rx.receive(),
+                replies.receive(),
+                select(telemetry.wait(), tick),
+            )
+            .await
+            {
+                Either4::First((tag, response)) => return Some(Outgoing::Command(tag, response)),
+                Either4::Second((tag, outcome)) => return Some(Outgoing::Motion(tag, outcome)),
+                Either4::Third(reply) => return Some(Outgoing::Reply(reply)),
+                Either4::Fourth(Either::First(rate)) => {
+                    *ticker = rate.map(|hz| Ticker::every(Duration::from_hz(hz as u64)));
+                }
+                Either4::Fourth(Either::Second(())) => return Some(Outgoing::Telemetry),
+            }
         }
     }
-}
 
-/// Рассчитывает размер заголовка LEB128 для заданного значения.
-pub const fn header_size(mut val: usize) -> usize {
-    let mut count = 1;
-    while val >= 0x80 {
-        val >>= 7;
-        count += 1;
+    fn clear(&mut self) {
+        self.pos_ack_rx.clear();
+        self.cmd_ack_rx.clear();
+        self.replies.clear();
+        self.telemetry.reset();
+        self.ticker = None;
     }
-    count
 }
@@ -1,11 +1,16 @@
 use crate::{
-    connectors::{PosAckReceiver, PosSender},
+    connectors::{ControlSender, PosAckReceiver, PosReceiver, PosSender, SharedMotionSnapshot},
     core_0::{
-        connectors::{CmdAckReceiver, CmdSender},
+        connectors::{CmdAckReceiver, CmdSender, SharedJointLimits},
         mk_static,
-        network::{ActiveWifiInterface, api, connectors::WifiInterface},
+        network::{
+            ActiveWifiInterface,
+            api::{self, EmbassyClock, Queues, ReplyQueue, ResponseQueue, Socket},
+            connectors::WifiInterface,
+        },
     },
 };
+use common::api::{End, Sessions};
 use core::{
     mem,
     net::Ipv4Addr,
@@ -31,6 +36,10 @@ const NETWORK_BUFFER_FACTOR: usize = 4;
 const RX_BUF_SIZE: usize = max_usize(WIFI_MTU, api::MAX_READ_PACKET_SIZE * NETWORK_BUFFER_FACTOR);
 const TX_BUF_SIZE: usize = max_usize(WIFI_MTU, api::MAX_WRITE_PACKET_SIZE * NETWORK_BUFFER_FACTOR);
 
+// Кадр наибольшего запроса (пакета точек) должен целиком помещаться в буфер
+// сокета.
+const _: () = assert!(RX_BUF_SIZE >= api::MAX_READ_PACKET_SIZE);
+
 /// Таймаут после неудачной попытки принять подключение.
 const ACCEPT_RETRY_TIMEOUT: Duration = Duration::from_secs(10);
 
@@ -39,19 +48,22 @@ const fn max_usize(a: usize, b: usize) -> usize {
 }
 
 pub struct TrafficResources<'a> {
-    pos_tx: PosSender,
-    cmd_tx: CmdSender<'a>,
-    pos_ack_rx: PosAckReceiver,
-    cmd_ack_rx: CmdAckReceiver<'a>,
+    queues: Queues<'a>,
+    replies: ReplyQueue<'a>,
+    responses: ResponseQueue<'a>,
     active_wifi_interface: &'a ActiveWifiInterface,
+    sessions: Sessions,
 }
 
 impl<'a> TrafficResources<'a> {
-    fn clear(&self) {
-        self.pos_tx.clear();
-        self.cmd_tx.clear();
-        self.pos_ack_rx.clear();
-        self.cmd_ack_rx.clear();
+    /// Завершает сессию по причине `end`.
+    fn end(&mut self, end: End) {
+        self.sessions.end(end, &self.queues, &mut self.responses);
+    }
+
+    /// Очищает очереди и начинает новую сессию.
+    fn clear(&mut self) {
+        self.sessions.clear(&self.queues, &mut self.responses);
     }
 }
 
@@ -60,17 +72,39 @@ struct AsyncTrafficResources<'a, M: RawMutex>(embassy_sync::mutex::Mutex<M, Traf
 impl<'a, M: RawMutex> AsyncTrafficResources<'a, M> {
     pub fn new(
         pos_tx: PosSender,
+        pos_rx: PosReceiver,
+        control_tx: ControlSender,
         cmd_tx: CmdSender<'a>,
         pos_ack_rx: PosAckReceiver,
         cmd_ack_rx: CmdAckReceiver<'a>,
+        joint_limits: &'a SharedJointLimits,
+        motion_snapshot: &'a SharedMotionSnapshot,
         active_wifi_interface: &'a ActiveWifiInterface,
+        replies: &'a api::ReplyChan,
+        telemetry: &'a api::TelemetrySignal,
     ) -> Self {
         Self(embassy_sync::mutex::Mutex::new(TrafficResources {
-            pos_tx,
-            cmd_tx,
-            pos_ack_rx,
-            cmd_ack_rx,
+            queues: Queues {
+                pos_tx,
+                pos_rx,
+                control_tx,
+                cmd_tx,
+                joint_limits,
+                motion_snapshot,
+            },
+            replies: ReplyQueue {
+                replies: replies.sender(),
+                telemetry,
+            },
+            responses: ResponseQueue {
+                pos_ack_rx,
+                cmd_ack_rx,
+                replies: replies.receiver(),
+                telemetry,
+                ticker: None,
+            },
             active_wifi_interface,
+            sessions: Sessions::new(),
         }))
     }
 
@@ -87,7 +121,7 @@ pub struct TrafficResourcesGuard<'a, M: RawMutex>(
 
 impl<'a, M: RawMutex> Drop for TrafficResourcesGuard<'a, M> {
     fn drop(&mut self) {
-        (*self).0.clear();
+        self.0.clear();
         self.active_wifi_interface.signal(WifiInterface::None);
     }
 }
@@ -250,26 +284,25 @@ impl<'a> RawTransport<'a> {
             }
 
             let (reader, writer) = active.split();
+            let session = tr.sessions.run(
+                &tr.queues,
+                &EmbassyClock,
+                Socket(reader),
+                Socket(writer),
+                &tr.replies,
+                &mut tr.responses,
+            );
             // Ожидаем либо завершения работы с текущим клиентом, либо нового подключения на запасной сокет.
-            match select(
-                spare.accept(PORT),
-                select(
-                    api::send_handle(writer, tr.pos_ack_rx, tr.cmd_ack_rx),
-                    api::receive_handle(reader, tr.pos_tx, tr.cmd_tx),
-                ),
-            )
-            .await
-            {
-                Either::Second(_) => {
-                    println!("TRANSPORT: client disconnected");
+            match select(spare.accept(PORT), session).await {
+                Either::Second(end) => {
+                    tr.end(end);
                     break;
                 }
                 Either::First(_) => {
-                    println!("TRANSPORT: client is displaced by a new one...");
                     mem::swap(active, spare);
 
                     spare.abort();
-                    tr.clear();
+                    tr.end(End::Displaced);
 
                     continue;
                 }
@@ -278,7 +311,6 @@ impl<'a> RawTransport<'a> {
 
         active.abort();
         spare.abort();
-        tr.clear();
     }
 }
 
@@ -324,19 +356,32 @@ impl MultiLinkTransport {
     pub async fn run(
         &'static mut self,
         pos_tx: PosSender,
+        pos_rx: PosReceiver,
         pos_ack_rx: PosAckReceiver,
+        control_tx: ControlSender,
         cmd_tx: CmdSender<'static>,
         cmd_ack_rx: CmdAckReceiver<'static>,
+        joint_limits: &'static SharedJointLimits,
+        motion_snapshot: &'static SharedMotionSnapshot,
         active_wifi_interface: &'static ActiveWifiInterface,
     ) -> ! {
+        // Ответы, которые формирует сам API (отказы), очищаются при смене сессии.
+        let replies = mk_static!(api::ReplyChan, api::ReplyChan::new());
+        let telemetry = mk_static!(api::TelemetrySignal, api::TelemetrySignal::new());
         let tr = mk_static!(
             AsyncTrafficResources<NoopRawMutex>,
             AsyncTrafficResources::new(
                 pos_tx,
+                pos_rx,
+                control_tx,
                 cmd_tx,
                 pos_ack_rx,
                 cmd_ack_rx,
+                joint_limits,
+                motion_snapshot,
                 active_wifi_interface,
+                replies,
+                telemetry,
             )
         );
 
@@ -1,7 +1,10 @@
 mod positioner;
 
 use crate::{
-    connectors::{PosAckSender, PosReceiver},
+    connectors::{
+        ControlReceiver, PosAckSender, PosReceiver, SharedMotionSnapshot, SignalCalibration,
+        SignalMechanicsConfig,
+    },
     mk_static,
 };
 use esp_hal::{
@@ -55,6 +58,10 @@ where
         self,
         pos_rx: PosReceiver,
         pos_ack_tx: PosAckSender,
+        control_rx: ControlReceiver,
+        mechanics_config: &'static SignalMechanicsConfig,
+        calibration: &'static SignalCalibration,
+        motion_snapshot: &'static SharedMotionSnapshot,
     ) -> Result<AppCoreGuard<'static>, system::Error> {
         let Self {
             mut cpu_control,
@@ -71,8 +78,14 @@ where
             let positioner =
                 Positioner::make(ledc, rotation_pin, shoulder_pin, forearm_pin, claw_pin)
                     .expect("failed to make the positioner");
-            let positioner = mk_static!(Positioner, positioner);
-            positioner.run(pos_rx, pos_ack_tx);
+            positioner.run(
+                pos_rx,
+                pos_ack_tx,
+                control_rx,
+                mechanics_config,
+                calibration,
+                motion_snapshot,
+            );
         })
     }
 }
@@ -1,41 +1,31 @@
 use crate::{
-    connectors::{PosAckSender, PosReceiver},
+    connectors::{
+        ControlReceiver, Motion, MotionSnapshot, Outcome, PosAckSender, PosReceiver,
+        SharedMotionSnapshot, SignalCalibration, SignalMechanicsConfig, Tag,
+    },
     core_1::positioner::{mechanics::servo_motor, utils::SecondsExt as _},
 };
 use common::{
-    quantities::{Position, Velocity},
-    units::{Radians, RadiansPerSecond, Seconds},
+    actuator::{Actuator, Joints},
+    calibration::Calibration,
+    mechanics_config::StartupMechanicsConfig,
+    motion::positioner::{self, Clock, Links, Snapshot},
+    request::MotionControl,
+    units::Seconds,
 };
-use core::f32::consts::PI;
 use embassy_time::Instant;
 use esp_hal::{gpio::interconnect::PeripheralOutput, ledc::channel::Error, peripherals::LEDC};
-use mechanics::Mechanics;
+use mechanics::ServoJoint;
 
 pub mod mechanics;
 pub mod utils;
 
-/// Позиция манипулятора при включении (TODO: перенести в постоянное
-/// хранилище).
-static INIT_POSITION: Position = Position {
-    rotation: Radians::new(1.57),
-    shoulder: Radians::new(1.3),
-    forearm: Radians::new(0.7),
-    claw: Radians::new(2.5),
-};
-
-/// Максимальные скорости осей (TODO: перенести в постоянное хранилище).
-const MAX_SPEED: Velocity = Velocity {
-    rotation: RadiansPerSecond::new(PI / 3.0),
-    shoulder: RadiansPerSecond::new(PI / 2.0),
-    forearm: RadiansPerSecond::new(PI / 2.0),
-    claw: RadiansPerSecond::new(PI),
-};
-
 /// Интервал обновления позиции, синхронизированный с частотой ШИМ, управляющим
 /// сервомоторами.
 const POSITIONING_INTERVAL: Seconds = Seconds::new(1.0 / servo_motor::PWM_FREQ_HZ as f32);
 
-pub struct Positioner(Mechanics);
+/// Позиционер, управляющий приводами `A` всех осей.
+pub struct Positioner<A = ServoJoint>(Joints<A>);
 
 impl Positioner {
     pub fn make<R, S, F, C>(
@@ -51,7 +41,7 @@ impl Positioner {
         F: PeripheralOutput<'static>,
         C: PeripheralOutput<'static>,
     {
-        Ok(Self(Mechanics::make(
+        Ok(Self::new(mechanics::make(
             ledc,
             rotation_pin,
             shoulder_pin,
@@ -59,32 +49,107 @@ impl Positioner {
             claw_pin,
         )?))
     }
+}
+
+impl<A: Actuator> Positioner<A> {
+    pub fn new(joints: Joints<A>) -> Self {
+        Self(joints)
+    }
 
     /// Задача управления траекторией движения манипулятора.
     ///
-    /// Получает целевые позиции, разбивает их на мелкие шаги и плавно перемещает
-    /// приводы, соблюдая временные интервалы.
-    pub fn run(&'static mut self, pos_rx: PosReceiver, pos_ack_tx: PosAckSender) {
-        let mut next_tick = Instant::now();
-        let mut current_pos = INIT_POSITION;
-        let interval = POSITIONING_INTERVAL.as_duration();
-
-        loop {
-            let dst = utils::blocking_receive(&pos_rx);
-
-            for pos in utils::interpolation(current_pos, dst, MAX_SPEED) {
-                next_tick += interval;
-                // Защита от накопления задержек: если мы отстали, выравниваем время.
-                next_tick = next_tick.max(Instant::now());
-
-                while Instant::now() < next_tick {
-                    core::hint::spin_loop();
-                }
-
-                self.0.set_pos(pos);
-                current_pos = pos;
-            }
-            utils::blocking_ack(&pos_ack_tx);
+    /// Исполняет общий цикл позиционирования
+    /// ([`common::motion::positioner::Positioner::run`]) с шагом, равным
+    /// периоду ШИМ. Шаги отмеряются активным ожиданием: второе ядро занято
+    /// только позиционированием.
+    pub fn run(
+        self,
+        pos_rx: PosReceiver,
+        pos_ack_tx: PosAckSender,
+        control_rx: ControlReceiver,
+        mechanics_config: &'static SignalMechanicsConfig,
+        calibration: &'static SignalCalibration,
+        motion_snapshot: &'static SharedMotionSnapshot,
+    ) -> ! {
+        let mut links = CoreLinks {
+            pos_rx,
+            pos_ack_tx,
+            control_rx,
+            mechanics_config,
+            calibration,
+            motion_snapshot,
+        };
+        positioner::Positioner::start(self.0, SpinClock, &mut links)
+            .run(&mut links, POSITIONING_INTERVAL)
+    }
+}
+
+/// Часы Embassy с активным ожиданием.
+struct SpinClock;
+
+impl Clock for SpinClock {
+    type Instant = Instant;
+
+    fn now(&mut self) -> Instant {
+        Instant::now()
+    }
+
+    fn after(&self, instant: Instant, interval: Seconds) -> Instant {
+        instant + interval.as_duration()
+    }
+
+    fn wait_until(&mut self, instant: Instant) {
+        while Instant::now() < instant {
+            core::hint::spin_loop();
         }
     }
+
+    fn idle(&mut self) {
+        core::hint::spin_loop();
+    }
+}
+
+/// Связи позиционера со вторым ядром: очереди и сигналы между ядрами.
+struct CoreLinks {
+    pos_rx: PosReceiver,
+    pos_ack_tx: PosAckSender,
+    control_rx: ControlReceiver,
+    mechanics_config: &'static SignalMechanicsConfig,
+    calibration: &'static SignalCalibration,
+    motion_snapshot: &'static SharedMotionSnapshot,
+}
+
+impl Links for CoreLinks {
+    type Tag = Tag;
+    type Instant = Instant;
+
+    fn try_motion(&mut self) -> Option<(Tag, Motion)> {
+        self.pos_rx.try_receive().ok()
+    }
+
+    fn try_control(&mut self) -> Option<MotionControl> {
+        self.control_rx.try_receive().ok()
+    }
+
+    fn try_config(&mut self) -> Option<StartupMechanicsConfig> {
+        self.mechanics_config.try_take()
+    }
+
+    fn try_calibration(&mut self) -> Option<Calibration> {
+        self.calibration.try_take()
+    }
+
+    fn try_ack(&mut self, ack: (Tag, Outcome)) -> bool {
+        self.pos_ack_tx.try_send(ack).is_ok()
+    }
+
+    fn publish(&mut self, snapshot: Snapshot, timestamp: Instant) {
+        self.motion_snapshot.publish(MotionSnapshot {
+            position: snapshot.position,
+            target: snapshot.target,
+            moving: snapshot.moving,
+            paused: snapshot.paused,
+            timestamp,
+        });
+    }
 }
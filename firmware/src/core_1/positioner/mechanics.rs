@@ -1,7 +1,11 @@
 pub mod pwm;
 pub mod servo_motor;
 
-use common::quantities::Position;
+use common::{
+    actuator::{Actuator, Joints},
+    calibration::ServoCalibration,
+    units::Radians,
+};
 use esp_hal::{
     gpio::interconnect::PeripheralOutput,
     ledc::channel::{self, Error},
@@ -11,43 +15,78 @@ use esp_println as _;
 use pwm::PWM;
 use servo_motor::Servo;
 
-/// Механика робота, объединяющая четыре сервопривода манипулятора.
-pub struct Mechanics {
-    rotation: Servo,
-    shoulder: Servo,
-    forearm: Servo,
-    claw: Servo,
+/// Механика робота: четыре сервопривода манипулятора на каналах LEDC.
+pub type Mechanics = Joints<ServoJoint>;
+
+/// Инициализирует механику робота, назначая каждому узлу свой канал ШИМ и пин.
+///
+/// Приводы создаются отключенными.
+pub fn make<R, S, F, C>(
+    ledc: LEDC<'static>,
+    rotation_pin: R,
+    shoulder_pin: S,
+    forearm_pin: F,
+    claw_pin: C,
+) -> Result<Mechanics, Error>
+where
+    R: PeripheralOutput<'static>,
+    S: PeripheralOutput<'static>,
+    F: PeripheralOutput<'static>,
+    C: PeripheralOutput<'static>,
+{
+    let pwm = PWM::make(ledc).expect("failed make PWM");
+    Ok(Mechanics {
+        rotation: ServoJoint::new(Servo::init(pwm, channel::Number::Channel0, rotation_pin)?),
+        shoulder: ServoJoint::new(Servo::init(pwm, channel::Number::Channel1, shoulder_pin)?),
+        forearm: ServoJoint::new(Servo::init(pwm, channel::Number::Channel2, forearm_pin)?),
+        claw: ServoJoint::new(Servo::init(pwm, channel::Number::Channel3, claw_pin)?),
+    })
+}
+
+/// Сочленение с сервоприводом на канале LEDC.
+///
+/// Угол пересчитывается в ширину импульса по калибровке привода. Отключенный
+/// привод не получает импульсов и не удерживает положение.
+pub struct ServoJoint {
+    servo: Servo,
+    calibration: ServoCalibration,
+    angle: Option<Radians>,
+    enabled: bool,
 }
 
-impl Mechanics {
-    /// Инициализирует механику робота, назначая каждому узлу свой канал ШИМ и пин.
-    pub fn make<R, S, F, C>(
-        ledc: LEDC<'static>,
-        rotation_pin: R,
-        shoulder_pin: S,
-        forearm_pin: F,
-        claw_pin: C,
-    ) -> Result<Self, Error>
-    where
-        R: PeripheralOutput<'static>,
-        S: PeripheralOutput<'static>,
-        F: PeripheralOutput<'static>,
-        C: PeripheralOutput<'static>,
-    {
-        let pwm = PWM::make(ledc).expect("failed make PWM");
-        Ok(Self {
-            rotation: Servo::init(pwm, channel::Number::Channel0, rotation_pin)?,
-            shoulder: Servo::init(pwm, channel::Number::Channel1, shoulder_pin)?,
-            forearm: Servo::init(pwm, channel::Number::Channel2, forearm_pin)?,
-            claw: Servo::init(pwm, channel::Number::Channel3, claw_pin)?,
-        })
+impl ServoJoint {
+    fn new(servo: Servo) -> Self {
+        Self {
+            servo,
+            calibration: ServoCalibration::default(),
+            angle: None,
+            enabled: false,
+        }
+    }
+}
+
+impl Actuator for ServoJoint {
+    fn set_angle(&mut self, angle: Radians) {
+        self.angle = Some(angle);
+        if self.enabled {
+            self.servo
+                .set_pulse_width(self.calibration.pulse_width_us(angle));
+        }
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        if let Some(angle) = self.angle {
+            self.set_angle(angle);
+        }
+    }
+
+    fn disable(&mut self) {
+        self.enabled = false;
+        self.servo.set_pulse_width(0.0);
     }
 
-    /// Устанавливает положение всех узлов манипулятора на основе структуры Position.
-    pub fn set_pos(&mut self, pos: Position) {
-        self.rotation.set_pos(pos.rotation);
-        self.shoulder.set_pos(pos.shoulder);
-        self.forearm.set_pos(pos.forearm);
-        self.claw.set_pos(pos.claw);
+    fn calibrate(&mut self, calibration: ServoCalibration) {
+        self.calibration = calibration;
     }
 }
@@ -44,13 +44,35 @@ async fn main(_spawner: Spawner) {
         WIFI,
         ..
     } = esp_hal::init(Config::default().with_cpu_clock(CpuClock::max()));
-    let Connectors { pos, pos_ack } = Connectors::new();
+    let Connectors {
+        pos,
+        pos_ack,
+        control,
+        mechanics_config,
+        calibration,
+        motion_snapshot,
+    } = Connectors::new();
 
     let _g = Core1::make(CPU_CTRL, LEDC, GPIO32, GPIO33, GPIO25, GPIO26)
-        .run(pos.receiver(), pos_ack.sender())
+        .run(
+            pos.receiver(),
+            pos_ack.sender(),
+            control.receiver(),
+            mechanics_config,
+            calibration,
+            motion_snapshot,
+        )
         .expect("failed to start core_1");
 
     Core0::make(TIMG0, FLASH, WIFI)
-        .run(pos.sender(), pos_ack.receiver())
+        .run(
+            pos.sender(),
+            pos.receiver(),
+            pos_ack.receiver(),
+            control.sender(),
+            mechanics_config,
+            calibration,
+            motion_snapshot,
+        )
         .await;
 }
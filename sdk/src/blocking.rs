@@ -0,0 +1,448 @@
+//! Блокирующий клиент на `std::net::TcpStream` для программ без асинхронной
+//! среды исполнения.
+//!
+//! Каждый вызов ограничен временем ожидания клиента (см.
+//! [`Robot::set_timeout`]). Ответы, принятые во время ожидания другого
+//! ответа, не теряются: они сохраняются и возвращаются методами
+//! [`Robot::recv`] и [`Robot::try_recv`].
+
+use crate::{
+    error::Error,
+    protocol::{self, HELLO_FRAME_SIZE, Incoming, MAX_REQUEST_FRAME_SIZE, Session, Unacked},
+};
+use common::{
+    framing::ResponseDecoder,
+    handshake::Welcome,
+    quantities::{Position, Velocity},
+    request::{Command, Request, Waypoint},
+    response::Response,
+    session::{RequestId, Tagged},
+    wifi_config::WifiConfig,
+};
+use std::{
+    collections::VecDeque,
+    io::{self, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+/// Время ожидания по умолчанию.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Соединение с роборукой.
+///
+/// Если время ожидания истекло посреди отправки кадра или соединение
+/// разорвано, последующие вызовы возвращают [`Error::Disconnected`].
+pub struct Robot {
+    welcome: Welcome,
+    session: Session,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    /// Декодер ответов хранит принятую часть кадра между вызовами.
+    decoder: ResponseDecoder,
+    /// Принятые, но еще не прочитанные ответы.
+    inbox: VecDeque<Tagged<Response>>,
+    timeout: Duration,
+    /// Граница кадров потеряна, и соединение больше не используется.
+    broken: bool,
+}
+
+impl Robot {
+    /// Подключается к роборуке и выполняет рукопожатие со временем ожидания
+    /// [`DEFAULT_TIMEOUT`].
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        Self::connect_timeout(addr, DEFAULT_TIMEOUT)
+    }
+
+    /// Подключается к роборуке и выполняет рукопожатие.
+    ///
+    /// Время ожидания `timeout` ограничивает подключение и рукопожатие и
+    /// становится временем ожидания клиента.
+    pub fn connect_timeout(addr: impl ToSocketAddrs, timeout: Duration) -> Result<Self, Error> {
+        let mut last_err = None;
+        let mut stream = None;
+        for addr in addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        let stream = match (stream, last_err) {
+            (Some(stream), _) => stream,
+            (None, Some(e)) => return Err(timed_out(e)),
+            (None, None) => return Err(io::Error::from(io::ErrorKind::AddrNotAvailable).into()),
+        };
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+
+        let mut buf = [0u8; HELLO_FRAME_SIZE];
+        writer
+            .write_all(protocol::hello(&mut buf)?)
+            .map_err(timed_out)?;
+        let mut decoder = protocol::welcome_decoder();
+        protocol::fill_frame(&mut reader, &mut decoder).map_err(timed_out)?;
+        let welcome = protocol::welcome(&mut decoder)?;
+
+        Ok(Self {
+            session: Session::new(&welcome),
+            welcome,
+            reader,
+            writer,
+            decoder: ResponseDecoder::new(),
+            inbox: VecDeque::new(),
+            timeout,
+            broken: false,
+        })
+    }
+
+    /// Ответ прошивки на приветствие.
+    pub fn welcome(&self) -> &Welcome {
+        &self.welcome
+    }
+
+    /// Время ожидания каждого вызова.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Задает время ожидания каждого вызова. Ожидание завершения
+    /// перемещения ограничено им же, поэтому для долгих перемещений его
+    /// следует увеличить.
+    pub fn set_timeout(&mut self, timeout: Duration) -> Result<(), Error> {
+        self.writer.set_write_timeout(Some(timeout))?;
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    /// Количество перемещений, которые можно отправить сейчас, не
+    /// переполнив очередь позиционирования.
+    pub fn free_slots(&self) -> u8 {
+        self.session.available()
+    }
+
+    /// Создает отправитель перемещений с обратным давлением.
+    pub fn throttle(&mut self) -> Throttle<'_> {
+        Throttle {
+            robot: self,
+            unacked: Unacked::default(),
+        }
+    }
+
+    /// Отправляет запрос, не дожидаясь ответа, и возвращает его
+    /// идентификатор. Ответы возвращаются методами [`Self::recv`] и
+    /// [`Self::try_recv`].
+    pub fn send(&mut self, request: Request) -> Result<RequestId, Error> {
+        if self.broken {
+            return Err(Error::Disconnected);
+        }
+        let mut buf = [0u8; MAX_REQUEST_FRAME_SIZE];
+        let (id, frame) = self.session.seal(request, &mut buf)?;
+        if let Err(e) = self.writer.write_all(frame) {
+            // Кадр мог быть отправлен частично.
+            self.broken = true;
+            return Err(timed_out(e));
+        }
+        Ok(id)
+    }
+
+    /// Отправляет запрос и ждет первого ответа на него.
+    ///
+    /// Отказ и отмена перемещения возвращаются ошибками. Для пакета
+    /// перемещений ответом считается итог первого из них.
+    pub fn request(&mut self, request: Request) -> Result<Response, Error> {
+        let deadline = Instant::now() + self.timeout;
+        let id = self.send(request)?;
+        loop {
+            match self.receive(Some(deadline))? {
+                Some(Incoming::Response(response)) if response.id == Some(id) => {
+                    return protocol::outcome(response.message);
+                }
+                Some(Incoming::Response(response)) => self.inbox.push_back(response),
+                Some(Incoming::Invalid(invalid, err)) if invalid == id => return Err(err),
+                Some(_) => {}
+                None => return Err(Error::Timeout),
+            }
+        }
+    }
+
+    /// Перемещает манипулятор в позицию `position` и ждет завершения
+    /// перемещения.
+    pub fn move_to(&mut self, position: Position) -> Result<(), Error> {
+        let response = self.request(Request::Enqueue(Waypoint::from(position)))?;
+        protocol::position_ack(response)
+    }
+
+    /// Сохраняет конфигурацию Wi-Fi. Применяется после перезагрузки.
+    pub fn configure_wifi(&mut self, config: WifiConfig) -> Result<(), Error> {
+        let response = self.request(Request::Immediate(Command::ConfigureWifi(config)))?;
+        protocol::command_ack(response)
+    }
+
+    /// Задает предельные скорости осей.
+    pub fn set_max_speed(&mut self, speed: Velocity) -> Result<(), Error> {
+        let response = self.request(Request::Immediate(Command::SetMaxSpeed(speed)))?;
+        protocol::command_ack(response)
+    }
+
+    /// Ждет следующего ответа не дольше времени ожидания клиента.
+    pub fn recv(&mut self) -> Result<Tagged<Response>, Error> {
+        let deadline = Instant::now() + self.timeout;
+        self.next(Some(deadline))?.ok_or(Error::Timeout)
+    }
+
+    /// Возвращает ответ, если он уже принят, не блокируя поток.
+    pub fn try_recv(&mut self) -> Result<Option<Tagged<Response>>, Error> {
+        self.next(None)
+    }
+
+    /// Следующий ответ: сначала из сохраненных, затем из соединения.
+    fn next(&mut self, deadline: Option<Instant>) -> Result<Option<Tagged<Response>>, Error> {
+        if let Some(response) = self.inbox.pop_front() {
+            return Ok(Some(response));
+        }
+        loop {
+            match self.receive(deadline)? {
+                Some(Incoming::Response(response)) => return Ok(Some(response)),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Принимает следующий кадр ответа до момента `deadline`. Без `deadline`
+    /// принимает только уже пришедшие байты.
+    ///
+    /// Возвращает `None`, если кадр не принят к сроку. Принятая часть кадра
+    /// сохраняется в декодере до следующего вызова.
+    fn receive(&mut self, deadline: Option<Instant>) -> Result<Option<Incoming>, Error> {
+        if self.broken {
+            return Err(Error::Disconnected);
+        }
+        // Без срока сокет читается в неблокирующем режиме. Сокет общий с
+        // `writer`, поэтому блокирующий режим сразу возвращается.
+        let nonblocking = deadline.is_none();
+        self.reader.get_ref().set_nonblocking(nonblocking)?;
+        let result = self.read_frame(deadline);
+        if nonblocking {
+            self.reader.get_ref().set_nonblocking(false)?;
+        }
+        match result {
+            Ok(true) => {}
+            Ok(false) => return Ok(None),
+            Err(e) => {
+                self.broken = true;
+                return Err(e);
+            }
+        }
+        match protocol::incoming(&mut self.decoder) {
+            Some(Ok(incoming)) => {
+                if let Incoming::Response(response) = &incoming {
+                    self.session.receive(response);
+                }
+                Ok(Some(incoming))
+            }
+            Some(Err(e)) => {
+                self.broken = true;
+                Err(e)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Принимает байты в декодер до готового кадра или до истечения срока.
+    /// Возвращает `true`, если кадр готов.
+    fn read_frame(&mut self, deadline: Option<Instant>) -> Result<bool, Error> {
+        while !self.decoder.is_ready() {
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(false);
+                }
+                self.reader.get_ref().set_read_timeout(Some(remaining))?;
+            }
+            match protocol::fill_chunk(&mut self.reader, &mut self.decoder) {
+                Ok(()) => {}
+                Err(e) if is_timeout(&e) => return Ok(false),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Err(Error::Disconnected);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Отправитель перемещений, который держит очередь позиционирования
+/// заполненной, но не переполняет ее.
+///
+/// Блокирующий вариант [`crate::Throttle`]. Ожидание места в очереди и
+/// завершения перемещений ограничено временем ожидания клиента на каждый
+/// ответ прошивки. Итоги своих перемещений отправитель забирает себе,
+/// остальные ответы сохраняются для [`Robot::recv`] и [`Robot::try_recv`].
+pub struct Throttle<'a> {
+    robot: &'a mut Robot,
+    unacked: Unacked,
+}
+
+impl Throttle<'_> {
+    /// Отправляет перемещение, дождавшись свободного места в очереди.
+    ///
+    /// Если одно из ранее отправленных перемещений не завершено успешно,
+    /// возвращается его ошибка, а `waypoint` не отправляется.
+    pub fn send(&mut self, waypoint: Waypoint) -> Result<RequestId, Error> {
+        loop {
+            match self.robot.send(Request::Enqueue(waypoint)) {
+                Ok(id) => {
+                    self.unacked.push(id);
+                    return Ok(id);
+                }
+                Err(Error::QueueFull { .. }) => self.wait()?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Ждет завершения всех отправленных перемещений.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        while self.in_flight() > 0 {
+            self.wait()?;
+        }
+        Ok(())
+    }
+
+    /// Количество отправленных и еще не завершенных перемещений.
+    pub fn in_flight(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Ждет следующего ответа не дольше времени ожидания клиента.
+    fn wait(&mut self) -> Result<(), Error> {
+        let deadline = Instant::now() + self.robot.timeout;
+        match self.robot.receive(Some(deadline))? {
+            Some(Incoming::Response(response)) => match self.unacked.settle(&response) {
+                Some(result) => result,
+                None => {
+                    self.robot.inbox.push_back(response);
+                    Ok(())
+                }
+            },
+            Some(Incoming::Invalid(id, err)) if self.unacked.remove(id) => Err(err),
+            Some(_) => Ok(()),
+            None => Err(Error::Timeout),
+        }
+    }
+}
+
+/// Истечение времени ожидания сокета: `WouldBlock` на Unix, `TimedOut` на
+/// Windows.
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Переводит истечение времени ожидания сокета в [`Error::Timeout`].
+fn timed_out(e: io::Error) -> Error {
+    if is_timeout(&e) {
+        Error::Timeout
+    } else {
+        e.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{device, welcome};
+
+    const TIMEOUT: Duration = Duration::from_millis(50);
+
+    fn connect(addr: std::net::SocketAddr) -> Robot {
+        let mut robot = Robot::connect(addr).unwrap();
+        robot.set_timeout(TIMEOUT).unwrap();
+        robot
+    }
+
+    #[test]
+    fn test_request_and_recv_time_out() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            let request = peer.request();
+            // Ответ приходит после истечения времени ожидания.
+            assert_eq!(peer.request().message, Request::Keepalive);
+            peer.respond(request.id, Response::CommandAck);
+            peer.wait_closed();
+        });
+        let mut robot = connect(addr);
+        let started = Instant::now();
+        assert!(matches!(
+            robot.request(Request::GetStatus),
+            Err(Error::Timeout)
+        ));
+        assert!(started.elapsed() >= TIMEOUT);
+        assert!(matches!(robot.recv(), Err(Error::Timeout)));
+
+        // Соединение после истечения времени ожидания исправно.
+        robot.send(Request::Keepalive).unwrap();
+        robot.set_timeout(DEFAULT_TIMEOUT).unwrap();
+        let late = robot.recv().unwrap();
+        assert_eq!(late, Tagged::new(Some(0), Response::CommandAck));
+        drop(robot);
+        device.join().unwrap();
+    }
+
+    #[test]
+    fn test_try_recv_does_not_block() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            let request = peer.request();
+            let last = peer.respond_partially(request.id, Response::CommandAck);
+            assert_eq!(peer.request().message, Request::Keepalive);
+            peer.write(&[last]);
+            peer.wait_closed();
+        });
+        let mut robot = connect(addr);
+        assert!(matches!(robot.try_recv(), Ok(None)));
+
+        // Принятая часть кадра сохраняется между вызовами.
+        robot.send(Request::GetStatus).unwrap();
+        for _ in 0..5 {
+            std::thread::sleep(TIMEOUT / 5);
+            assert!(matches!(robot.try_recv(), Ok(None)));
+        }
+        robot.send(Request::Keepalive).unwrap();
+        robot.set_timeout(DEFAULT_TIMEOUT).unwrap();
+        let response = robot.recv().unwrap();
+        assert_eq!(response, Tagged::new(Some(0), Response::CommandAck));
+        assert!(matches!(robot.try_recv(), Ok(None)));
+        drop(robot);
+        device.join().unwrap();
+    }
+
+    #[test]
+    fn test_disconnect_mid_frame() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            let request = peer.request();
+            peer.respond_partially(request.id, Response::CommandAck);
+        });
+        let mut robot = connect(addr);
+        robot.set_timeout(DEFAULT_TIMEOUT).unwrap();
+        assert!(matches!(
+            robot.request(Request::GetStatus),
+            Err(Error::Disconnected)
+        ));
+        device.join().unwrap();
+        assert!(matches!(robot.try_recv(), Err(Error::Disconnected)));
+        assert!(matches!(
+            robot.send(Request::GetStatus),
+            Err(Error::Disconnected)
+        ));
+    }
+}
@@ -0,0 +1,123 @@
+use common::{
+    envelope::OpenError,
+    framing::FrameError,
+    handshake::{PROTOCOL_VERSION, Version},
+    response::{Rejection, Response},
+};
+use std::{fmt, io};
+
+/// Ошибка клиента.
+#[derive(Debug)]
+pub enum Error {
+    /// Ошибка ввода-вывода соединения.
+    Io(io::Error),
+    /// Рукопожатие не удалось.
+    Handshake(HandshakeError),
+    /// Кадр ответа поврежден. Граница следующего кадра потеряна, соединение
+    /// разорвано.
+    Frame(FrameError),
+    /// Ответ не удалось разобрать.
+    Envelope(OpenError),
+    /// Запрос не удалось сериализовать.
+    Encode(postcard::Error),
+    /// Команда не поддерживается прошивкой (см. `Welcome::commands`).
+    Unsupported,
+    /// В очереди позиционирования не хватает места для перемещений запроса.
+    /// Запрос не отправлен.
+    QueueFull {
+        /// Количество перемещений, которые можно отправить сейчас.
+        available: u8,
+    },
+    /// Прошивка отклонила запрос.
+    Rejected(Rejection),
+    /// Перемещение отменено остановкой или очисткой очереди.
+    Cancelled,
+    /// Прошивка ответила на запрос ответом другого типа.
+    UnexpectedResponse(Box<Response>),
+    /// Время ожидания истекло.
+    Timeout,
+    /// Подписчик [`crate::Responses`] отстал: столько самых старых ответов
+    /// пропущено.
+    Lagged(u64),
+    /// Соединение закрыто.
+    Disconnected,
+}
+
+/// Ошибка рукопожатия.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HandshakeError {
+    /// Ответ на приветствие не удалось разобрать.
+    Malformed,
+    /// Устройство не является роборукой.
+    NotARobot,
+    /// Прошивка говорит на другой версии протокола.
+    Incompatible {
+        protocol_version: u16,
+        firmware_version: Version,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Handshake(e) => write!(f, "{e}"),
+            Self::Frame(e) => write!(f, "некорректный кадр ответа: {e:?}"),
+            Self::Envelope(e) => write!(f, "ошибка разбора ответа: {e:?}"),
+            Self::Encode(e) => write!(f, "ошибка сериализации запроса: {e}"),
+            Self::Unsupported => f.write_str("команда не поддерживается прошивкой"),
+            Self::QueueFull { available } => write!(
+                f,
+                "очередь позиционирования заполнена (свободно {available}), дождитесь подтверждений"
+            ),
+            Self::Rejected(rejection) => write!(f, "отказ: {rejection}"),
+            Self::Cancelled => f.write_str("перемещение отменено"),
+            Self::UnexpectedResponse(response) => write!(f, "неожиданный ответ: {response:?}"),
+            Self::Timeout => f.write_str("время ожидания истекло"),
+            Self::Lagged(skipped) => write!(f, "пропущено ответов: {skipped}"),
+            Self::Disconnected => f.write_str("соединение закрыто"),
+        }
+    }
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => f.write_str("некорректный ответ на приветствие"),
+            Self::NotARobot => f.write_str("устройство не является роборукой"),
+            Self::Incompatible {
+                protocol_version,
+                firmware_version,
+            } => write!(
+                f,
+                "несовместимая версия протокола: клиент {PROTOCOL_VERSION}, прошивка {protocol_version} ({})",
+                firmware_version.as_str()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Handshake(e) => Some(e),
+            Self::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<HandshakeError> for Error {
+    fn from(e: HandshakeError) -> Self {
+        Self::Handshake(e)
+    }
+}
@@ -0,0 +1,37 @@
+//! Клиентская библиотека для управления манипулятором по сети.
+//!
+//! [`Robot`] устанавливает соединение с прошивкой, выполняет рукопожатие и
+//! ведет сессию: нумерует запросы, учитывает кредиты очереди
+//! позиционирования (см. [`common::flow`]) и сопоставляет ответы с
+//! запросами. Типы запросов и ответов, а также кодек кадров берутся из
+//! `common`.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), sdk::Error> {
+//! use common::{quantities::Position, units::Radians};
+//!
+//! let robot = sdk::Robot::connect("192.168.4.1:8080").await?;
+//! let angle = Radians::new(1.0);
+//! robot
+//!     .move_to(Position {
+//!         rotation: angle,
+//!         shoulder: angle,
+//!         forearm: angle,
+//!         claw: angle,
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod blocking;
+mod error;
+mod protocol;
+mod robot;
+#[cfg(test)]
+mod testing;
+mod throttle;
+
+pub use error::{Error, HandshakeError};
+pub use robot::{Responses, Robot};
+pub use throttle::Throttle;
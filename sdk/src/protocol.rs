@@ -0,0 +1,257 @@
+//! Протокол клиента: рукопожатие, нумерация запросов, кредиты очереди
+//! позиционирования и разбор ответов.
+//!
+//! Ввод-вывод здесь только в чтении кадров из буфера чтения, общем для
+//! блокирующего и асинхронного клиентов.
+
+use crate::error::{Error, HandshakeError};
+use common::{
+    envelope::{self, MAX_REQUEST_ENVELOPE_SIZE, OpenError},
+    flow::Credits,
+    framing::{self, FrameDecoder, FrameError, ResponseDecoder, frame_size, max_frame_size},
+    handshake::{CommandSet, Hello, MAGIC, Version, Welcome},
+    request::Request,
+    response::Response,
+    session::{RequestId, Tagged},
+};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Наибольший размер кадра запроса.
+pub(crate) const MAX_REQUEST_FRAME_SIZE: usize = frame_size(MAX_REQUEST_ENVELOPE_SIZE);
+
+/// Наибольший размер кадра приветствия.
+pub(crate) const HELLO_FRAME_SIZE: usize = max_frame_size::<Hello>();
+
+/// Декодер ответа на приветствие.
+pub(crate) type WelcomeDecoder = FrameDecoder<{ max_frame_size::<Welcome>() }>;
+
+/// Кодирует приветствие клиента.
+pub(crate) fn hello(buf: &mut [u8; HELLO_FRAME_SIZE]) -> Result<&[u8], Error> {
+    let version = Version::try_from(env!("CARGO_PKG_VERSION")).unwrap_or_default();
+    framing::encode(&Hello::new(version), buf).map_err(Error::Encode)
+}
+
+/// Создает декодер ответа на приветствие.
+///
+/// Поля, добавленные в `Welcome` в будущих версиях, отбрасываются.
+pub(crate) fn welcome_decoder() -> WelcomeDecoder {
+    WelcomeDecoder::truncating()
+}
+
+/// Разбирает и проверяет ответ прошивки на приветствие.
+pub(crate) fn welcome(decoder: &mut WelcomeDecoder) -> Result<Welcome, Error> {
+    let Some(Ok(body)) = decoder.pull() else {
+        return Err(HandshakeError::Malformed.into());
+    };
+    let welcome = common::from_bytes::<Welcome>(body).map_err(|_| HandshakeError::Malformed)?;
+    if welcome.magic != MAGIC {
+        return Err(HandshakeError::NotARobot.into());
+    }
+    if !welcome.is_compatible() {
+        return Err(HandshakeError::Incompatible {
+            protocol_version: welcome.protocol_version,
+            firmware_version: welcome.firmware_version,
+        }
+        .into());
+    }
+    Ok(welcome)
+}
+
+/// Принимает байты в декодер, пока в нем не будет готов кадр.
+///
+/// Байты следующих кадров остаются в буфере чтения. Закрытие соединения
+/// возвращается как `UnexpectedEof`.
+pub(crate) fn fill_frame<R: BufRead, const N: usize>(
+    reader: &mut R,
+    decoder: &mut FrameDecoder<N>,
+) -> io::Result<()> {
+    while !decoder.is_ready() {
+        fill_chunk(reader, decoder)?;
+    }
+    Ok(())
+}
+
+/// Принимает в декодер одну порцию байтов из буфера чтения.
+///
+/// Для чтения с ограничением времени, которое задается перед каждой порцией.
+pub(crate) fn fill_chunk<R: BufRead, const N: usize>(
+    reader: &mut R,
+    decoder: &mut FrameDecoder<N>,
+) -> io::Result<()> {
+    let used = push(decoder, reader.fill_buf()?)?;
+    reader.consume(used);
+    Ok(())
+}
+
+/// Асинхронный вариант [`fill_frame`].
+///
+/// Ожидание можно прервать: принятая часть кадра сохраняется в декодере.
+pub(crate) async fn fill_frame_async<R: AsyncBufRead + Unpin, const N: usize>(
+    reader: &mut R,
+    decoder: &mut FrameDecoder<N>,
+) -> io::Result<()> {
+    while !decoder.is_ready() {
+        let used = push(decoder, reader.fill_buf().await?)?;
+        reader.consume(used);
+    }
+    Ok(())
+}
+
+/// Передает декодеру прочитанные байты и возвращает, сколько он принял.
+/// Пустой буфер означает закрытие соединения.
+fn push<const N: usize>(decoder: &mut FrameDecoder<N>, data: &[u8]) -> io::Result<usize> {
+    if data.is_empty() {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(decoder.push(data))
+}
+
+/// Принятый кадр ответа.
+pub(crate) enum Incoming {
+    /// Ответ прошивки.
+    Response(Tagged<Response>),
+    /// Ответ на запрос `id`, который не удалось разобрать.
+    Invalid(RequestId, Error),
+    /// Ответ пропущен: он неизвестного типа, слишком велик или не относится
+    /// к запросу.
+    Skipped,
+}
+
+/// Забирает ответ из декодера. Возвращает `None`, если кадр еще не принят.
+///
+/// Ответы новых типов от более новой прошивки пропускаются. Ошибка
+/// означает, что граница следующего кадра потеряна и соединение следует
+/// разорвать.
+pub(crate) fn incoming(decoder: &mut ResponseDecoder) -> Option<Result<Incoming, Error>> {
+    let incoming = match decoder.pull()? {
+        Ok(body) => match envelope::open::<Response>(body) {
+            Ok(response) => Incoming::Response(response),
+            Err(OpenError::Unsupported { .. }) => Incoming::Skipped,
+            Err(e) => match e.id() {
+                Some(id) => Incoming::Invalid(id, Error::Envelope(e)),
+                None => Incoming::Skipped,
+            },
+        },
+        Err(FrameError::TooLarge(_)) => Incoming::Skipped,
+        Err(e) => return Some(Err(Error::Frame(e))),
+    };
+    Some(Ok(incoming))
+}
+
+/// Состояние сессии с роборукой.
+#[derive(Clone, Debug)]
+pub(crate) struct Session {
+    /// Идентификатор следующего запроса.
+    next_id: RequestId,
+    /// Команды, которые поддерживает прошивка.
+    commands: CommandSet,
+    /// Кредиты на места в очереди позиционирования.
+    credits: Credits,
+}
+
+impl Session {
+    pub(crate) fn new(welcome: &Welcome) -> Self {
+        Self {
+            next_id: 0,
+            commands: welcome.commands,
+            credits: Credits::new(welcome.free_slots),
+        }
+    }
+
+    /// Количество перемещений, которые можно отправить сейчас.
+    pub(crate) fn available(&self) -> u8 {
+        self.credits.available()
+    }
+
+    /// Помечает запрос очередным идентификатором и кодирует его в кадр.
+    ///
+    /// Команды, которые прошивка не поддерживает, и перемещения сверх
+    /// свободного места в очереди не кодируются. Закодированный запрос
+    /// тратит кредиты, поэтому кадр должен быть отправлен.
+    pub(crate) fn seal<'a>(
+        &mut self,
+        request: Request,
+        buf: &'a mut [u8; MAX_REQUEST_FRAME_SIZE],
+    ) -> Result<(RequestId, &'a [u8]), Error> {
+        if let Request::Immediate(cmd) = &request
+            && !self.commands.contains(cmd)
+        {
+            return Err(Error::Unsupported);
+        }
+        let id = self.next_id;
+        let request = Tagged::new(Some(id), request);
+        let frame = framing::encode(&envelope::seal(&request), buf).map_err(Error::Encode)?;
+        if !self.credits.acquire(id, &request.message) {
+            return Err(Error::QueueFull {
+                available: self.credits.available(),
+            });
+        }
+        self.next_id = id.wrapping_add(1);
+        Ok((id, frame))
+    }
+
+    /// Учитывает ответ прошивки.
+    pub(crate) fn receive(&mut self, response: &Tagged<Response>) {
+        self.credits.release(response);
+    }
+}
+
+/// Итог запроса по первому ответу на него.
+pub(crate) fn outcome(response: Response) -> Result<Response, Error> {
+    match response {
+        Response::Rejected(rejection) => Err(Error::Rejected(rejection)),
+        Response::MotionCancelled => Err(Error::Cancelled),
+        response => Ok(response),
+    }
+}
+
+/// Итог перемещения: подтверждение, отмена или отказ.
+pub(crate) fn position_ack(response: Response) -> Result<(), Error> {
+    match outcome(response)? {
+        Response::PositionAck { .. } => Ok(()),
+        response => Err(Error::UnexpectedResponse(Box::new(response))),
+    }
+}
+
+/// Итог команды: подтверждение или отказ.
+pub(crate) fn command_ack(response: Response) -> Result<(), Error> {
+    match outcome(response)? {
+        Response::CommandAck => Ok(()),
+        response => Err(Error::UnexpectedResponse(Box::new(response))),
+    }
+}
+
+/// Перемещения, отправленные и еще не завершенные.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Unacked(VecDeque<RequestId>);
+
+impl Unacked {
+    pub(crate) fn push(&mut self, id: RequestId) {
+        self.0.push_back(id);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Снимает перемещение `id` с учета. Возвращает `false`, если оно не
+    /// учитывалось.
+    pub(crate) fn remove(&mut self, id: RequestId) -> bool {
+        let Some(index) = self.0.iter().position(|&unacked| unacked == id) else {
+            return false;
+        };
+        self.0.remove(index);
+        true
+    }
+
+    /// Учитывает итог перемещения. Возвращает `None`, если ответ не
+    /// относится к учитываемым перемещениям.
+    pub(crate) fn settle(&mut self, response: &Tagged<Response>) -> Option<Result<(), Error>> {
+        let id = response.id?;
+        self.remove(id).then(|| position_ack(response.message))
+    }
+}
@@ -0,0 +1,424 @@
+//! Асинхронный клиент на tokio.
+
+use crate::{
+    error::Error,
+    protocol::{self, HELLO_FRAME_SIZE, Incoming, MAX_REQUEST_FRAME_SIZE, Session},
+    throttle::Throttle,
+};
+use common::{
+    framing::ResponseDecoder,
+    handshake::Welcome,
+    quantities::{Position, Velocity},
+    request::{Command, Request, Waypoint},
+    response::Response,
+    session::{RequestId, Tagged},
+    wifi_config::WifiConfig,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use tokio::{
+    io::{AsyncWriteExt, BufReader},
+    net::{
+        TcpStream, ToSocketAddrs,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    select,
+    sync::{broadcast, mpsc, oneshot},
+};
+
+/// Емкость очереди ответов каждого подписчика [`Responses`].
+const RESPONSE_QUEUE_LEN: usize = 256;
+
+/// Ожидающий ответа запрос.
+type Reply = oneshot::Sender<Result<Response, Error>>;
+
+/// Соединение с роборукой.
+///
+/// Ввод-вывод ведет фоновая задача, поэтому методы принимают `&self`, а
+/// запросы из нескольких задач отправляются параллельно. Ожидание ответа
+/// можно прервать в любой момент: запрос к этому времени уже передан
+/// фоновой задаче и будет отправлен целиком.
+///
+/// Соединение закрывается, когда `Robot` уничтожен.
+pub struct Robot {
+    welcome: Welcome,
+    shared: Arc<Mutex<Shared>>,
+    /// Кадры запросов для фоновой задачи.
+    frames: mpsc::UnboundedSender<Vec<u8>>,
+    /// Подписка, от которой создаются новые подписчики.
+    responses: broadcast::Receiver<Tagged<Response>>,
+}
+
+/// Состояние, общее для клиента и фоновой задачи.
+struct Shared {
+    session: Session,
+    /// Запросы, ожидающие первого ответа.
+    pending: HashMap<RequestId, Reply>,
+    /// Соединение закрыто.
+    closed: bool,
+}
+
+impl Robot {
+    /// Подключается к роборуке и выполняет рукопожатие.
+    pub async fn connect(addr: impl ToSocketAddrs) -> Result<Self, Error> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let mut buf = [0u8; HELLO_FRAME_SIZE];
+        writer.write_all(protocol::hello(&mut buf)?).await?;
+        let mut decoder = protocol::welcome_decoder();
+        protocol::fill_frame_async(&mut reader, &mut decoder).await?;
+        let welcome = protocol::welcome(&mut decoder)?;
+
+        let shared = Arc::new(Mutex::new(Shared {
+            session: Session::new(&welcome),
+            pending: HashMap::new(),
+            closed: false,
+        }));
+        let (frames_tx, frames_rx) = mpsc::unbounded_channel();
+        let (responses_tx, responses_rx) = broadcast::channel(RESPONSE_QUEUE_LEN);
+        tokio::spawn(run(reader, writer, frames_rx, shared.clone(), responses_tx));
+
+        Ok(Self {
+            welcome,
+            shared,
+            frames: frames_tx,
+            responses: responses_rx,
+        })
+    }
+
+    /// Ответ прошивки на приветствие.
+    pub fn welcome(&self) -> &Welcome {
+        &self.welcome
+    }
+
+    /// Количество перемещений, которые можно отправить сейчас, не
+    /// переполнив очередь позиционирования.
+    pub fn free_slots(&self) -> u8 {
+        self.lock().session.available()
+    }
+
+    /// Подписывается на ответы прошивки, принятые после вызова.
+    pub fn responses(&self) -> Responses {
+        Responses(self.responses.resubscribe())
+    }
+
+    /// Создает отправитель перемещений с обратным давлением.
+    pub fn throttle(&self) -> Throttle<'_> {
+        Throttle::new(self)
+    }
+
+    /// Отправляет запрос, не дожидаясь ответа, и возвращает его
+    /// идентификатор. Ответы приходят подписчикам [`Self::responses`].
+    pub fn send(&self, request: Request) -> Result<RequestId, Error> {
+        self.submit(request, None)
+    }
+
+    /// Отправляет запрос и ждет первого ответа на него.
+    ///
+    /// Отказ и отмена перемещения возвращаются ошибками. Для пакета
+    /// перемещений ответом считается итог первого из них.
+    pub async fn request(&self, request: Request) -> Result<Response, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.submit(request, Some(tx))?;
+        protocol::outcome(rx.await.map_err(|_| Error::Disconnected)??)
+    }
+
+    /// Перемещает манипулятор в позицию `position` и ждет завершения
+    /// перемещения.
+    pub async fn move_to(&self, position: Position) -> Result<(), Error> {
+        let response = self.request(Request::Enqueue(Waypoint::from(position)));
+        protocol::position_ack(response.await?)
+    }
+
+    /// Сохраняет конфигурацию Wi-Fi. Применяется после перезагрузки.
+    pub async fn configure_wifi(&self, config: WifiConfig) -> Result<(), Error> {
+        let response = self.request(Request::Immediate(Command::ConfigureWifi(config)));
+        protocol::command_ack(response.await?)
+    }
+
+    /// Задает предельные скорости осей.
+    pub async fn set_max_speed(&self, speed: Velocity) -> Result<(), Error> {
+        let response = self.request(Request::Immediate(Command::SetMaxSpeed(speed)));
+        protocol::command_ack(response.await?)
+    }
+
+    /// Кодирует запрос и передает кадр фоновой задаче.
+    fn submit(&self, request: Request, reply: Option<Reply>) -> Result<RequestId, Error> {
+        let mut shared = self.lock();
+        if shared.closed {
+            return Err(Error::Disconnected);
+        }
+        let mut buf = [0u8; MAX_REQUEST_FRAME_SIZE];
+        let (id, frame) = shared.session.seal(request, &mut buf)?;
+        self.frames
+            .send(frame.to_vec())
+            .map_err(|_| Error::Disconnected)?;
+        if let Some(reply) = reply {
+            shared.pending.insert(id, reply);
+        }
+        Ok(id)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        lock(&self.shared)
+    }
+}
+
+/// Ответы прошивки в порядке приема.
+pub struct Responses(broadcast::Receiver<Tagged<Response>>);
+
+impl Responses {
+    /// Ждет следующего ответа. Когда соединение закрыто, возвращает
+    /// [`Error::Disconnected`].
+    ///
+    /// Если подписчик отстал больше чем на `RESPONSE_QUEUE_LEN` ответов,
+    /// самые старые из них пропускаются, и возвращается [`Error::Lagged`].
+    /// Следующий вызов вернет самый старый из сохраненных ответов.
+    pub async fn recv(&mut self) -> Result<Tagged<Response>, Error> {
+        self.0.recv().await.map_err(|e| match e {
+            broadcast::error::RecvError::Lagged(skipped) => Error::Lagged(skipped),
+            broadcast::error::RecvError::Closed => Error::Disconnected,
+        })
+    }
+
+    /// Возвращает ответ, если он уже принят, не дожидаясь следующего.
+    ///
+    /// Ошибки те же, что у [`Self::recv`].
+    pub fn try_recv(&mut self) -> Result<Option<Tagged<Response>>, Error> {
+        match self.0.try_recv() {
+            Ok(response) => Ok(Some(response)),
+            Err(broadcast::error::TryRecvError::Empty) => Ok(None),
+            Err(broadcast::error::TryRecvError::Lagged(skipped)) => Err(Error::Lagged(skipped)),
+            Err(broadcast::error::TryRecvError::Closed) => Err(Error::Disconnected),
+        }
+    }
+}
+
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_, Shared> {
+    shared.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Фоновая задача соединения: отправляет кадры запросов и раздает ответы.
+///
+/// По завершении все ожидающие запросы получают [`Error::Disconnected`], а
+/// подписчики — конец потока ответов.
+async fn run(
+    mut reader: BufReader<OwnedReadHalf>,
+    mut writer: OwnedWriteHalf,
+    mut frames: mpsc::UnboundedReceiver<Vec<u8>>,
+    shared: Arc<Mutex<Shared>>,
+    responses: broadcast::Sender<Tagged<Response>>,
+) {
+    let mut decoder = ResponseDecoder::new();
+    loop {
+        select! {
+            res = protocol::fill_frame_async(&mut reader, &mut decoder) => {
+                let incoming = match res {
+                    Ok(()) => protocol::incoming(&mut decoder),
+                    Err(_) => break,
+                };
+                match incoming {
+                    Some(Ok(Incoming::Response(response))) => {
+                        let reply = {
+                            let mut shared = lock(&shared);
+                            shared.session.receive(&response);
+                            response.id.and_then(|id| shared.pending.remove(&id))
+                        };
+                        if let Some(reply) = reply {
+                            let _ = reply.send(Ok(response.message));
+                        }
+                        let _ = responses.send(response);
+                    }
+                    Some(Ok(Incoming::Invalid(id, err))) => {
+                        if let Some(reply) = lock(&shared).pending.remove(&id) {
+                            let _ = reply.send(Err(err));
+                        }
+                    }
+                    Some(Ok(Incoming::Skipped)) | None => {}
+                    Some(Err(_)) => break,
+                }
+            }
+            frame = frames.recv() => {
+                let Some(frame) = frame else {
+                    // Клиент уничтожен.
+                    let _ = writer.shutdown().await;
+                    break;
+                };
+                if writer.write_all(&frame).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    let mut shared = lock(&shared);
+    shared.closed = true;
+    shared.pending.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::HandshakeError,
+        testing::{FREE_SLOTS, device, join, welcome},
+    };
+    use common::{
+        handshake::{CommandSet, PROTOCOL_VERSION},
+        response::Rejection,
+        units::RadiansPerSecond,
+    };
+
+    fn position(angle: f32) -> Position {
+        Position {
+            rotation: angle.into(),
+            shoulder: angle.into(),
+            forearm: angle.into(),
+            claw: angle.into(),
+        }
+    }
+
+    fn speed() -> Velocity {
+        let speed = RadiansPerSecond::new(1.0);
+        Velocity {
+            rotation: speed,
+            shoulder: speed,
+            forearm: speed,
+            claw: speed,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handshake() {
+        let (addr, device) = device(welcome(), |mut peer| peer.wait_closed());
+        let robot = Robot::connect(addr).await.unwrap();
+        assert_eq!(robot.welcome(), &welcome());
+        assert_eq!(robot.free_slots(), FREE_SLOTS);
+        drop(robot);
+        join(device).await;
+    }
+
+    #[tokio::test]
+    async fn test_handshake_errors() {
+        let not_a_robot = Welcome {
+            magic: *b"HTTP",
+            ..welcome()
+        };
+        let (addr, _) = device(not_a_robot, |mut peer| peer.wait_closed());
+        assert!(matches!(
+            Robot::connect(addr).await,
+            Err(Error::Handshake(HandshakeError::NotARobot))
+        ));
+
+        let incompatible = Welcome {
+            protocol_version: PROTOCOL_VERSION + 1,
+            ..welcome()
+        };
+        let (addr, _) = device(incompatible, |mut peer| peer.wait_closed());
+        assert!(matches!(
+            Robot::connect(addr).await,
+            Err(Error::Handshake(HandshakeError::Incompatible {
+                protocol_version,
+                ..
+            })) if protocol_version == PROTOCOL_VERSION + 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_command_is_not_sent() {
+        let welcome = Welcome {
+            commands: CommandSet::EMPTY,
+            ..welcome()
+        };
+        let (addr, device) = device(welcome, |mut peer| peer.request());
+        let robot = Robot::connect(addr).await.unwrap();
+        assert!(matches!(
+            robot.set_max_speed(speed()).await,
+            Err(Error::Unsupported)
+        ));
+        // Следующим устройство получает уже запрос после отклоненного.
+        robot.send(Request::Keepalive).unwrap();
+        assert_eq!(join(device).await.message, Request::Keepalive);
+    }
+
+    #[tokio::test]
+    async fn test_credits() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            let requests: Vec<_> = (0..FREE_SLOTS).map(|_| peer.request()).collect();
+            peer.respond(requests[0].id, Response::PositionAck { free_slots: 1 });
+            assert_eq!(peer.request().message, Request::GetStatus);
+            peer.respond(
+                requests[1].id,
+                Response::Rejected(Rejection::PositionQueueFull),
+            );
+            peer.wait_closed();
+        });
+        let robot = Robot::connect(addr).await.unwrap();
+        let mut responses = robot.responses();
+        for i in 0..FREE_SLOTS {
+            robot.send(Request::Enqueue(position(0.1).into())).unwrap();
+            assert_eq!(robot.free_slots(), FREE_SLOTS - i - 1);
+        }
+        assert!(matches!(
+            robot.send(Request::Enqueue(position(0.1).into())),
+            Err(Error::QueueFull { available: 0 })
+        ));
+
+        // Подтверждение и отказ возвращают кредиты.
+        responses.recv().await.unwrap();
+        assert_eq!(robot.free_slots(), 1);
+        // Запросы без перемещений кредитов не требуют.
+        robot.send(Request::GetStatus).unwrap();
+        responses.recv().await.unwrap();
+        assert_eq!(robot.free_slots(), 2);
+        drop(robot);
+        join(device).await;
+    }
+
+    #[tokio::test]
+    async fn test_responses_fan_out() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            let request = peer.request();
+            peer.respond(None, Response::MotionCancelled);
+            peer.respond(request.id, Response::CommandAck);
+            peer.wait_closed();
+        });
+        let robot = Robot::connect(addr).await.unwrap();
+        let (mut first, mut second) = (robot.responses(), robot.responses());
+
+        // Ответ на запрос получает и ожидающий его вызов, и подписчики.
+        robot.set_max_speed(speed()).await.unwrap();
+        for responses in [&mut first, &mut second] {
+            let unsolicited = responses.recv().await.unwrap();
+            assert_eq!(unsolicited, Tagged::new(None, Response::MotionCancelled));
+            let reply = responses.recv().await.unwrap();
+            assert_eq!(reply, Tagged::new(Some(0), Response::CommandAck));
+            assert!(matches!(responses.try_recv(), Ok(None)));
+        }
+        drop(robot);
+        join(device).await;
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_fails_pending_requests() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            peer.request();
+        });
+        let robot = Robot::connect(addr).await.unwrap();
+        let mut responses = robot.responses();
+        assert!(matches!(
+            robot.move_to(position(0.5)).await,
+            Err(Error::Disconnected)
+        ));
+        assert!(matches!(responses.recv().await, Err(Error::Disconnected)));
+        assert!(matches!(
+            robot.send(Request::GetStatus),
+            Err(Error::Disconnected)
+        ));
+        join(device).await;
+    }
+}
@@ -0,0 +1,122 @@
+//! Имитация прошивки на локальном TCP-сокете для тестов клиентов.
+//!
+//! Устройство работает в отдельном потоке и говорит на протоколе на уровне
+//! кадров: принимает приветствие, отвечает заданным `Welcome`, читает
+//! запросы и отправляет ответы, которые задает тест.
+
+use crate::protocol;
+use common::{
+    envelope::{self, MAX_RESPONSE_ENVELOPE_SIZE},
+    framing::{self, FrameDecoder, RequestDecoder, frame_size, max_frame_size},
+    handshake::{CommandSet, Hello, MAGIC, PROTOCOL_VERSION, Welcome},
+    request::Request,
+    response::Response,
+    session::{RequestId, Tagged},
+};
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread::{self, JoinHandle},
+};
+
+/// Свободное место в очереди позиционирования по умолчанию.
+pub(crate) const FREE_SLOTS: u8 = 4;
+
+/// Приветствие совместимой прошивки со всеми командами.
+pub(crate) fn welcome() -> Welcome {
+    Welcome {
+        magic: MAGIC,
+        protocol_version: PROTOCOL_VERSION,
+        firmware_version: "test".try_into().unwrap(),
+        session: 1,
+        axes: 4,
+        commands: CommandSet::ALL,
+        free_slots: FREE_SLOTS,
+        watchdog_tripped: false,
+    }
+}
+
+/// Запускает устройство, которое отвечает на приветствие `welcome` и затем
+/// передает соединение `script`. Возвращает адрес устройства и поток, из
+/// которого `script` возвращает результат.
+pub(crate) fn device<T: Send + 'static>(
+    welcome: Welcome,
+    script: impl FnOnce(Peer) -> T + Send + 'static,
+) -> (SocketAddr, JoinHandle<T>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut peer = Peer {
+            writer: stream.try_clone().unwrap(),
+            reader: BufReader::new(stream),
+            decoder: RequestDecoder::new(),
+        };
+
+        let mut decoder = FrameDecoder::<{ max_frame_size::<Hello>() }>::new();
+        protocol::fill_frame(&mut peer.reader, &mut decoder).expect("клиент закрыл соединение");
+        let hello = common::from_bytes::<Hello>(decoder.pull().unwrap().unwrap()).unwrap();
+        assert!(hello.is_compatible());
+
+        let mut buf = [0u8; max_frame_size::<Welcome>()];
+        peer.write(framing::encode(&welcome, &mut buf).unwrap());
+        script(peer)
+    });
+    (addr, handle)
+}
+
+/// Ждет завершения устройства, не блокируя фоновую задачу асинхронного
+/// клиента.
+pub(crate) async fn join<T: Send + 'static>(device: JoinHandle<T>) -> T {
+    tokio::task::spawn_blocking(move || device.join().unwrap())
+        .await
+        .unwrap()
+}
+
+/// Соединение устройства с клиентом.
+pub(crate) struct Peer {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    decoder: RequestDecoder,
+}
+
+impl Peer {
+    /// Ждет следующего запроса.
+    pub(crate) fn request(&mut self) -> Tagged<Request> {
+        protocol::fill_frame(&mut self.reader, &mut self.decoder)
+            .expect("клиент закрыл соединение");
+        envelope::open(self.decoder.pull().unwrap().unwrap()).unwrap()
+    }
+
+    /// Отправляет ответ на запрос `id`.
+    pub(crate) fn respond(&mut self, id: Option<RequestId>, response: Response) {
+        let mut buf = [0u8; frame_size(MAX_RESPONSE_ENVELOPE_SIZE)];
+        self.write(frame(id, response, &mut buf));
+    }
+
+    /// Отправляет кадр ответа без последнего байта и возвращает этот байт.
+    pub(crate) fn respond_partially(&mut self, id: Option<RequestId>, response: Response) -> u8 {
+        let mut buf = [0u8; frame_size(MAX_RESPONSE_ENVELOPE_SIZE)];
+        let frame = frame(id, response, &mut buf);
+        let (head, last) = frame.split_at(frame.len() - 1);
+        self.writer.write_all(head).unwrap();
+        last[0]
+    }
+
+    /// Отправляет байты как есть.
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        self.writer.write_all(bytes).unwrap();
+    }
+
+    /// Ждет, пока клиент закроет соединение.
+    pub(crate) fn wait_closed(&mut self) {
+        while matches!(self.reader.fill_buf(), Ok(data) if !data.is_empty()) {
+            let len = self.reader.buffer().len();
+            self.reader.consume(len);
+        }
+    }
+}
+
+fn frame(id: Option<RequestId>, response: Response, buf: &mut [u8]) -> &[u8] {
+    framing::encode(&envelope::seal(&Tagged::new(id, response)), buf).unwrap()
+}
@@ -0,0 +1,159 @@
+//! Потоковая отправка перемещений с обратным давлением.
+
+use crate::{
+    error::Error,
+    protocol::Unacked,
+    robot::{Responses, Robot},
+};
+use common::{
+    request::{Request, Waypoint},
+    response::Response,
+    session::{RequestId, Tagged},
+};
+
+/// Отправитель перемещений, который держит очередь позиционирования
+/// заполненной, но не переполняет ее.
+///
+/// [`Self::send`] ждет, пока в очереди освободится место, поэтому
+/// траекторию любой длины можно отправлять точка за точкой: скорость
+/// отправки подстраивается под скорость исполнения. Отправитель учитывает
+/// свои незавершенные перемещения; отказ или отмена любого из них
+/// возвращается ошибкой из очередного вызова [`Self::send`] или
+/// [`Self::flush`].
+///
+/// Если отправитель не успевал разбирать ответы и часть из них пропущена,
+/// итоги незавершенных перемещений неизвестны: они снимаются с учета, а
+/// вызов возвращает [`Error::Lagged`].
+///
+/// ```no_run
+/// # async fn example(robot: &sdk::Robot, trajectory: &[common::request::Waypoint])
+/// # -> Result<(), sdk::Error> {
+/// let mut throttle = robot.throttle();
+/// for waypoint in trajectory {
+///     throttle.send(*waypoint).await?;
+/// }
+/// throttle.flush().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Throttle<'a> {
+    robot: &'a Robot,
+    responses: Responses,
+    unacked: Unacked,
+}
+
+impl<'a> Throttle<'a> {
+    pub(crate) fn new(robot: &'a Robot) -> Self {
+        Self {
+            robot,
+            responses: robot.responses(),
+            unacked: Unacked::default(),
+        }
+    }
+
+    /// Отправляет перемещение, дождавшись свободного места в очереди.
+    ///
+    /// Если одно из ранее отправленных перемещений не завершено успешно,
+    /// возвращается его ошибка, а `waypoint` не отправляется.
+    pub async fn send(&mut self, waypoint: Waypoint) -> Result<RequestId, Error> {
+        while let Some(response) = self.responses.try_recv().map_err(|e| self.lost(e))? {
+            self.settle(&response)?;
+        }
+        loop {
+            match self.robot.send(Request::Enqueue(waypoint)) {
+                Ok(id) => {
+                    self.unacked.push(id);
+                    return Ok(id);
+                }
+                Err(Error::QueueFull { .. }) => self.wait().await?,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Ждет завершения всех отправленных перемещений.
+    pub async fn flush(&mut self) -> Result<(), Error> {
+        while self.in_flight() > 0 {
+            self.wait().await?;
+        }
+        Ok(())
+    }
+
+    /// Количество отправленных и еще не завершенных перемещений.
+    pub fn in_flight(&self) -> usize {
+        self.unacked.len()
+    }
+
+    /// Ждет следующего ответа.
+    async fn wait(&mut self) -> Result<(), Error> {
+        let response = self.responses.recv().await.map_err(|e| self.lost(e))?;
+        self.settle(&response)
+    }
+
+    /// Снимает с учета перемещения, итоги которых могли быть пропущены.
+    fn lost(&mut self, error: Error) -> Error {
+        if let Error::Lagged(_) = error {
+            self.unacked = Unacked::default();
+        }
+        error
+    }
+
+    fn settle(&mut self, response: &Tagged<Response>) -> Result<(), Error> {
+        self.unacked.settle(response).unwrap_or(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{device, join, welcome};
+    use common::quantities::Position;
+
+    /// Количество ответов, после которых подписчик гарантированно отстает.
+    const FLOOD_LEN: usize = 300;
+
+    fn waypoint() -> Waypoint {
+        let angle = 0.5.into();
+        Waypoint::from(Position {
+            rotation: angle,
+            shoulder: angle,
+            forearm: angle,
+            claw: angle,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_flush_returns_after_lag() {
+        let (addr, device) = device(welcome(), |mut peer| {
+            let request = peer.request();
+            // Итог перемещения вытесняется из очереди подписчика следующими
+            // ответами.
+            peer.respond(request.id, Response::PositionAck { free_slots: 4 });
+            for _ in 0..FLOOD_LEN {
+                peer.respond(None, Response::CommandAck);
+            }
+            peer.respond(None, Response::MotionCancelled);
+            peer.wait_closed();
+        });
+        let robot = Robot::connect(addr).await.unwrap();
+        let mut probe = robot.responses();
+        let mut throttle = robot.throttle();
+        throttle.send(waypoint()).await.unwrap();
+
+        // Ждет, пока будут приняты все ответы.
+        loop {
+            match probe.recv().await {
+                Ok(response) if response.message == Response::MotionCancelled => break,
+                Ok(_) | Err(Error::Lagged(_)) => {}
+                Err(e) => panic!("{e}"),
+            }
+        }
+        assert!(matches!(throttle.flush().await, Err(Error::Lagged(_))));
+        assert_eq!(throttle.in_flight(), 0);
+        throttle.flush().await.unwrap();
+
+        drop(throttle);
+        drop(robot);
+        join(device).await;
+    }
+}
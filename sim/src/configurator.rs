@@ -0,0 +1,108 @@
+use crate::connectors::{Connectors, Tag};
+use common::{
+    calibration::Calibration,
+    configurator::{Configurator, Listeners, Storage},
+    mechanics_config::StartupMechanicsConfig,
+    request::Command,
+    wifi_config::WifiConfig,
+};
+use std::{fmt, fs, io, path::PathBuf};
+use tokio::sync::mpsc;
+
+/// Наибольший размер файла конфигурации Wi-Fi.
+const MAX_WIFI_CONFIG_SIZE: usize = 512;
+
+/// Хранилище конфигурации вместо Flash-памяти.
+///
+/// Конфигурация Wi-Fi сохраняется в файл и переживает перезапуск симулятора.
+/// Конфигурация механики и калибровка хранятся в памяти и при запуске
+/// принимают значения по умолчанию.
+pub struct ConfigStorage {
+    path: PathBuf,
+    mechanics: Option<StartupMechanicsConfig>,
+    calibration: Option<Calibration>,
+}
+
+impl ConfigStorage {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            mechanics: None,
+            calibration: None,
+        }
+    }
+}
+
+impl Storage for ConfigStorage {
+    type Error = io::Error;
+
+    async fn fetch_wifi(&mut self) -> Result<Option<WifiConfig>, io::Error> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        common::from_bytes(&bytes).map(Some).map_err(invalid_data)
+    }
+
+    async fn store_wifi(&mut self, config: &WifiConfig) -> Result<(), io::Error> {
+        let bytes = common::to_vec::<_, MAX_WIFI_CONFIG_SIZE>(config).map_err(invalid_data)?;
+        fs::write(&self.path, &*bytes)
+    }
+
+    async fn fetch_mechanics(&mut self) -> Result<Option<StartupMechanicsConfig>, io::Error> {
+        Ok(self.mechanics.clone())
+    }
+
+    async fn store_mechanics(&mut self, config: &StartupMechanicsConfig) -> Result<(), io::Error> {
+        self.mechanics = Some(config.clone());
+        Ok(())
+    }
+
+    async fn fetch_calibration(&mut self) -> Result<Option<Calibration>, io::Error> {
+        Ok(self.calibration)
+    }
+
+    async fn store_calibration(&mut self, calibration: &Calibration) -> Result<(), io::Error> {
+        self.calibration = Some(*calibration);
+        Ok(())
+    }
+}
+
+fn invalid_data(err: postcard::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+impl Listeners for &Connectors {
+    fn wifi(&self, _config: WifiConfig) {
+        // Сетевого стека нет: конфигурация Wi-Fi только хранится.
+    }
+
+    fn mechanics(&self, config: StartupMechanicsConfig) {
+        self.joint_limits.set(config.joint_limits);
+        self.mechanics_config.signal(config);
+    }
+
+    fn calibration(&self, calibration: Calibration) {
+        self.calibration.signal(calibration);
+    }
+
+    fn log(&self, args: fmt::Arguments<'_>) {
+        println!("{args}");
+    }
+}
+
+/// Конфигуратор: исполняет команды настройки, как в прошивке.
+pub async fn run(
+    storage: ConfigStorage,
+    connectors: &Connectors,
+    mut cmd_rx: mpsc::Receiver<(Tag, Command)>,
+) {
+    let mut configurator = Configurator::start(storage, connectors).await;
+    while let Some((tag, command)) = cmd_rx.recv().await {
+        let response = configurator.handle(command).await;
+        if connectors.cmd_ack_tx.send((tag, response)).await.is_err() {
+            break;
+        }
+    }
+}
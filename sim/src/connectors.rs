@@ -0,0 +1,271 @@
+//! Связи между задачами симулятора.
+//!
+//! Повторяют каналы и сигналы прошивки: сетевое API, конфигуратор и
+//! позиционер обмениваются теми же сообщениями и с той же емкостью очередей.
+//! Позиционер работает в отдельном потоке, как на своем ядре в прошивке,
+//! поэтому общие очереди построены на блокировках стандартной библиотеки.
+
+use common::{
+    api::{Device, QueueFull},
+    calibration::Calibration,
+    mechanics_config::{JointLimits, StartupMechanicsConfig},
+    motion::positioner::Snapshot,
+    quantities::Position,
+    request::{Command, MotionControl},
+    response::Response,
+    units::Radians,
+};
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Mutex, MutexGuard},
+    time::Instant,
+};
+use tokio::sync::mpsc;
+
+pub use common::{
+    api::Tag,
+    motion::executor::{Motion, Outcome},
+};
+
+pub const POS_QUEUE_LEN: usize = 16;
+
+// Пакет точек должен помещаться в пустую очередь.
+const _: () = assert!(common::request::MAX_BATCH_LEN <= POS_QUEUE_LEN);
+pub const CONTROL_QUEUE_LEN: usize = 4;
+
+/// Очередь позиционирования. Пополняется сетевым API, опустошается
+/// позиционером и сетевым API при очистке.
+pub struct PosQueue(Mutex<VecDeque<(Tag, Motion)>>);
+
+impl PosQueue {
+    pub fn new() -> Self {
+        Self(Mutex::new(VecDeque::with_capacity(POS_QUEUE_LEN)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<(Tag, Motion)>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Ставит перемещение в очередь, если в ней есть место.
+    pub fn try_send(&self, item: (Tag, Motion)) -> Result<(), QueueFull> {
+        let mut queue = self.lock();
+        if queue.len() >= POS_QUEUE_LEN {
+            return Err(QueueFull);
+        }
+        queue.push_back(item);
+        Ok(())
+    }
+
+    pub fn try_receive(&self) -> Option<(Tag, Motion)> {
+        self.lock().pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        POS_QUEUE_LEN
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+}
+
+/// Сигнал со значением: хранит последнее значение до того, как его заберут.
+pub struct Signal<T>(Mutex<Option<T>>);
+
+impl<T> Signal<T> {
+    pub fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    pub fn signal(&self, value: T) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(value);
+    }
+
+    pub fn try_take(&self) -> Option<T> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+// Сигнал обновления конфигурации механики от конфигуратора к позиционеру.
+pub type SignalMechanicsConfig = Signal<StartupMechanicsConfig>;
+
+// Сигнал обновления калибровки сервоприводов от конфигуратора к позиционеру.
+pub type SignalCalibration = Signal<Calibration>;
+
+/// Программные ограничения осей для проверки запросов сетевым API.
+pub struct SharedJointLimits(Mutex<JointLimits>);
+
+impl SharedJointLimits {
+    pub fn new() -> Self {
+        Self(Mutex::new(JointLimits::default()))
+    }
+
+    pub fn get(&self) -> JointLimits {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set(&self, limits: JointLimits) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+    }
+}
+
+/// Состояние движения, которое публикует позиционер.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionSnapshot {
+    /// Текущая позиция приводов.
+    pub position: Position,
+    /// Цель исполняемого перемещения.
+    pub target: Position,
+    /// Манипулятор движется.
+    pub moving: bool,
+    /// Движение приостановлено командой.
+    pub paused: bool,
+    /// Момент, к которому относится позиция.
+    pub timestamp: Instant,
+}
+
+/// Последний снимок состояния движения.
+pub struct SharedMotionSnapshot {
+    snapshot: Mutex<Option<MotionSnapshot>>,
+    /// Момент запуска: от него отсчитываются метки времени телеметрии.
+    pub start: Instant,
+}
+
+impl SharedMotionSnapshot {
+    pub fn new() -> Self {
+        Self {
+            snapshot: Mutex::new(None),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn publish(&self, snapshot: MotionSnapshot) {
+        *self.snapshot.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot);
+    }
+
+    /// Читает последний снимок. До первой публикации манипулятор покоится в
+    /// нулевой позиции.
+    pub fn read(&self) -> MotionSnapshot {
+        let snapshot = *self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        let zero = Position {
+            rotation: Radians::new(0.0),
+            shoulder: Radians::new(0.0),
+            forearm: Radians::new(0.0),
+            claw: Radians::new(0.0),
+        };
+        snapshot.unwrap_or(MotionSnapshot {
+            position: zero,
+            target: zero,
+            moving: false,
+            paused: false,
+            timestamp: self.start,
+        })
+    }
+}
+
+/// Связи сетевого API с позиционером и конфигуратором.
+pub struct Connectors {
+    pub pos: PosQueue,
+    pub pos_ack_tx: mpsc::Sender<(Tag, Outcome)>,
+    pub control_tx: mpsc::Sender<MotionControl>,
+    pub cmd_tx: mpsc::Sender<(Tag, Command)>,
+    pub cmd_ack_tx: mpsc::Sender<(Tag, Response)>,
+    pub mechanics_config: SignalMechanicsConfig,
+    pub calibration: SignalCalibration,
+    pub joint_limits: SharedJointLimits,
+    pub motion_snapshot: SharedMotionSnapshot,
+}
+
+/// Приемные концы каналов, которые забирают задачи симулятора.
+pub struct Receivers {
+    pub pos_ack_rx: mpsc::Receiver<(Tag, Outcome)>,
+    pub control_rx: mpsc::Receiver<MotionControl>,
+    pub cmd_rx: mpsc::Receiver<(Tag, Command)>,
+    pub cmd_ack_rx: mpsc::Receiver<(Tag, Response)>,
+}
+
+impl Connectors {
+    pub fn new() -> (Self, Receivers) {
+        let (pos_ack_tx, pos_ack_rx) = mpsc::channel(1);
+        let (control_tx, control_rx) = mpsc::channel(CONTROL_QUEUE_LEN);
+        let (cmd_tx, cmd_rx) = mpsc::channel(1);
+        let (cmd_ack_tx, cmd_ack_rx) = mpsc::channel(1);
+        let connectors = Self {
+            pos: PosQueue::new(),
+            pos_ack_tx,
+            control_tx,
+            cmd_tx,
+            cmd_ack_tx,
+            mechanics_config: Signal::new(),
+            calibration: Signal::new(),
+            joint_limits: SharedJointLimits::new(),
+            motion_snapshot: SharedMotionSnapshot::new(),
+        };
+        let receivers = Receivers {
+            pos_ack_rx,
+            control_rx,
+            cmd_rx,
+            cmd_ack_rx,
+        };
+        (connectors, receivers)
+    }
+}
+
+impl Device for Connectors {
+    const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    fn joint_limits(&self) -> JointLimits {
+        self.joint_limits.get()
+    }
+
+    fn try_enqueue(&self, tag: Tag, motion: Motion) -> Result<(), QueueFull> {
+        self.pos.try_send((tag, motion))
+    }
+
+    fn try_dequeue(&self) -> Option<Tag> {
+        self.pos.try_receive().map(|(tag, _)| tag)
+    }
+
+    fn queued(&self) -> usize {
+        self.pos.len()
+    }
+
+    fn queue_capacity(&self) -> usize {
+        self.pos.capacity()
+    }
+
+    fn try_command(&self, tag: Tag, command: Command) -> Result<(), QueueFull> {
+        self.cmd_tx.try_send((tag, command)).map_err(|_| QueueFull)
+    }
+
+    fn try_control(&self, action: MotionControl) -> Result<(), QueueFull> {
+        self.control_tx.try_send(action).map_err(|_| QueueFull)
+    }
+
+    fn motion(&self) -> (Snapshot, u64) {
+        let snapshot = self.motion_snapshot.read();
+        let timestamp = snapshot
+            .timestamp
+            .duration_since(self.motion_snapshot.start);
+        let motion = Snapshot {
+            position: snapshot.position,
+            target: snapshot.target,
+            moving: snapshot.moving,
+            paused: snapshot.paused,
+        };
+        (motion, timestamp.as_micros() as u64)
+    }
+
+    fn clear(&self) {
+        self.pos.clear();
+    }
+
+    fn log(&self, args: fmt::Arguments<'_>) {
+        println!("{args}");
+    }
+}
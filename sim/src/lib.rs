@@ -0,0 +1,59 @@
+//! Симулятор манипулятора для проверки клиентов без платы.
+//!
+//! Запускает ту же обработку протокола, что и прошивка: сетевое API,
+//! конфигуратор и позиционер с исполнителем перемещений из `common`. Вместо
+//! сервоприводов используется механика, записывающая углы по времени, вместо
+//! Wi-Fi — TCP-сервер на локальном интерфейсе, вместо Flash-памяти — файл
+//! конфигурации Wi-Fi.
+
+use configurator::ConfigStorage;
+use connectors::{Connectors, Receivers};
+use std::{io, io::Write, path::PathBuf, sync::Arc, thread};
+use tokio::net::TcpListener;
+use transport::Transport;
+
+mod configurator;
+mod connectors;
+mod mechanics;
+mod positioner;
+mod transport;
+
+/// Параметры симулятора.
+pub struct Options {
+    /// Файл конфигурации Wi-Fi.
+    pub wifi_config: PathBuf,
+    /// Приемник записи углов по времени в формате CSV.
+    pub trace: Option<Box<dyn Write + Send>>,
+}
+
+/// Запускает симулятор и обслуживает клиентов, подключающихся к `listener`.
+///
+/// Позиционер работает в отдельном потоке, как на втором ядре прошивки, и
+/// продолжает работу после завершения задачи.
+pub async fn run(listener: TcpListener, options: Options) -> io::Result<()> {
+    let Options { wifi_config, trace } = options;
+
+    let (connectors, receivers) = Connectors::new();
+    let connectors = Arc::new(connectors);
+    let Receivers {
+        pos_ack_rx,
+        control_rx,
+        cmd_rx,
+        cmd_ack_rx,
+    } = receivers;
+
+    let shared = connectors.clone();
+    thread::spawn(move || {
+        let joints = mechanics::make(shared.motion_snapshot.start, trace);
+        positioner::run(joints, &shared, control_rx)
+    });
+
+    let storage = ConfigStorage::new(wifi_config);
+    let shared = connectors.clone();
+    tokio::spawn(async move { configurator::run(storage, &shared, cmd_rx).await });
+
+    println!("TRANSPORT: listening on {}", listener.local_addr()?);
+    Transport::new(&connectors, pos_ack_rx, cmd_ack_rx)
+        .run(&listener)
+        .await
+}
@@ -0,0 +1,70 @@
+//! Запуск симулятора манипулятора.
+
+use std::{
+    env,
+    fs::File,
+    io::{self, BufWriter, Write},
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+};
+use tokio::net::TcpListener;
+
+/// Порт TCP-сервера управления.
+const PORT: u16 = 8080;
+
+/// Файл конфигурации Wi-Fi по умолчанию.
+const DEFAULT_WIFI_CONFIG_PATH: &str = "sim-wifi.bin";
+
+/// Параметры запуска.
+struct Args {
+    port: u16,
+    wifi_config: PathBuf,
+    trace: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Option<Self> {
+        let mut args = Self {
+            port: PORT,
+            wifi_config: DEFAULT_WIFI_CONFIG_PATH.into(),
+            trace: None,
+        };
+        let mut iter = env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            let value = iter.next()?;
+            match arg.as_str() {
+                "--port" => args.port = value.parse().ok()?,
+                "--wifi-config" => args.wifi_config = value.into(),
+                "--trace" => args.trace = Some(value.into()),
+                _ => return None,
+            }
+        }
+        Some(args)
+    }
+}
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    let Some(args) = Args::parse() else {
+        let name = env::args().next().unwrap_or_default();
+        eprintln!(
+            "Использование: {name} [--port <порт>] [--wifi-config <файл>] [--trace <файл.csv>]"
+        );
+        std::process::exit(1);
+    };
+
+    let trace = match &args.trace {
+        Some(path) => {
+            let file: Box<dyn Write + Send> = Box::new(BufWriter::new(File::create(path)?));
+            Some(file)
+        }
+        None => None,
+    };
+
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, args.port))).await?;
+    let options = sim::Options {
+        wifi_config: args.wifi_config,
+        trace,
+    };
+    sim::run(listener, options).await
+}
@@ -0,0 +1,138 @@
+//! Симулированная механика манипулятора.
+
+use common::{
+    actuator::{Actuator, Joints},
+    calibration::ServoCalibration,
+    quantities::Axis,
+    units::Radians,
+};
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+    time::Instant,
+};
+
+/// Создает приводы всех осей. Если `trace` задан, в него записываются углы и
+/// ширина импульсов включенных приводов по времени.
+pub fn make(start: Instant, trace: Option<Box<dyn Write + Send>>) -> Joints<SimJoint> {
+    let trace = Rc::new(RefCell::new(Trace::new(start, trace)));
+    let joint = |axis| SimJoint {
+        axis,
+        calibration: ServoCalibration::default(),
+        angle: None,
+        enabled: false,
+        trace: trace.clone(),
+    };
+    Joints {
+        rotation: joint(Axis::Rotation),
+        shoulder: joint(Axis::Shoulder),
+        forearm: joint(Axis::Forearm),
+        claw: joint(Axis::Claw),
+    }
+}
+
+/// Привод одной оси без сервопривода: пересчитывает угол в ширину импульса
+/// по калибровке и передает их в общую запись.
+pub struct SimJoint {
+    axis: Axis,
+    calibration: ServoCalibration,
+    angle: Option<Radians>,
+    enabled: bool,
+    trace: Rc<RefCell<Trace>>,
+}
+
+impl SimJoint {
+    fn output(&self) {
+        if self.enabled
+            && let Some(angle) = self.angle
+        {
+            let pulse_us = self.calibration.pulse_width_us(angle);
+            self.trace.borrow_mut().set(self.axis, angle, pulse_us);
+        }
+    }
+}
+
+impl Actuator for SimJoint {
+    fn set_angle(&mut self, angle: Radians) {
+        self.angle = Some(angle);
+        self.output();
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        self.output();
+    }
+
+    fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    fn calibrate(&mut self, calibration: ServoCalibration) {
+        self.calibration = calibration;
+    }
+}
+
+/// Угол и ширина импульса одной оси.
+type Output = (Radians, f32);
+
+/// Запись положения механики в формате CSV: время в секундах от запуска,
+/// четыре угла в радианах и четыре ширины импульса в микросекундах.
+///
+/// Строка записывается, когда заданы выходы всех осей. Повторение той же
+/// строки не записывается.
+struct Trace {
+    start: Instant,
+    row: [Option<Output>; Axis::ALL.len()],
+    last: Option<[Output; Axis::ALL.len()]>,
+    writer: Option<Box<dyn Write + Send>>,
+}
+
+impl Trace {
+    fn new(start: Instant, writer: Option<Box<dyn Write + Send>>) -> Self {
+        let mut trace = Self {
+            start,
+            row: [None; Axis::ALL.len()],
+            last: None,
+            writer,
+        };
+        let header = "time_s,rotation,shoulder,forearm,claw,\
+                      rotation_us,shoulder_us,forearm_us,claw_us";
+        trace.record(|writer| writeln!(writer, "{header}"));
+        trace
+    }
+
+    fn set(&mut self, axis: Axis, angle: Radians, pulse_us: f32) {
+        self.row[axis as usize] = Some((angle, pulse_us));
+        if self.row.iter().any(Option::is_none) {
+            return;
+        }
+        let row = self.row.map(Option::unwrap);
+        self.row = [None; Axis::ALL.len()];
+        if self.last == Some(row) {
+            return;
+        }
+        self.last = Some(row);
+        let time = self.start.elapsed().as_secs_f32();
+        self.record(|writer| {
+            write!(writer, "{time:.3}")?;
+            for (angle, _) in row {
+                write!(writer, ",{:.4}", f32::from(angle))?;
+            }
+            for (_, pulse_us) in row {
+                write!(writer, ",{pulse_us:.1}")?;
+            }
+            writeln!(writer)
+        });
+    }
+
+    /// Дописывает запись. Запись отключается при первой ошибке.
+    fn record(&mut self, write: impl FnOnce(&mut dyn Write) -> io::Result<()>) {
+        if let Some(writer) = self.writer.as_mut()
+            && let Err(err) = write(writer).and_then(|()| writer.flush())
+        {
+            println!("MECHANICS ERROR: failed to write trace: {err}");
+            self.writer = None;
+        }
+    }
+}
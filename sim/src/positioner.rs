@@ -0,0 +1,103 @@
+use crate::{
+    connectors::{Connectors, Motion, MotionSnapshot, Outcome, Tag},
+    mechanics::SimJoint,
+};
+use common::{
+    actuator::Joints,
+    calibration::Calibration,
+    mechanics_config::StartupMechanicsConfig,
+    motion::positioner::{Clock, Links, Positioner, Snapshot},
+    request::MotionControl,
+    units::Seconds,
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::sync::mpsc;
+
+/// Интервал обновления позиции, как у прошивки с ШИМ 50 Гц.
+const POSITIONING_INTERVAL: Seconds = Seconds::new(1.0 / 50.0);
+
+/// Интервал опроса очередей в покое.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Задача управления траекторией движения манипулятора.
+///
+/// Исполняет тот же цикл позиционирования, что и прошивка, с приводами
+/// `joints`. Выполняется в отдельном потоке, как на втором ядре прошивки.
+pub fn run(
+    joints: Joints<SimJoint>,
+    connectors: &Connectors,
+    control_rx: mpsc::Receiver<MotionControl>,
+) -> ! {
+    let mut links = SimLinks {
+        connectors,
+        control_rx,
+    };
+    Positioner::start(joints, SystemClock, &mut links).run(&mut links, POSITIONING_INTERVAL)
+}
+
+/// Часы стандартной библиотеки: ожидание шага усыпляет поток.
+struct SystemClock;
+
+impl Clock for SystemClock {
+    type Instant = Instant;
+
+    fn now(&mut self) -> Instant {
+        Instant::now()
+    }
+
+    fn after(&self, instant: Instant, interval: Seconds) -> Instant {
+        instant + Duration::from_secs_f32(interval.into())
+    }
+
+    fn wait_until(&mut self, instant: Instant) {
+        thread::sleep(instant.saturating_duration_since(Instant::now()));
+    }
+
+    fn idle(&mut self) {
+        thread::sleep(IDLE_POLL_INTERVAL);
+    }
+}
+
+/// Связи позиционера с сетевым API и конфигуратором.
+struct SimLinks<'a> {
+    connectors: &'a Connectors,
+    control_rx: mpsc::Receiver<MotionControl>,
+}
+
+impl Links for SimLinks<'_> {
+    type Tag = Tag;
+    type Instant = Instant;
+
+    fn try_motion(&mut self) -> Option<(Tag, Motion)> {
+        self.connectors.pos.try_receive()
+    }
+
+    fn try_control(&mut self) -> Option<MotionControl> {
+        self.control_rx.try_recv().ok()
+    }
+
+    fn try_config(&mut self) -> Option<StartupMechanicsConfig> {
+        self.connectors.mechanics_config.try_take()
+    }
+
+    fn try_calibration(&mut self) -> Option<Calibration> {
+        self.connectors.calibration.try_take()
+    }
+
+    fn try_ack(&mut self, ack: (Tag, Outcome)) -> bool {
+        self.connectors.pos_ack_tx.try_send(ack).is_ok()
+    }
+
+    fn publish(&mut self, snapshot: Snapshot, timestamp: Instant) {
+        self.connectors.motion_snapshot.publish(MotionSnapshot {
+            position: snapshot.position,
+            target: snapshot.target,
+            moving: snapshot.moving,
+            paused: snapshot.paused,
+            timestamp,
+        });
+    }
+}
@@ -0,0 +1,190 @@
+//! Транспорт симулятора: TCP-сервер на сокетах Tokio.
+//!
+//! Сессии ведет общее сетевое API из `common`; здесь только ввод-вывод и
+//! очереди ответов.
+
+use crate::connectors::{Connectors, Outcome, Tag};
+use common::{
+    api::{Clock, End, Outbox, Outgoing, Read, Replies, Sessions, Write},
+    response::Response,
+    session::Tagged,
+};
+use std::{io, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+    select,
+    sync::{mpsc, watch},
+    time::{self, Instant, Interval, MissedTickBehavior},
+};
+
+const REPLY_QUEUE_LEN: usize = 4;
+
+/// Транспорт: принимает клиентов и ведет сессии.
+///
+/// Одновременно обслуживается один клиент; новое подключение вытесняет
+/// текущее, как в прошивке.
+pub struct Transport<'a> {
+    connectors: &'a Connectors,
+    clock: SimClock,
+    sessions: Sessions,
+    replies: SimReplies,
+    outbox: SimOutbox,
+}
+
+impl<'a> Transport<'a> {
+    pub fn new(
+        connectors: &'a Connectors,
+        pos_ack_rx: mpsc::Receiver<(Tag, Outcome)>,
+        cmd_ack_rx: mpsc::Receiver<(Tag, Response)>,
+    ) -> Self {
+        // Ответы, которые формирует сам API (отказы), очищаются при смене сессии.
+        let (replies_tx, replies_rx) = mpsc::channel(REPLY_QUEUE_LEN);
+        let (telemetry_tx, telemetry_rx) = watch::channel(None);
+        Self {
+            connectors,
+            clock: SimClock(connectors.motion_snapshot.start.into()),
+            sessions: Sessions::new(),
+            replies: SimReplies {
+                replies: replies_tx,
+                telemetry: telemetry_tx,
+            },
+            outbox: SimOutbox {
+                pos_ack: pos_ack_rx,
+                cmd_ack: cmd_ack_rx,
+                replies: replies_rx,
+                telemetry: telemetry_rx,
+                ticker: None,
+            },
+        }
+    }
+
+    pub async fn run(&mut self, listener: &TcpListener) -> io::Result<()> {
+        let mut active = listener.accept().await?;
+        loop {
+            println!("TRANSPORT: client connected: {}", active.1);
+            let _ = active.0.set_nodelay(true);
+            let (reader, writer) = active.0.into_split();
+            // Ожидаем либо завершения работы с текущим клиентом, либо нового подключения.
+            select! {
+                accepted = listener.accept() => {
+                    self.sessions.end(End::Displaced, self.connectors, &mut self.outbox);
+                    active = accepted?;
+                    continue;
+                }
+                end = self.sessions.run(
+                    self.connectors,
+                    &self.clock,
+                    Socket(reader),
+                    Socket(writer),
+                    &self.replies,
+                    &mut self.outbox,
+                ) => {
+                    self.sessions.end(end, self.connectors, &mut self.outbox);
+                }
+            }
+            active = listener.accept().await?;
+        }
+    }
+}
+
+/// Часы Tokio, отсчитывающие время от запуска симулятора.
+struct SimClock(Instant);
+
+impl Clock for SimClock {
+    fn now_ms(&self) -> u64 {
+        self.0.elapsed().as_millis() as u64
+    }
+
+    async fn sleep_ms(&self, ms: u64) {
+        time::sleep(Duration::from_millis(ms)).await
+    }
+}
+
+/// Половина TCP-соединения.
+struct Socket<T>(T);
+
+impl<T: AsyncRead + Unpin> Read for Socket<T> {
+    type Error = io::Error;
+
+    async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf).await
+    }
+}
+
+impl<T: AsyncWrite + Unpin> Write for Socket<T> {
+    type Error = io::Error;
+
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.0.write_all(frame).await?;
+        self.0.flush().await
+    }
+}
+
+/// Передающие концы очередей ответов.
+struct SimReplies {
+    replies: mpsc::Sender<Tagged<Response>>,
+    telemetry: watch::Sender<Option<u8>>,
+}
+
+impl Replies for SimReplies {
+    async fn reply(&self, reply: Tagged<Response>) {
+        let _ = self.replies.send(reply).await;
+    }
+
+    fn subscribe(&self, rate_hz: Option<u8>) {
+        self.telemetry.send_replace(rate_hz);
+    }
+}
+
+/// Приемные концы очередей ответов и таймер телеметрии.
+struct SimOutbox {
+    pos_ack: mpsc::Receiver<(Tag, Outcome)>,
+    cmd_ack: mpsc::Receiver<(Tag, Response)>,
+    replies: mpsc::Receiver<Tagged<Response>>,
+    telemetry: watch::Receiver<Option<u8>>,
+    ticker: Option<Interval>,
+}
+
+impl Outbox for SimOutbox {
+    async fn next(&mut self) -> Option<Outgoing> {
+        let Self {
+            pos_ack,
+            cmd_ack,
+            replies,
+            telemetry,
+            ticker,
+        } = self;
+        loop {
+            let tick = async {
+                match ticker.as_mut() {
+                    Some(ticker) => ticker.tick().await,
+                    None => std::future::pending().await,
+                }
+            };
+            select! {
+                Some((tag, response)) = cmd_ack.recv() => return Some(Outgoing::Command(tag, response)),
+                Some((tag, outcome)) = pos_ack.recv() => return Some(Outgoing::Motion(tag, outcome)),
+                Some(reply) = replies.recv() => return Some(Outgoing::Reply(reply)),
+                Ok(()) = telemetry.changed() => {
+                    let rate = *telemetry.borrow_and_update();
+                    *ticker = rate.map(|hz| {
+                        let mut ticker = time::interval(Duration::from_secs(1) / hz as u32);
+                        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                        ticker
+                    });
+                }
+                _ = tick => return Some(Outgoing::Telemetry),
+                else => return None,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        while self.pos_ack.try_recv().is_ok() {}
+        while self.cmd_ack.try_recv().is_ok() {}
+        while self.replies.try_recv().is_ok() {}
+        self.telemetry.borrow_and_update();
+        self.ticker = None;
+    }
+}
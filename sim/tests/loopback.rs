@@ -0,0 +1,216 @@
+//! Проверка полного пути запрос → ответ: клиент `sdk::Robot` против
+//! симулятора на локальном интерфейсе.
+
+use common::{
+    quantities::Position,
+    request::{MotionControl, Request},
+    response::{MotionState, Response, Status},
+    units::Radians,
+};
+use sdk::{Error, Robot};
+use std::{
+    env,
+    future::Future,
+    net::{SocketAddr, TcpListener},
+    process, thread,
+    time::Duration,
+};
+use tokio::{join, runtime, time};
+
+/// Наибольшее время ожидания любого события в тестах.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Запускает симулятор в отдельном потоке и возвращает его адрес.
+fn start() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    listener.set_nonblocking(true).unwrap();
+    // Файл не создается: тесты не меняют конфигурацию Wi-Fi.
+    let wifi_config = env::temp_dir().join(format!(
+        "sim-loopback-{}-{}.bin",
+        process::id(),
+        addr.port()
+    ));
+    thread::spawn(move || {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(async {
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                let options = sim::Options {
+                    wifi_config,
+                    trace: None,
+                };
+                sim::run(listener, options).await
+            })
+            .unwrap();
+    });
+    addr
+}
+
+async fn timeout<F: Future>(future: F) -> F::Output {
+    time::timeout(TIMEOUT, future)
+        .await
+        .expect("событие не наступило")
+}
+
+fn position(rotation: f32, shoulder: f32, forearm: f32, claw: f32) -> Position {
+    Position {
+        rotation: Radians::new(rotation),
+        shoulder: Radians::new(shoulder),
+        forearm: Radians::new(forearm),
+        claw: Radians::new(claw),
+    }
+}
+
+/// Цель, до которой манипулятор идет дольше секунды из начальной позиции.
+fn far() -> Position {
+    position(0.2, 2.8, 2.8, 0.3)
+}
+
+async fn status(robot: &Robot) -> Status {
+    match timeout(robot.request(Request::GetStatus)).await.unwrap() {
+        Response::Status(status) => status,
+        response => panic!("ожидалось состояние: {response:?}"),
+    }
+}
+
+/// Ждет, пока соединение не будет закрыто сервером.
+async fn closed(robot: &Robot) {
+    let mut responses = robot.responses();
+    timeout(async { while !matches!(responses.recv().await, Err(Error::Disconnected)) {} }).await
+}
+
+/// Ждет остановки манипулятора.
+async fn idle(robot: &Robot) -> Status {
+    timeout(async {
+        loop {
+            let status = status(robot).await;
+            if status.state == MotionState::Idle {
+                return status;
+            }
+            time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+}
+
+fn assert_near(actual: Position, expected: Position) {
+    let near = |a: Radians, b: Radians| (f32::from(a) - f32::from(b)).abs() < 1e-3;
+    assert!(
+        near(actual.rotation, expected.rotation)
+            && near(actual.shoulder, expected.shoulder)
+            && near(actual.forearm, expected.forearm)
+            && near(actual.claw, expected.claw),
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_handshake() {
+    let robot = timeout(Robot::connect(start())).await.unwrap();
+    let welcome = robot.welcome();
+    assert_eq!(welcome.session, 0);
+    assert_eq!(welcome.axes, 4);
+    assert!(!welcome.watchdog_tripped);
+    assert!(welcome.free_slots > 0);
+    assert_eq!(robot.free_slots(), welcome.free_slots);
+
+    let status = status(&robot).await;
+    assert_eq!(status.state, MotionState::Idle);
+    assert_eq!(status.queued, 0);
+    assert_eq!(status.queue_capacity, welcome.free_slots);
+}
+
+#[tokio::test]
+async fn test_move_to_passes() {
+    let robot = timeout(Robot::connect(start())).await.unwrap();
+    let target = position(1.2, 1.5, 0.9, 2.0);
+
+    timeout(robot.move_to(target)).await.unwrap();
+
+    let status = idle(&robot).await;
+    assert_near(status.position, target);
+    assert_eq!(robot.free_slots(), robot.welcome().free_slots);
+}
+
+#[tokio::test]
+async fn test_stop_cancels_motions() {
+    let robot = timeout(Robot::connect(start())).await.unwrap();
+    let mut responses = robot.responses();
+
+    let stop = async {
+        time::sleep(Duration::from_millis(100)).await;
+        robot.request(Request::Control(MotionControl::Stop)).await
+    };
+    let (first, second, stop) = timeout(async {
+        join!(
+            robot.move_to(far()),
+            robot.move_to(position(1.57, 1.3, 0.7, 2.5)),
+            stop
+        )
+    })
+    .await;
+
+    assert!(matches!(first, Err(Error::Cancelled)), "{first:?}");
+    assert!(matches!(second, Err(Error::Cancelled)), "{second:?}");
+    assert_eq!(stop.unwrap(), Response::CommandAck);
+    // Каждое перемещение подтверждено отменой.
+    timeout(async {
+        let mut cancelled = 0;
+        while cancelled < 2 {
+            if responses.recv().await.unwrap().message == Response::MotionCancelled {
+                cancelled += 1;
+            }
+        }
+    })
+    .await;
+    let status = idle(&robot).await;
+    assert_ne!(status.position, far());
+    assert_eq!(robot.free_slots(), robot.welcome().free_slots);
+}
+
+#[tokio::test]
+async fn test_watchdog_trips_on_silence() {
+    let addr = start();
+    let robot = timeout(Robot::connect(addr)).await.unwrap();
+    let response = timeout(robot.request(Request::SetWatchdog { timeout_ms: 200 })).await;
+    assert_eq!(response.unwrap(), Response::CommandAck);
+
+    // Клиент ждет итога перемещения и ничего не отправляет.
+    let (moved, ()) = join!(robot.move_to(far()), closed(&robot));
+    assert!(matches!(moved, Err(Error::Disconnected)), "{moved:?}");
+
+    let robot = timeout(Robot::connect(addr)).await.unwrap();
+    assert_eq!(robot.welcome().session, 1);
+    assert!(robot.welcome().watchdog_tripped);
+    // Движение остановлено, не дойдя до цели.
+    let status = idle(&robot).await;
+    assert_ne!(status.position, far());
+
+    // О срабатывании сообщается только один раз.
+    drop(robot);
+    let robot = timeout(Robot::connect(addr)).await.unwrap();
+    assert!(!robot.welcome().watchdog_tripped);
+}
+
+#[tokio::test]
+async fn test_new_client_displaces_previous() {
+    let addr = start();
+    let first = timeout(Robot::connect(addr)).await.unwrap();
+    let second = timeout(Robot::connect(addr)).await.unwrap();
+
+    closed(&first).await;
+    assert!(matches!(
+        first.send(Request::GetStatus),
+        Err(Error::Disconnected)
+    ));
+    assert_eq!(second.welcome().session, first.welcome().session + 1);
+    assert!(!second.welcome().watchdog_tripped);
+
+    let target = position(1.4, 1.2, 0.8, 2.4);
+    timeout(second.move_to(target)).await.unwrap();
+    assert_near(idle(&second).await.position, target);
+}